@@ -75,5 +75,10 @@ pub fn bench_index_atoms(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_index, bench_all_raw_chunks, bench_index_atoms);
+criterion_group!(
+    benches,
+    bench_index,
+    bench_all_raw_chunks,
+    bench_index_atoms
+);
 criterion_main!(benches);
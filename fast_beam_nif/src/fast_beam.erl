@@ -0,0 +1,19 @@
+-module(fast_beam).
+
+-export([module_name/1, chunks/1, exports/1, imports/1, scan_dir/1]).
+
+-on_load(init/0).
+
+init() ->
+    Path = filename:join(code:priv_dir(fast_beam), "libfast_beam_nif"),
+    erlang:load_nif(Path, 0).
+
+module_name(_Beam) -> erlang:nif_error(not_loaded).
+
+chunks(_Beam) -> erlang:nif_error(not_loaded).
+
+exports(_Beam) -> erlang:nif_error(not_loaded).
+
+imports(_Beam) -> erlang:nif_error(not_loaded).
+
+scan_dir(_Dir) -> erlang:nif_error(not_loaded).
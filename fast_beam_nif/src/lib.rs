@@ -0,0 +1,114 @@
+//! NIFs for the `fast_beam` Erlang module, see `fast_beam.erl`
+//!
+//! Functions taking a binary work on the contents of a BEAM file, for example
+//! from `code:get_object_code/1`. They return `{ok, Result}` or
+//! `{error, Reason}` with the reason as a binary.
+
+use std::{
+    fs,
+    io::{Cursor, Read, Seek},
+};
+
+use fast_beam::{BeamFile, BeamSlice, ExpTChunk, ImpTChunk, NaiveInterner};
+use rustler::{Atom, Binary, Env, OwnedBinary};
+
+type NifResult<T> = Result<T, String>;
+
+/// `{Function, Arity}` of every export
+type Exports = Vec<(Atom, u32)>;
+
+fn open(data: &[u8]) -> NifResult<BeamFile<Cursor<&[u8]>>> {
+    let mut file = BeamFile::from_slice(data).map_err(|err| err.to_string())?;
+    file.index_atoms(NaiveInterner)
+        .map_err(|err| err.to_string())?;
+    Ok(file)
+}
+
+fn atom(env: Env<'_>, name: &str) -> NifResult<Atom> {
+    Atom::from_str(env, name).map_err(|_| format!("invalid atom {:?}", name))
+}
+
+fn exports_of<R: Read + Seek>(env: Env<'_>, file: &mut BeamFile<R>) -> NifResult<Exports> {
+    let chunk: ExpTChunk<String> = file.read().map_err(|err| err.to_string())?;
+    chunk
+        .exports
+        .iter()
+        .map(|export| Ok((atom(env, &export.function)?, export.arity)))
+        .collect()
+}
+
+/// The module name
+#[rustler::nif]
+fn module_name(env: Env<'_>, data: Binary<'_>) -> NifResult<Atom> {
+    let file = open(data.as_slice())?;
+    atom(env, file.name().map_or("", String::as_str))
+}
+
+/// Chunk ids and sizes in the order they appear in the file
+#[rustler::nif]
+fn chunks<'a>(env: Env<'a>, data: Binary<'a>) -> NifResult<Vec<(Binary<'a>, usize)>> {
+    let slice = BeamSlice::new(data.as_slice()).map_err(|err| err.to_string())?;
+    slice
+        .chunks()
+        .map(|(id, chunk)| {
+            let id: [u8; 4] = id.into();
+            let mut binary = OwnedBinary::new(4).ok_or("out of memory")?;
+            binary.as_mut_slice().copy_from_slice(&id);
+            Ok((binary.release(env), chunk.len()))
+        })
+        .collect()
+}
+
+/// Exported functions as `{Function, Arity}`
+#[rustler::nif]
+fn exports(env: Env<'_>, data: Binary<'_>) -> NifResult<Exports> {
+    exports_of(env, &mut open(data.as_slice())?)
+}
+
+/// Imported functions as `{Module, Function, Arity}`
+#[rustler::nif]
+fn imports(env: Env<'_>, data: Binary<'_>) -> NifResult<Vec<(Atom, Atom, u32)>> {
+    let chunk: ImpTChunk<String> = open(data.as_slice())?
+        .read()
+        .map_err(|err| err.to_string())?;
+    chunk
+        .imports
+        .iter()
+        .map(|import| {
+            Ok((
+                atom(env, &import.module)?,
+                atom(env, &import.function)?,
+                import.arity,
+            ))
+        })
+        .collect()
+}
+
+/// Exports of all `.beam` files in a directory, as `{Module, Exports}`
+///
+/// Runs on a dirty IO scheduler, since it reads the whole directory.
+#[rustler::nif(schedule = "DirtyIo")]
+fn scan_dir(env: Env<'_>, dir: String) -> NifResult<Vec<(Atom, Exports)>> {
+    let mut paths = fs::read_dir(&dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()
+        })
+        .map_err(|err| err.to_string())?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "beam"));
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let mut file = BeamFile::from_file(path)
+                .and_then(|mut file| file.index_atoms(NaiveInterner).map(|_| file))
+                .map_err(|err| err.to_string())?;
+            let name = atom(env, file.name().map_or("", String::as_str))?;
+            Ok((name, exports_of(env, &mut file)?))
+        })
+        .collect()
+}
+
+rustler::init!("fast_beam");
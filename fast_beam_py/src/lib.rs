@@ -0,0 +1,117 @@
+//! Python bindings, build with `maturin build` in this directory
+
+use std::{fs, io::Cursor};
+
+use fast_beam::{BeamFileError, Doc, ExpTChunk, Id, ImpTChunk, NaiveInterner};
+use pyo3::{create_exception, exceptions::PyException, prelude::*, types::PyBytes};
+
+create_exception!(fast_beam_py, BeamError, PyException);
+
+fn to_py(err: BeamFileError) -> PyErr {
+    BeamError::new_err(err.to_string())
+}
+
+fn chunk_id(id: &str) -> PyResult<Id> {
+    id.parse()
+        .map_err(|_| BeamError::new_err(format!("invalid chunk id {:?}", id)))
+}
+
+/// `(kind, name, arity, doc)` of a documentation entry
+type DocTuple = (String, String, u32, Option<String>);
+
+/// A BEAM file, loaded into memory
+#[pyclass(name = "BeamFile")]
+struct PyBeamFile {
+    file: fast_beam::BeamFile<Cursor<Vec<u8>>>,
+}
+
+#[pymethods]
+impl PyBeamFile {
+    #[new]
+    fn new(data: Vec<u8>) -> PyResult<Self> {
+        let mut file = fast_beam::BeamFile::from_bytes(data).map_err(to_py)?;
+        file.index_atoms(NaiveInterner).map_err(to_py)?;
+        Ok(PyBeamFile { file })
+    }
+
+    #[staticmethod]
+    fn open(path: &str) -> PyResult<Self> {
+        let data = fs::read(path).map_err(|err| to_py(err.into()))?;
+        Self::new(data)
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.file.name().cloned()
+    }
+
+    fn atoms(&self) -> Vec<String> {
+        self.file.atom_index().unwrap_or_default().to_vec()
+    }
+
+    /// Chunks in the order they appear in the file, as `(id, offset, size)`
+    fn chunks(&self) -> Vec<(String, u64, u64)> {
+        self.file
+            .chunks()
+            .map(|(id, offset, size)| (id.to_string(), offset, size))
+            .collect()
+    }
+
+    /// Raw contents of the chunk `id`, like `"Code"`
+    fn read_chunk<'py>(&mut self, py: Python<'py>, id: &str) -> PyResult<Bound<'py, PyBytes>> {
+        let data = self.file.read_raw(chunk_id(id)?).map_err(to_py)?;
+        Ok(PyBytes::new(py, &data))
+    }
+
+    /// Exports as `(function, arity, label)`
+    fn exports(&mut self) -> PyResult<Vec<(String, u32, u32)>> {
+        let chunk: ExpTChunk<String> = self.file.read().map_err(to_py)?;
+        Ok(chunk
+            .exports
+            .into_iter()
+            .map(|export| (export.function, export.arity, export.label))
+            .collect())
+    }
+
+    /// Imports as `(module, function, arity)`
+    fn imports(&mut self) -> PyResult<Vec<(String, String, u32)>> {
+        let chunk: ImpTChunk<String> = self.file.read().map_err(to_py)?;
+        Ok(chunk
+            .imports
+            .into_iter()
+            .map(|import| (import.module, import.function, import.arity))
+            .collect())
+    }
+
+    /// Documentation as `(kind, name, arity, doc)`, with the module doc first
+    ///
+    /// The doc is the text in `language`, `None` if undocumented or hidden.
+    #[pyo3(signature = (language = "en"))]
+    fn docs(&mut self, language: &str) -> PyResult<Vec<DocTuple>> {
+        let chunk = self.file.docs().map_err(to_py)?;
+        let text = |doc: &Doc| doc.text(language);
+        let module = (
+            "module".to_string(),
+            self.name().unwrap_or_default(),
+            0,
+            text(&chunk.module_doc),
+        );
+        Ok(std::iter::once(module)
+            .chain(chunk.entries.iter().map(|entry| {
+                (
+                    entry.kind.clone(),
+                    entry.name.clone(),
+                    entry.arity,
+                    text(&entry.doc),
+                )
+            }))
+            .collect())
+    }
+}
+
+#[pymodule]
+fn fast_beam_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyBeamFile>()?;
+    m.add("BeamError", m.py().get_type::<BeamError>())?;
+    Ok(())
+}
@@ -0,0 +1,353 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Write},
+    io::{Read, Seek},
+};
+
+use crate::{term::AtomName, BeamFile, BeamFileError, Id, InternerMut, Result, Term};
+
+/// Abstract code of a module, the forms produced by the Erlang parser
+///
+/// Forms are kept as terms, in the representation documented for
+/// `erl_parse`, for example `{attribute, Anno, module, Name}`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AbstractCode {
+    pub forms: Vec<Term>,
+}
+
+impl AbstractCode {
+    /// Values of the attributes `-name(Value).`, in the order of the source
+    pub fn attributes<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Term> + 'a {
+        self.forms
+            .iter()
+            .filter_map(move |form| match tagged(form) {
+                Some(("attribute", [_, Term::Atom(attribute), value])) if attribute == name => {
+                    Some(value)
+                }
+                _ => None,
+            })
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Decodes the abstract code from the `Dbgi` chunk, or the legacy `Abst` chunk
+    ///
+    /// Returns `None` if the module was compiled without debug info, had it
+    /// stripped, or was compiled by a backend other than Erlang's, such as Elixir's.
+    /// The decompressed size is bounded by `Limits::max_decompressed_size`.
+    pub fn abstract_code(&mut self) -> Result<Option<AbstractCode>> {
+        let id = match (self.has_chunk(Id::DBGI), self.has_chunk(Id::ABST)) {
+            (true, _) => Id::DBGI,
+            (false, true) => Id::ABST,
+            (false, false) => return Ok(None),
+        };
+        let data = self.read_raw(id)?;
+        let position = self.index[&id].position;
+        let result = match data.is_empty() {
+            true => Ok(None),
+            false => Term::decode_limited(&data[..], self.limits.max_decompressed_size)
+                .and_then(|term| forms(id, term)),
+        };
+        self.finish_decode(result, id, position)
+    }
+}
+
+/// Extracts the forms from the term stored in `Dbgi` or `Abst`
+fn forms(id: Id, term: Term) -> Result<Option<AbstractCode>> {
+    let malformed = || BeamFileError::MalformedChunk {
+        chunk: id,
+        reason: "unexpected debug info",
+    };
+    let forms = match term {
+        Term::Tuple(elements) => match <[Term; 3]>::try_from(elements) {
+            Ok([Term::Atom(version), Term::Atom(backend), Term::Tuple(data)])
+                if version == "debug_info_v1" && backend == "erl_abstract_code" =>
+            {
+                data.into_iter().next().ok_or_else(malformed)?
+            }
+            Ok([Term::Atom(version), _, _]) if version == "debug_info_v1" => return Ok(None),
+            Ok(_) => return Err(malformed()),
+            Err(elements) => match <[Term; 2]>::try_from(elements) {
+                Ok([Term::Atom(version), forms]) if version == "raw_abstract_v1" => forms,
+                _ => return Err(malformed()),
+            },
+        },
+        _ => return Err(malformed()),
+    };
+
+    match forms {
+        Term::Atom(none) if none == "none" => Ok(None),
+        forms => match forms.as_list() {
+            Some(forms) => Ok(Some(AbstractCode {
+                forms: forms.to_vec(),
+            })),
+            None => Err(malformed()),
+        },
+    }
+}
+
+/// Splits a node of abstract code into its tag and the remaining elements
+///
+/// The first of the elements is the annotation for most nodes.
+pub(crate) fn tagged(term: &Term) -> Option<(&str, &[Term])> {
+    match term {
+        Term::Tuple(elements) => match elements.split_first() {
+            Some((Term::Atom(tag), rest)) => Some((tag, rest)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Splits the value of a `-spec` or `-callback` attribute into the function and its clauses
+pub(crate) fn spec_clauses(value: &Term) -> Option<((String, u32), Vec<Term>)> {
+    let (function, clauses) = match value {
+        Term::Tuple(elements) => match &elements[..] {
+            [function, clauses] => (function, clauses),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let (name, arity) = match function {
+        Term::Tuple(elements) => match &elements[..] {
+            [Term::Atom(name), Term::Integer(arity)]
+            | [_, Term::Atom(name), Term::Integer(arity)]
+                if (0..=255).contains(arity) =>
+            {
+                (name, *arity as u32)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let clauses = clauses
+        .as_list()?
+        .iter()
+        .filter(|clause| tagged(clause).is_some())
+        .cloned()
+        .collect();
+    Some(((name.clone(), arity), clauses))
+}
+
+/// Displays a type of abstract code in Erlang syntax
+///
+/// Local user types are qualified with `module`, if given, so the type can
+/// be used outside of the module defining it.
+pub(crate) struct Type<'a> {
+    pub(crate) ty: &'a Term,
+    pub(crate) module: Option<&'a str>,
+}
+
+impl<'a> Type<'a> {
+    fn nested(&self, ty: &'a Term) -> Type<'a> {
+        Type {
+            ty,
+            module: self.module,
+        }
+    }
+
+    fn write_list(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        types: &[Term],
+        separator: &str,
+    ) -> fmt::Result {
+        for (i, ty) in types.iter().enumerate() {
+            if i > 0 {
+                f.write_str(separator)?;
+            }
+            write!(f, "{}", self.nested(ty))?;
+        }
+        Ok(())
+    }
+
+    fn write_call(&self, f: &mut fmt::Formatter<'_>, name: &str, args: &[Term]) -> fmt::Result {
+        write!(f, "{}(", AtomName(name))?;
+        self.write_list(f, args, ", ")?;
+        f.write_char(')')
+    }
+
+    /// Writes the `(Args) -> Result` of a function type
+    fn write_fun(&self, f: &mut fmt::Formatter<'_>, fun: &[Term]) -> fmt::Result {
+        match fun {
+            [args, result] => {
+                match tagged(args).and_then(|(tag, args)| Some((tag, args.get(2)?.as_list()?))) {
+                    Some(("type", args)) => {
+                        f.write_char('(')?;
+                        self.write_list(f, args, ", ")?;
+                        f.write_char(')')?;
+                    }
+                    _ => f.write_str("(...)")?,
+                }
+                write!(f, " -> {}", self.nested(result))
+            }
+            _ => f.write_str("(...) -> any()"),
+        }
+    }
+
+    fn write_builtin(&self, f: &mut fmt::Formatter<'_>, name: &str, args: &[Term]) -> fmt::Result {
+        match (name, args) {
+            ("union", _) => self.write_list(f, args, " | "),
+            ("tuple", _) => {
+                f.write_char('{')?;
+                self.write_list(f, args, ", ")?;
+                f.write_char('}')
+            }
+            ("list", [element]) => write!(f, "[{}]", self.nested(element)),
+            ("nonempty_list", [element]) => write!(f, "[{}, ...]", self.nested(element)),
+            ("nil", []) => f.write_str("[]"),
+            ("fun", []) => f.write_str("fun()"),
+            ("fun", fun) => {
+                f.write_str("fun(")?;
+                self.write_fun(f, fun)?;
+                f.write_char(')')
+            }
+            ("bounded_fun", [fun, constraints]) => {
+                match tagged(fun) {
+                    Some(("type", [_, _, fun])) => {
+                        self.write_fun(f, fun.as_list().unwrap_or(&[]))?
+                    }
+                    _ => write!(f, "{}", self.nested(fun))?,
+                }
+                f.write_str(" when ")?;
+                self.write_list(f, constraints.as_list().unwrap_or(&[]), ", ")
+            }
+            ("constraint", [_, bound]) => {
+                self.write_list(f, bound.as_list().unwrap_or(&[]), " :: ")
+            }
+            ("map", _) => {
+                f.write_str("#{")?;
+                self.write_list(f, args, ", ")?;
+                f.write_char('}')
+            }
+            ("map_field_assoc", [key, value]) => {
+                write!(f, "{} => {}", self.nested(key), self.nested(value))
+            }
+            ("map_field_exact", [key, value]) => {
+                write!(f, "{} := {}", self.nested(key), self.nested(value))
+            }
+            ("range", [low, high]) => write!(f, "{}..{}", self.nested(low), self.nested(high)),
+            ("binary", [size, unit]) => match (integer(size), integer(unit)) {
+                (Some(0), Some(0)) => f.write_str("<<>>"),
+                (Some(size), Some(0)) => write!(f, "<<_:{}>>", size),
+                (Some(0), Some(unit)) => write!(f, "<<_:_*{}>>", unit),
+                (Some(size), Some(unit)) => write!(f, "<<_:{}, _:_*{}>>", size, unit),
+                _ => f.write_str("binary()"),
+            },
+            ("record", [name, fields @ ..]) => {
+                f.write_char('#')?;
+                match tagged(name) {
+                    Some(("atom", [_, Term::Atom(name)])) => write!(f, "{}", AtomName(name))?,
+                    _ => write!(f, "{}", self.nested(name))?,
+                }
+                f.write_char('{')?;
+                self.write_list(f, fields, ", ")?;
+                f.write_char('}')
+            }
+            ("field_type", [name, ty]) => match tagged(name) {
+                Some(("atom", [_, Term::Atom(name)])) => {
+                    write!(f, "{} :: {}", AtomName(name), self.nested(ty))
+                }
+                _ => write!(f, "{} :: {}", self.nested(name), self.nested(ty)),
+            },
+            ("product", _) => self.write_list(f, args, ", "),
+            _ => self.write_call(f, name, args),
+        }
+    }
+}
+
+/// Value of an `{integer, Anno, Value}` node
+fn integer(term: &Term) -> Option<i64> {
+    match tagged(term) {
+        Some(("integer", [_, Term::Integer(value)])) => Some(*value),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Type<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match tagged(self.ty) {
+            Some(("type", [_, Term::Atom(name), Term::Atom(any)])) if any == "any" => {
+                write!(f, "{}()", AtomName(name))
+            }
+            Some(("type", [_, Term::Atom(name), args])) => match args.as_list() {
+                Some(args) => self.write_builtin(f, name, args),
+                None => write!(f, "{}", self.ty),
+            },
+            Some(("user_type", [_, Term::Atom(name), args])) => {
+                if let Some(module) = self.module {
+                    write!(f, "{}:", AtomName(module))?;
+                }
+                self.write_call(f, name, args.as_list().unwrap_or(&[]))
+            }
+            Some(("remote_type", [_, parts])) => match parts.as_list() {
+                Some([module, name, args]) => match (tagged(module), tagged(name)) {
+                    (
+                        Some(("atom", [_, Term::Atom(module)])),
+                        Some(("atom", [_, Term::Atom(name)])),
+                    ) => {
+                        write!(f, "{}:", AtomName(module))?;
+                        self.write_call(f, name, args.as_list().unwrap_or(&[]))
+                    }
+                    _ => write!(f, "{}", self.ty),
+                },
+                _ => write!(f, "{}", self.ty),
+            },
+            Some(("ann_type", [_, parts])) => {
+                self.write_list(f, parts.as_list().unwrap_or(&[]), " :: ")
+            }
+            Some(("paren_type", [_, parts])) => {
+                f.write_char('(')?;
+                self.write_list(f, parts.as_list().unwrap_or(&[]), ", ")?;
+                f.write_char(')')
+            }
+            Some(("var", [_, Term::Atom(name)])) => f.write_str(name),
+            Some(("atom", [_, Term::Atom(name)])) => write!(f, "{}", AtomName(name)),
+            Some(("integer", [_, value])) | Some(("char", [_, value])) => write!(f, "{}", value),
+            Some(("op", [_, Term::Atom(op), operand])) => match op.chars().all(char::is_alphabetic)
+            {
+                true => write!(f, "{} {}", op, self.nested(operand)),
+                false => write!(f, "{}{}", op, self.nested(operand)),
+            },
+            Some(("op", [_, Term::Atom(op), left, right])) => {
+                write!(f, "{} {} {}", self.nested(left), op, self.nested(right))
+            }
+            _ => write!(f, "{}", self.ty),
+        }
+    }
+}
+
+/// Displays the clauses of a `-spec` or `-callback` as `name(Args) -> Result; ...`
+pub(crate) struct Spec<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) clauses: &'a [Term],
+    pub(crate) module: Option<&'a str>,
+}
+
+impl fmt::Display for Spec<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, clause) in self.clauses.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";\n    ")?;
+            }
+            write!(f, "{}", AtomName(self.name))?;
+            let ty = Type {
+                ty: clause,
+                module: self.module,
+            };
+            match tagged(clause) {
+                Some(("type", [_, Term::Atom(kind), fun]))
+                    if kind == "fun" || kind == "bounded_fun" =>
+                {
+                    let fun = fun.as_list().unwrap_or(&[]);
+                    match kind.as_str() {
+                        "fun" => ty.write_fun(f, fun)?,
+                        _ => ty.write_builtin(f, kind, fun)?,
+                    }
+                }
+                _ => write!(f, "{}", ty)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::io::Cursor;
+
+use bumpalo::{collections::Vec as BumpVec, Bump};
+
+use crate::{
+    chunk::decode_counted,
+    code::{decode_instructions, split_header},
+    CodeHeader, Export, Id, Import, Instruction, Result,
+};
+
+/// A chunk whose decoded collections are allocated in a bump arena
+///
+/// Decoded from the chunk contents in place, see `BeamFile::read_in`.
+pub trait ArenaChunk<'bump> {
+    const ID: Id;
+    type Atom: Clone;
+
+    fn decode_in(
+        reader: &mut Cursor<&[u8]>,
+        atom_index: &[Self::Atom],
+        bump: &'bump Bump,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+}
+
+/// Creates the collection for `decode_counted` in `bump`
+fn bump_vec<'bump, T>(bump: &'bump Bump) -> impl FnOnce(usize) -> BumpVec<'bump, T> {
+    move |capacity| BumpVec::with_capacity_in(capacity, bump)
+}
+
+pub struct ArenaImpTChunk<'bump, A> {
+    pub imports: BumpVec<'bump, Import<A>>,
+}
+
+impl<'bump, A: Clone> ArenaChunk<'bump> for ArenaImpTChunk<'bump, A> {
+    const ID: Id = Id::IMP_T;
+    type Atom = A;
+
+    fn decode_in(reader: &mut Cursor<&[u8]>, atom_index: &[A], bump: &'bump Bump) -> Result<Self> {
+        let imports = decode_counted(reader, bump_vec(bump), |reader| {
+            Import::decode(reader, atom_index, Self::ID)
+        })?;
+        Ok(ArenaImpTChunk { imports })
+    }
+}
+
+pub struct ArenaExpTChunk<'bump, A> {
+    pub exports: BumpVec<'bump, Export<A>>,
+}
+
+impl<'bump, A: Clone> ArenaChunk<'bump> for ArenaExpTChunk<'bump, A> {
+    const ID: Id = Id::EXP_T;
+    type Atom = A;
+
+    fn decode_in(reader: &mut Cursor<&[u8]>, atom_index: &[A], bump: &'bump Bump) -> Result<Self> {
+        let exports = decode_counted(reader, bump_vec(bump), |reader| {
+            Export::decode(reader, atom_index, Self::ID)
+        })?;
+        Ok(ArenaExpTChunk { exports })
+    }
+}
+
+/// Local functions, stored in the same format as exports
+pub struct ArenaLocTChunk<'bump, A> {
+    pub locals: BumpVec<'bump, Export<A>>,
+}
+
+impl<'bump, A: Clone> ArenaChunk<'bump> for ArenaLocTChunk<'bump, A> {
+    const ID: Id = Id::LOC_T;
+    type Atom = A;
+
+    fn decode_in(reader: &mut Cursor<&[u8]>, atom_index: &[A], bump: &'bump Bump) -> Result<Self> {
+        let locals = decode_counted(reader, bump_vec(bump), |reader| {
+            Export::decode(reader, atom_index, Self::ID)
+        })?;
+        Ok(ArenaLocTChunk { locals })
+    }
+}
+
+/// The instructions of the `Code` chunk
+///
+/// Only the instruction list is allocated in the arena, the arguments of
+/// each instruction are not.
+pub struct ArenaCodeChunk<'bump, A> {
+    pub header: CodeHeader,
+    pub instructions: BumpVec<'bump, Instruction<A>>,
+}
+
+impl<'bump, A: Clone> ArenaChunk<'bump> for ArenaCodeChunk<'bump, A> {
+    const ID: Id = CodeHeader::ID;
+    type Atom = A;
+
+    fn decode_in(reader: &mut Cursor<&[u8]>, atom_index: &[A], bump: &'bump Bump) -> Result<Self> {
+        let data = *reader.get_ref();
+        reader.set_position(data.len() as u64);
+        let (header, code) = split_header(data)?;
+        let instructions = decode_instructions(code, atom_index, bump_vec(bump))?;
+        Ok(ArenaCodeChunk {
+            header,
+            instructions,
+        })
+    }
+}
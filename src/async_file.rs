@@ -0,0 +1,218 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    io::{self, Read, Seek, SeekFrom},
+};
+
+use crate::{
+    metrics::metrics, scan::Scanner, BeamFile, BeamFileError, BeamFileOptions, Chunk, Id,
+    InternerMut, NaiveInterner, Result,
+};
+
+/// Chunks fetched by `AsyncBeamFile`, readable at their offsets in the file
+///
+/// Reading data that wasn't fetched fails with `io::ErrorKind::NotFound`.
+#[derive(Clone, Debug, Default)]
+pub struct Fetched {
+    ranges: BTreeMap<u64, Vec<u8>>,
+    position: u64,
+}
+
+impl Fetched {
+    fn contains(&self, position: u64) -> bool {
+        self.ranges.contains_key(&position)
+    }
+}
+
+impl Read for Fetched {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let position = self.position;
+        let (start, data) = self
+            .ranges
+            .range(..=position)
+            .next_back()
+            .filter(|(start, data)| position < **start + data.len() as u64)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "chunk not fetched"))?;
+
+        let data = &data[(position - start) as usize..];
+        let len = buf.len().min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for Fetched {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(_) => None,
+        }
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(self.position)
+    }
+}
+
+/// An async reader of BEAM files
+///
+/// Implemented for tokio readers with the `tokio` feature, and for
+/// futures-io readers wrapped in `FuturesIo` with the `futures-io` feature.
+pub trait AsyncSource {
+    /// The current position, where the file is expected to start
+    fn position(&mut self) -> impl Future<Output = io::Result<u64>>;
+
+    /// The size of the whole source, in bytes
+    fn size(&mut self) -> impl Future<Output = io::Result<u64>>;
+
+    /// Fills `buf` with the data at `position`
+    fn read_exact_at(
+        &mut self,
+        position: u64,
+        buf: &mut [u8],
+    ) -> impl Future<Output = io::Result<()>>;
+}
+
+#[cfg(feature = "tokio")]
+impl<R: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin> AsyncSource for R {
+    async fn position(&mut self) -> io::Result<u64> {
+        tokio::io::AsyncSeekExt::stream_position(self).await
+    }
+
+    async fn size(&mut self) -> io::Result<u64> {
+        tokio::io::AsyncSeekExt::seek(self, SeekFrom::End(0)).await
+    }
+
+    async fn read_exact_at(&mut self, position: u64, buf: &mut [u8]) -> io::Result<()> {
+        tokio::io::AsyncSeekExt::seek(self, SeekFrom::Start(position)).await?;
+        tokio::io::AsyncReadExt::read_exact(self, buf).await?;
+        Ok(())
+    }
+}
+
+/// Adapts a futures-io reader, as used by async-std and smol, to `AsyncSource`
+#[cfg(feature = "futures-io")]
+#[derive(Clone, Debug)]
+pub struct FuturesIo<R>(pub R);
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead + futures_io::AsyncSeek + Unpin> FuturesIo<R> {
+    async fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let reader = &mut self.0;
+        std::future::poll_fn(|cx| std::pin::Pin::new(&mut *reader).poll_seek(cx, pos)).await
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<R: futures_io::AsyncRead + futures_io::AsyncSeek + Unpin> AsyncSource for FuturesIo<R> {
+    async fn position(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::Current(0)).await
+    }
+
+    async fn size(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::End(0)).await
+    }
+
+    async fn read_exact_at(&mut self, position: u64, mut buf: &mut [u8]) -> io::Result<()> {
+        self.seek(SeekFrom::Start(position)).await?;
+        let reader = &mut self.0;
+        while !buf.is_empty() {
+            let len = std::future::poll_fn(|cx| {
+                std::pin::Pin::new(&mut *reader).poll_read(cx, &mut *buf)
+            })
+            .await?;
+            if len == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            buf = &mut buf[len..];
+        }
+        Ok(())
+    }
+}
+
+/// A BEAM file indexed from an async reader
+///
+/// Chunks are read from the reader asynchronously and kept in memory, where
+/// the full `BeamFile` API is available on them through `file`.
+pub struct AsyncBeamFile<R, I: InternerMut = NaiveInterner> {
+    reader: R,
+    file: BeamFile<Fetched, I>,
+}
+
+impl<R: AsyncSource> AsyncBeamFile<R> {
+    pub async fn from_reader(reader: R) -> Result<Self> {
+        Self::parse(reader, &BeamFileOptions::default()).await
+    }
+
+    pub(crate) async fn parse(mut reader: R, options: &BeamFileOptions) -> Result<Self> {
+        let start = reader.position().await?;
+        let file_size = reader.size().await?;
+
+        let mut scanner = Scanner::new(start, file_size, options);
+        let mut buffer = [0; 12];
+        while let Some((position, len)) = scanner.next_read() {
+            reader.read_exact_at(position, &mut buffer[..len]).await?;
+            metrics().bytes_read(len as u64);
+            scanner.feed(&buffer[..len])?;
+        }
+
+        Ok(AsyncBeamFile {
+            reader,
+            file: BeamFile::from_scan(Fetched::default(), scanner, options),
+        })
+    }
+}
+
+impl<R: AsyncSource, I: InternerMut> AsyncBeamFile<R, I> {
+    /// Reads chunks into memory, ignoring ids not present in the file
+    pub async fn fetch(&mut self, ids: &[Id]) -> Result<()> {
+        for id in ids {
+            let entry = match self.file.index.get(id) {
+                Some(entry) if !self.file.reader.contains(entry.position) => entry,
+                _ => continue,
+            };
+
+            let mut data = vec![0; entry.len as usize];
+            self.reader
+                .read_exact_at(entry.position, &mut data)
+                .await
+                .map_err(|err| BeamFileError::from(err).in_chunk(*id, entry.position))?;
+            self.file.reader.ranges.insert(entry.position, data);
+        }
+        Ok(())
+    }
+
+    pub async fn read_raw(&mut self, id: Id) -> Result<Vec<u8>> {
+        self.fetch(&[id]).await?;
+        self.file.read_raw(id)
+    }
+
+    /// Reads a structured chunk representation
+    ///
+    /// Fails with `AtomsNotIndexed` if the atoms weren't indexed with `index_atoms`.
+    pub async fn read<C: Chunk<Atom = I::Atom>>(&mut self) -> Result<C> {
+        self.fetch(&[C::ID]).await?;
+        self.file.read()
+    }
+
+    pub async fn index_atoms(&mut self, interner: I) -> Result<()> {
+        self.fetch(&[Id::AT_U8, Id::ATOM]).await?;
+        self.file.index_atoms(interner)
+    }
+}
+
+impl<R, I: InternerMut> AsyncBeamFile<R, I> {
+    /// The file with the chunks fetched so far
+    pub fn file(&self) -> &BeamFile<Fetched, I> {
+        &self.file
+    }
+
+    pub fn file_mut(&mut self) -> &mut BeamFile<Fetched, I> {
+        &mut self.file
+    }
+
+    /// Drops the reader, keeping the chunks fetched so far
+    pub fn into_file(self) -> BeamFile<Fetched, I> {
+        self.file
+    }
+}
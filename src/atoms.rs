@@ -0,0 +1,176 @@
+use std::{io, str};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{BeamFileError, BorrowingInterner, Id, Result};
+
+/// Raw contents of the atom chunk
+///
+/// Allows streaming atom names without interning or collecting them.
+pub struct RawAtoms {
+    id: Id,
+    position: u64,
+    data: Vec<u8>,
+    count: usize,
+}
+
+impl RawAtoms {
+    /// Wraps the data of chunk `id`, which starts at `position` in the file
+    pub(crate) fn new(id: Id, position: u64, data: Vec<u8>) -> Result<Self> {
+        if data.len() < 4 {
+            return Err(
+                BeamFileError::from(io::Error::from(io::ErrorKind::UnexpectedEof))
+                    .in_chunk(id, position),
+            );
+        }
+        let count = BigEndian::read_u32(&data) as usize;
+        Ok(Self {
+            id,
+            position,
+            data,
+            count,
+        })
+    }
+
+    /// Number of atoms declared in the chunk header
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Upper bound on the number of atoms actually present in the chunk
+    ///
+    /// Every atom takes at least one byte, so unlike `len` this can't exceed
+    /// the chunk size and is safe to preallocate with.
+    pub fn capacity_hint(&self) -> usize {
+        self.count.min(self.data.len() - 4)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn iter(&self) -> RawAtomsIter<'_> {
+        RawAtomsIter {
+            inner: self.iter_bytes(),
+        }
+    }
+
+    /// Builds an atom table borrowing from this buffer
+    ///
+    /// Pass the result to `BeamFile::read_with_atoms` to decode chunks without
+    /// allocating atom names.
+    pub fn intern_borrowed<I: BorrowingInterner>(&self, interner: &I) -> Result<Vec<I::Atom<'_>>> {
+        self.iter()
+            .map(|name| Ok(interner.intern_borrowed(name?)))
+            .collect()
+    }
+
+    /// Iterates over atom names without validating them as UTF-8
+    pub fn iter_bytes(&self) -> RawAtomBytesIter<'_> {
+        RawAtomBytesIter {
+            id: self.id,
+            offset: self.position + 4,
+            data: &self.data[4..],
+            remaining: self.count,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RawAtoms {
+    type Item = Result<&'a str>;
+    type IntoIter = RawAtomsIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over atom names borrowed from a `RawAtoms` buffer
+///
+/// Stops after the first error.
+pub struct RawAtomsIter<'a> {
+    inner: RawAtomBytesIter<'a>,
+}
+
+impl<'a> Iterator for RawAtomsIter<'a> {
+    type Item = Result<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.inner.offset;
+        let result = self.inner.next()?.and_then(|name| {
+            str::from_utf8(name)
+                .map_err(|err| BeamFileError::from(err).in_chunk(self.inner.id, offset))
+        });
+        if result.is_err() {
+            self.inner.remaining = 0;
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+/// Iterator over undecoded atom names borrowed from a `RawAtoms` buffer
+///
+/// Stops after the first error.
+pub struct RawAtomBytesIter<'a> {
+    id: Id,
+    offset: u64,
+    data: &'a [u8],
+    remaining: usize,
+}
+
+impl<'a> RawAtomBytesIter<'a> {
+    /// Bytes of the chunk not consumed by the iterator yet
+    pub(crate) fn remainder(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Offset in the file of the next atom
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub(crate) fn id(&self) -> Id {
+        self.id
+    }
+
+    fn next_atom(&mut self) -> Result<&'a [u8]> {
+        let len = match self.data.first() {
+            Some(&len) if self.data.len() > len as usize => len as usize,
+            _ => {
+                return Err(
+                    BeamFileError::from(io::Error::from(io::ErrorKind::UnexpectedEof))
+                        .in_chunk(self.id, self.offset),
+                )
+            }
+        };
+        let (name, rest) = self.data[1..].split_at(len);
+        self.data = rest;
+        self.offset += 1 + len as u64;
+        Ok(name)
+    }
+}
+
+impl<'a> Iterator for RawAtomBytesIter<'a> {
+    type Item = Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let result = self.next_atom();
+        if result.is_err() {
+            self.remaining = 0;
+        }
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
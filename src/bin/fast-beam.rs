@@ -0,0 +1,425 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use fast_beam::{
+    BeamFile, BeamFileError, BeamSet, DiffOptions, DisasmOptions, Doc, DocEntry, ErrorContext, Id,
+    Result,
+};
+use fxhash::FxHashMap;
+
+/// Inspect compiled Erlang modules
+///
+/// Exits with status 2 if a file can't be read or decoded, and 1 if it
+/// doesn't have what was asked for, like debug info or a function.
+#[derive(Parser)]
+#[command(name = "fast-beam", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the module name, number of functions, the chunk table and the compile info
+    Info { file: PathBuf },
+    /// List the chunks of a file, or write the contents of one to stdout
+    Chunks {
+        file: PathBuf,
+        /// Id of the chunk to extract, for example `AtU8`
+        #[arg(long, value_name = "ID")]
+        raw: Option<Id>,
+        /// Id of a chunk to print as a hex dump with labelled fields
+        #[arg(long, value_name = "ID", conflicts_with = "raw")]
+        dump: Option<Id>,
+    },
+    /// Print the disassembled code
+    Disasm {
+        file: PathBuf,
+        /// Only print the function `name/arity`
+        #[arg(long, value_name = "NAME/ARITY", value_parser = parse_function)]
+        function: Option<(String, u32)>,
+        /// Print literals instead of their index
+        #[arg(long)]
+        literals: bool,
+        /// Print source locations of `line` instructions
+        #[arg(long)]
+        lines: bool,
+    },
+    /// Print the Erlang source recovered from the debug info
+    ///
+    /// Exits with status 1 if the module has no debug info, whether it was
+    /// compiled without it or stripped.
+    Decompile { file: PathBuf },
+    /// Remove the chunks not needed to load the modules
+    ///
+    /// Files are overwritten unless `--out` is given. Inputs with the same
+    /// file name can't be written to the same `--out` directory.
+    Strip {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+        /// Directory to write the stripped files to, created if missing
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+        /// Id of an additional chunk to keep, can be repeated
+        #[arg(long, value_name = "CHUNK")]
+        keep: Vec<Id>,
+    },
+    /// Print the documentation of a module or one of its functions
+    Docs {
+        file: PathBuf,
+        /// Function to document, as `fun`, `fun/arity` or `Mod.fun/arity`
+        #[arg(value_parser = parse_doc_function)]
+        function: Option<(String, Option<u32>)>,
+    },
+    /// Compare two versions of a module
+    ///
+    /// Like diff(1), exits with status 0 if they're the same, 1 if they
+    /// differ and 2 on errors.
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        /// Ignore the compile info, which differs between reproducible builds
+        #[arg(long)]
+        ignore_nondeterministic: bool,
+    },
+    /// Find calls to undefined and deprecated functions between modules, and
+    /// modules defined more than once
+    ///
+    /// Exits with status 1 if anything is found, and 2 on errors.
+    Xref {
+        #[arg(required = true)]
+        dirs: Vec<PathBuf>,
+        #[arg(long, value_enum, default_value_t = Format::Human)]
+        format: Format,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Human,
+    Json,
+}
+
+fn parse_function(function: &str) -> std::result::Result<(String, u32), String> {
+    let (name, arity) = function
+        .rsplit_once('/')
+        .ok_or_else(|| "expected name/arity".to_string())?;
+    let arity = arity.parse().map_err(|_| "invalid arity".to_string())?;
+    Ok((name.to_string(), arity))
+}
+
+fn parse_doc_function(function: &str) -> std::result::Result<(String, Option<u32>), String> {
+    let (name, arity) = match function.rsplit_once('/') {
+        Some((name, arity)) => {
+            let arity = arity.parse().map_err(|_| "invalid arity".to_string())?;
+            (name, Some(arity))
+        }
+        None => (function, None),
+    };
+    let name = name.rsplit(['.', ':']).next().unwrap_or(name);
+    Ok((name.to_string(), arity))
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Command::Info { file } => info(file),
+        Command::Chunks {
+            file,
+            raw: Some(id),
+            ..
+        } => raw_chunk(file, id),
+        Command::Chunks {
+            file,
+            dump: Some(id),
+            ..
+        } => dump_chunk(file, id),
+        Command::Chunks { file, .. } => chunks(file),
+        Command::Disasm {
+            file,
+            function,
+            literals,
+            lines,
+        } => disasm(file, function, literals, lines),
+        Command::Decompile { file } => decompile(file),
+        Command::Strip { paths, out, keep } => strip(paths, out, keep),
+        Command::Docs { file, function } => docs(file, function),
+        Command::Diff {
+            old,
+            new,
+            ignore_nondeterministic,
+        } => diff(old, new, ignore_nondeterministic),
+        Command::Xref { dirs, format } => xref(dirs, format),
+    };
+
+    if let Err(err) = result {
+        eprintln!("fast-beam: {}", err);
+        process::exit(2);
+    }
+}
+
+fn info(path: PathBuf) -> Result<()> {
+    let mut file = BeamFile::from_file(&path)?;
+    print!("{}", file.summary()?);
+    Ok(())
+}
+
+fn chunks(path: PathBuf) -> Result<()> {
+    let file = BeamFile::from_file(&path)?;
+    print_chunks(&file)
+}
+
+fn print_chunks<R>(file: &BeamFile<R>) -> Result<()> {
+    println!("{:<6} {:>10} {:>10}", "chunk", "offset", "size");
+    for (id, offset, size) in file.chunks() {
+        println!("{:<6} {:>10} {:>10}", id, offset, size);
+    }
+    Ok(())
+}
+
+fn dump_chunk(path: PathBuf, id: Id) -> Result<()> {
+    let mut file = BeamFile::from_file(&path)?;
+    print!("{}", file.hex_dump(id)?);
+    Ok(())
+}
+
+fn raw_chunk(path: PathBuf, id: Id) -> Result<()> {
+    let mut file = BeamFile::from_file(&path)?;
+    let data = file.read_raw(id)?;
+    io::stdout().lock().write_all(&data)?;
+    Ok(())
+}
+
+fn disasm(
+    path: PathBuf,
+    function: Option<(String, u32)>,
+    literals: bool,
+    lines: bool,
+) -> Result<()> {
+    let mut file = BeamFile::from_file(&path)?;
+    let disassembly = file.disassemble(
+        DisasmOptions::new()
+            .resolve_literals(literals)
+            .resolve_lines(lines),
+    )?;
+
+    match function {
+        Some((name, arity)) => {
+            let found = disassembly
+                .functions
+                .iter()
+                .find(|function| function.name == name && function.arity == arity);
+            match found {
+                Some(function) => println!("{}", function),
+                None => {
+                    eprintln!("fast-beam: function {}/{} not found", name, arity);
+                    process::exit(1);
+                }
+            }
+        }
+        None => print!("{}", disassembly),
+    }
+    Ok(())
+}
+
+fn decompile(path: PathBuf) -> Result<()> {
+    let mut file = BeamFile::from_file(&path)?;
+    match file.abstract_code()? {
+        Some(code) => print!("{}", code),
+        None => {
+            eprintln!("fast-beam: {} has no debug info", path.display());
+            process::exit(1);
+        }
+    }
+    Ok(())
+}
+
+fn strip(paths: Vec<PathBuf>, out: Option<PathBuf>, keep: Vec<Id>) -> Result<()> {
+    let targets = match &out {
+        Some(dir) => {
+            fs::create_dir_all(dir).map_err(in_path(dir))?;
+            let mut sources = FxHashMap::default();
+            let mut targets = Vec::with_capacity(paths.len());
+            for path in &paths {
+                let target = dir.join(path.file_name().unwrap_or(path.as_os_str()));
+                if let Some(other) = sources.insert(target.clone(), path) {
+                    let message = format!(
+                        "{} and {} would both be written to {}",
+                        other.display(),
+                        path.display(),
+                        target.display()
+                    );
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+                }
+                targets.push(target);
+            }
+            targets
+        }
+        None => paths.clone(),
+    };
+
+    let (mut before, mut after) = (0, 0);
+    for (path, target) in paths.iter().zip(&targets) {
+        let mut file = BeamFile::from_file(path)?;
+        let original = fs::metadata(path).map_err(in_path(path))?.len();
+
+        // Written next to the target first, as it may be the file being read
+        let partial = target.with_extension("beam.partial");
+        let written = write_stripped(&mut file, &keep, &partial);
+        drop(file);
+        let renamed = written.and_then(|stripped| {
+            fs::rename(&partial, target).map_err(in_path(target))?;
+            Ok(stripped)
+        });
+        let stripped = match renamed {
+            Ok(stripped) => stripped,
+            Err(err) => {
+                let _ = fs::remove_file(&partial);
+                return Err(err);
+            }
+        };
+
+        println!(
+            "{}: {} -> {} bytes, {} saved",
+            target.display(),
+            original,
+            stripped,
+            original.saturating_sub(stripped)
+        );
+        before += original;
+        after += stripped;
+    }
+
+    if paths.len() > 1 {
+        println!(
+            "total: {} -> {} bytes, {} saved",
+            before,
+            after,
+            before.saturating_sub(after)
+        );
+    }
+    Ok(())
+}
+
+fn write_stripped<R: Read + Seek>(
+    file: &mut BeamFile<R>,
+    keep: &[Id],
+    partial: &Path,
+) -> Result<u64> {
+    let mut writer = BufWriter::new(File::create(partial).map_err(in_path(partial))?);
+    let stripped = file.strip_to(keep, &mut writer)?;
+    writer.flush().map_err(in_path(partial))?;
+    Ok(stripped)
+}
+
+/// Attaches the path to an io error, like the errors of files read by the library
+fn in_path(path: &Path) -> impl FnOnce(io::Error) -> BeamFileError + '_ {
+    move |err| BeamFileError::WithContext {
+        context: ErrorContext {
+            path: Some(path.to_path_buf()),
+            ..ErrorContext::default()
+        },
+        source: Box::new(err.into()),
+    }
+}
+
+fn docs(path: PathBuf, function: Option<(String, Option<u32>)>) -> Result<()> {
+    let mut file = BeamFile::from_file(&path)?;
+    let docs = file.docs()?;
+
+    match function {
+        None => {
+            let name = file.peek_name()?.unwrap_or_default();
+            println!("# {}\n", name);
+            print_doc(&docs.module_doc);
+            for entry in docs.entries.iter().filter(|entry| entry.doc != Doc::Hidden) {
+                println!();
+                print_signature(entry);
+            }
+        }
+        Some((name, arity)) => {
+            let entries: Vec<_> = docs.function(&name, arity).collect();
+            if entries.is_empty() {
+                eprintln!("fast-beam: no documentation for {}", name);
+                process::exit(1);
+            }
+            for (i, entry) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                print_signature(entry);
+                println!();
+                print_doc(&entry.doc);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_signature(entry: &DocEntry) {
+    match entry.signature.is_empty() {
+        true => println!("## {}/{}", entry.name, entry.arity),
+        false => println!("## {}", entry.signature.join("\n## ")),
+    }
+}
+
+fn print_doc(doc: &Doc) {
+    match doc {
+        Doc::Text(_) => match doc.text("en") {
+            Some(text) => println!("{}", text),
+            None => println!("(no English documentation)"),
+        },
+        Doc::Hidden => println!("(hidden)"),
+        Doc::None => println!("(not documented)"),
+    }
+}
+
+fn diff(old: PathBuf, new: PathBuf, ignore_nondeterministic: bool) -> Result<()> {
+    let mut old = BeamFile::from_file(&old)?;
+    let mut new = BeamFile::from_file(&new)?;
+    let diff = old.diff(
+        &mut new,
+        DiffOptions::new().ignore_nondeterministic(ignore_nondeterministic),
+    )?;
+
+    for change in &diff.changes {
+        println!("{}", change);
+    }
+    if !diff.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn xref(dirs: Vec<PathBuf>, format: Format) -> Result<()> {
+    let mut set = BeamSet::new();
+    for dir in &dirs {
+        set.add_dir(dir)?;
+    }
+
+    let mut findings = set.duplicate_modules();
+    findings.extend(set.undefined_calls());
+    findings.extend(set.deprecated_calls());
+
+    match format {
+        Format::Human => {
+            for finding in &findings {
+                println!("{}", finding);
+            }
+        }
+        Format::Json => {
+            let json = serde_json::to_string_pretty(&findings).map_err(io::Error::from)?;
+            println!("{}", json);
+        }
+    }
+
+    if !findings.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
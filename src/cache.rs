@@ -0,0 +1,76 @@
+use std::io::{Read, Seek};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{optional, BeamFile, BeamFileError, Export, Id, Import, InternerMut, Result, Term};
+
+/// Identifies serialized summaries, followed by the format version
+const MAGIC: &[u8; 4] = b"FBMS";
+const VERSION: u16 = 1;
+
+/// The parts of a module build tools commonly need, cheap to store and load
+///
+/// Intended to be cached keyed by a hash of the file, so unchanged files
+/// don't need to be parsed again.
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+pub struct ModuleSummary {
+    /// Chunks in file order, as `(id, offset, len)`
+    pub chunks: Vec<(Id, u64, u64)>,
+    pub atoms: Vec<String>,
+    pub exports: Vec<Export<String>>,
+    pub imports: Vec<Import<String>>,
+    pub attributes: Vec<(String, Term)>,
+}
+
+impl ModuleSummary {
+    /// Summarizes a file, chunks other than the atoms are optional
+    pub fn new<R: Read + Seek, I: InternerMut>(file: &mut BeamFile<R, I>) -> Result<Self> {
+        let chunks = file.chunks().collect();
+        let atoms = file.owned_atoms()?;
+        let exports = file.owned_exports(&atoms)?;
+        let imports = file.owned_imports(&atoms)?;
+        let attributes =
+            optional(file.attributes())?.map_or_else(Vec::new, |chunk| chunk.attributes);
+
+        Ok(ModuleSummary {
+            chunks,
+            atoms,
+            exports,
+            imports,
+            attributes,
+        })
+    }
+
+    /// The module name, the first atom
+    pub fn name(&self) -> Option<&str> {
+        self.atoms.first().map(String::as_str)
+    }
+
+    /// Serializes the summary, prefixed with the format version
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&VERSION.to_be_bytes());
+        postcard::to_extend(self, data).expect("serializing to a vector can't fail")
+    }
+
+    /// Loads a summary serialized by `to_bytes`
+    ///
+    /// Summaries from other versions of the format are rejected, so they
+    /// can be treated as cache misses.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let payload = match data.strip_prefix(MAGIC) {
+            Some([major, minor, payload @ ..]) => {
+                if u16::from_be_bytes([*major, *minor]) != VERSION {
+                    return Err(BeamFileError::InvalidCache("unsupported version"));
+                }
+                payload
+            }
+            _ => return Err(BeamFileError::InvalidCache("not a summary")),
+        };
+        match postcard::take_from_bytes(payload) {
+            Ok((summary, [])) => Ok(summary),
+            Ok(_) => Err(BeamFileError::InvalidCache("trailing data")),
+            Err(_) => Err(BeamFileError::InvalidCache("malformed summary")),
+        }
+    }
+}
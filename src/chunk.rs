@@ -1,8 +1,11 @@
-use std::io::Read;
+use std::{
+    io::{Cursor, Read},
+    mem,
+};
 
 use byteorder::{BigEndian, ReadBytesExt};
 
-use crate::{Result, Id};
+use crate::{optional, BeamFile, BeamFileError, Id, Index, InternerMut, Limits, LitTChunk, Result};
 
 pub trait Chunk {
     const ID: Id;
@@ -13,64 +16,292 @@ pub trait Chunk {
         Self: Sized;
 }
 
+/// A chunk whose decoding needs other chunks of the file
+///
+/// For example resolving the literals referenced by the `Code` chunk, or
+/// the atoms mentioned by the documentation. Read with
+/// `BeamFile::read_in_context`. Chunks decoded on their own implement
+/// `Chunk` instead.
+pub trait ContextChunk {
+    const ID: Id;
+    type Atom: Clone;
+
+    fn decode_in_context<R: Read>(
+        reader: R,
+        context: &mut DecodeContext<'_, Self::Atom>,
+    ) -> Result<Self>
+    where
+        Self: Sized;
+}
+
+/// Reads a chunk of the file, along with its position
+pub(crate) type ReadSibling<'a> = dyn FnMut(Id) -> Result<(Vec<u8>, u64)> + 'a;
+
+/// Access to the rest of the file while decoding a `ContextChunk`
+pub struct DecodeContext<'a, A> {
+    pub(crate) atom_index: &'a [A],
+    pub(crate) index: &'a Index,
+    pub(crate) limits: &'a Limits,
+    pub(crate) read: &'a mut ReadSibling<'a>,
+}
+
+impl<A: Clone> DecodeContext<'_, A> {
+    /// The atom table, indexed by atom references starting from 1
+    pub fn atoms(&self) -> &[A] {
+        self.atom_index
+    }
+
+    /// The limits the file was opened with, see `BeamFileOptions::limits`
+    pub fn limits(&self) -> &Limits {
+        self.limits
+    }
+
+    pub fn has_chunk(&self, id: Id) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    /// Reads the contents of another chunk
+    pub fn read_raw(&mut self, id: Id) -> Result<Vec<u8>> {
+        (self.read)(id).map(|(data, _)| data)
+    }
+
+    /// Decodes another chunk using the same atoms
+    ///
+    /// Errors point to the other chunk.
+    pub fn read<C: Chunk<Atom = A>>(&mut self) -> Result<C> {
+        let (data, position) = (self.read)(C::ID)?;
+        let mut reader = Cursor::new(data);
+        C::decode(&mut reader, self.atom_index)
+            .map_err(|err| err.in_chunk(C::ID, position + reader.position()))
+    }
+
+    /// Like `read`, but returns `None` if the chunk is absent
+    pub fn read_opt<C: Chunk<Atom = A>>(&mut self) -> Result<Option<C>> {
+        optional(self.read())
+    }
+
+    /// Decodes the literal table, `None` if the file has none
+    ///
+    /// The decompressed size is bounded by `Limits::max_decompressed_size`.
+    pub fn literals(&mut self) -> Result<Option<LitTChunk>> {
+        if !self.has_chunk(LitTChunk::ID) {
+            return Ok(None);
+        }
+        let (data, position) = (self.read)(LitTChunk::ID)?;
+        let mut reader = Cursor::new(data);
+        LitTChunk::decode(&mut reader, self.limits.max_decompressed_size)
+            .map(Some)
+            .map_err(|err| err.in_chunk(LitTChunk::ID, position + reader.position()))
+    }
+}
+
+/// A tuple of chunk types decoded together by `BeamFile::read_many`
+///
+/// Implemented for tuples of up to 8 chunk types sharing an atom type.
+pub trait ChunkTuple<A>: Sized {
+    /// Ids of the chunks, in the order of the tuple
+    fn ids() -> Vec<Id>;
+
+    /// Decodes the tuple from chunks read by `BeamFile::read_many`
+    ///
+    /// `chunks` holds `(id, position, data)` for each chunk.
+    fn decode_all<R, I: InternerMut>(
+        file: &BeamFile<R, I>,
+        chunks: &[(Id, u64, Vec<u8>)],
+        atom_index: &[A],
+    ) -> Result<Self>;
+}
+
+macro_rules! chunk_tuple {
+    ($($chunk:ident),+) => {
+        impl<A: Clone, $($chunk: Chunk<Atom = A>),+> ChunkTuple<A> for ($($chunk,)+) {
+            fn ids() -> Vec<Id> {
+                vec![$($chunk::ID),+]
+            }
+
+            fn decode_all<R, I: InternerMut>(
+                file: &BeamFile<R, I>,
+                chunks: &[(Id, u64, Vec<u8>)],
+                atom_index: &[A],
+            ) -> Result<Self> {
+                Ok(($(file.decode_raw::<$chunk>(chunks, atom_index)?,)+))
+            }
+        }
+    };
+}
+
+chunk_tuple!(C1);
+chunk_tuple!(C1, C2);
+chunk_tuple!(C1, C2, C3);
+chunk_tuple!(C1, C2, C3, C4);
+chunk_tuple!(C1, C2, C3, C4, C5);
+chunk_tuple!(C1, C2, C3, C4, C5, C6);
+chunk_tuple!(C1, C2, C3, C4, C5, C6, C7);
+chunk_tuple!(C1, C2, C3, C4, C5, C6, C7, C8);
+
+/// Upper bound on memory preallocated for decoded collections
+///
+/// Counts come straight from the file, so a corrupt header shouldn't be able to
+/// trigger a huge allocation. Collections still grow past it if the data is there.
+const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+
+/// Capacity to preallocate for `count` elements of `T` read from the file
+pub(crate) fn cautious_capacity<T>(count: usize) -> usize {
+    count.min(MAX_PREALLOC_BYTES / mem::size_of::<T>().max(1))
+}
+
+/// Decodes a count followed by that many entries
+///
+/// `entries` creates the collection with the capacity to preallocate, see
+/// `cautious_capacity`.
+pub(crate) fn decode_counted<R: Read, T, C: Extend<T>>(
+    mut reader: R,
+    entries: impl FnOnce(usize) -> C,
+    mut decode: impl FnMut(&mut R) -> Result<T>,
+) -> Result<C> {
+    let count = reader.read_u32::<BigEndian>()? as usize;
+    let mut decoded = entries(cautious_capacity::<T>(count));
+    for _ in 0..count {
+        decoded.extend(Some(decode(&mut reader)?));
+    }
+    Ok(decoded)
+}
+
+/// Reads a 1-based atom reference and resolves it in the atom table
+pub(crate) fn read_atom<R: Read, A: Clone>(
+    mut reader: R,
+    atom_index: &[A],
+    chunk: Id,
+) -> Result<A> {
+    let index = reader.read_u32::<BigEndian>()?;
+    (index as usize)
+        .checked_sub(1)
+        .and_then(|i| atom_index.get(i))
+        .cloned()
+        .ok_or(BeamFileError::InvalidAtomIndex { chunk, index })
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Import<A> {
     pub module: A,
     pub function: A,
     pub arity: u32,
 }
 
+impl<A: Clone> Import<A> {
+    pub(crate) fn decode<R: Read>(mut reader: R, atom_index: &[A], chunk: Id) -> Result<Self> {
+        Ok(Import {
+            module: read_atom(&mut reader, atom_index, chunk)?,
+            function: read_atom(&mut reader, atom_index, chunk)?,
+            arity: reader.read_u32::<BigEndian>()?,
+        })
+    }
+}
+
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImpTChunk<A> {
     pub imports: Vec<Import<A>>,
 }
 
 impl<A: Clone> Chunk for ImpTChunk<A> {
-    const ID: Id = Id(*b"ImpT");
+    const ID: Id = Id::IMP_T;
     type Atom = A;
 
-    fn decode<R: Read>(mut reader: R, atom_index: &[A]) -> Result<Self> {
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut imports = Vec::with_capacity(count);
-
-        for _ in 0..count {
-            imports.push(Import {
-                module: atom_index[reader.read_u32::<BigEndian>()? as usize - 1].clone(),
-                function: atom_index[reader.read_u32::<BigEndian>()? as usize - 1].clone(),
-                arity: reader.read_u32::<BigEndian>()?,
-            })
-        }
-
+    fn decode<R: Read>(reader: R, atom_index: &[A]) -> Result<Self> {
+        let imports = decode_counted(reader, Vec::with_capacity, |reader| {
+            Import::decode(reader, atom_index, Self::ID)
+        })?;
         Ok(ImpTChunk { imports })
     }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Export<A> {
     pub function: A,
     pub arity: u32,
     pub label: u32,
 }
 
+impl<A: Clone> Export<A> {
+    pub(crate) fn decode<R: Read>(mut reader: R, atom_index: &[A], chunk: Id) -> Result<Self> {
+        Ok(Export {
+            function: read_atom(&mut reader, atom_index, chunk)?,
+            arity: reader.read_u32::<BigEndian>()?,
+            label: reader.read_u32::<BigEndian>()?,
+        })
+    }
+}
+
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ExpTChunk<A> {
     pub exports: Vec<Export<A>>,
 }
 
 impl<A: Clone> Chunk for ExpTChunk<A> {
-    const ID: Id = Id(*b"ExpT");
+    const ID: Id = Id::EXP_T;
+    type Atom = A;
+
+    fn decode<R: Read>(reader: R, atom_index: &[A]) -> Result<Self> {
+        let exports = decode_counted(reader, Vec::with_capacity, |reader| {
+            Export::decode(reader, atom_index, Self::ID)
+        })?;
+        Ok(ExpTChunk { exports })
+    }
+}
+
+/// Local functions, stored in the same format as exports
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocTChunk<A> {
+    pub locals: Vec<Export<A>>,
+}
+
+impl<A: Clone> Chunk for LocTChunk<A> {
+    const ID: Id = Id::LOC_T;
     type Atom = A;
 
-    fn decode<R: Read>(mut reader: R, atom_index: &[A]) -> Result<Self> {
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut exports = Vec::with_capacity(count);
+    fn decode<R: Read>(reader: R, atom_index: &[A]) -> Result<Self> {
+        let locals = decode_counted(reader, Vec::with_capacity, |reader| {
+            Export::decode(reader, atom_index, Self::ID)
+        })?;
+        Ok(LocTChunk { locals })
+    }
+}
+
+/// The header of the `Code` chunk
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeHeader {
+    pub instruction_set: u32,
+    pub opcode_max: u32,
+    pub label_count: u32,
+    pub function_count: u32,
+}
+
+impl CodeHeader {
+    pub const ID: Id = Id::CODE;
 
-        for _ in 0..count {
-            exports.push(Export {
-                function: atom_index[reader.read_u32::<BigEndian>()? as usize - 1].clone(),
-                arity: reader.read_u32::<BigEndian>()?,
-                label: reader.read_u32::<BigEndian>()?,
-            })
+    pub fn decode<R: Read>(mut reader: R) -> Result<Self> {
+        let header_size = reader.read_u32::<BigEndian>()?;
+        if header_size < 16 {
+            return Err(BeamFileError::MalformedChunk {
+                chunk: Self::ID,
+                reason: "header too short",
+            });
         }
 
-        Ok(ExpTChunk { exports })
+        Ok(CodeHeader {
+            instruction_set: reader.read_u32::<BigEndian>()?,
+            opcode_max: reader.read_u32::<BigEndian>()?,
+            label_count: reader.read_u32::<BigEndian>()?,
+            function_count: reader.read_u32::<BigEndian>()?,
+        })
     }
 }
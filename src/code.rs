@@ -0,0 +1,676 @@
+use std::{convert::TryFrom, io::Read};
+
+use crate::{
+    chunk::cautious_capacity, BeamFileError, Chunk, CodeHeader, ContextChunk, DecodeContext, Id,
+    ImpTChunk, Import, Result, Term,
+};
+
+/// Names and arities of the generic BEAM instructions, indexed by opcode
+///
+/// Mirrors `genop.tab` from the Erlang/OTP sources.
+const OPCODES: [(&str, u8); 184] = [
+    ("", 0),
+    ("label", 1),
+    ("func_info", 3),
+    ("int_code_end", 0),
+    ("call", 2),
+    ("call_last", 3),
+    ("call_only", 2),
+    ("call_ext", 2),
+    ("call_ext_last", 3),
+    ("bif0", 2),
+    ("bif1", 4),
+    ("bif2", 5),
+    ("allocate", 2),
+    ("allocate_heap", 3),
+    ("allocate_zero", 2),
+    ("allocate_heap_zero", 3),
+    ("test_heap", 2),
+    ("init", 1),
+    ("deallocate", 1),
+    ("return", 0),
+    ("send", 0),
+    ("remove_message", 0),
+    ("timeout", 0),
+    ("loop_rec", 2),
+    ("loop_rec_end", 1),
+    ("wait", 1),
+    ("wait_timeout", 2),
+    ("m_plus", 4),
+    ("m_minus", 4),
+    ("m_times", 4),
+    ("m_div", 4),
+    ("int_div", 4),
+    ("int_rem", 4),
+    ("int_band", 4),
+    ("int_bor", 4),
+    ("int_bxor", 4),
+    ("int_bsl", 4),
+    ("int_bsr", 4),
+    ("int_bnot", 3),
+    ("is_lt", 3),
+    ("is_ge", 3),
+    ("is_eq", 3),
+    ("is_ne", 3),
+    ("is_eq_exact", 3),
+    ("is_ne_exact", 3),
+    ("is_integer", 2),
+    ("is_float", 2),
+    ("is_number", 2),
+    ("is_atom", 2),
+    ("is_pid", 2),
+    ("is_reference", 2),
+    ("is_port", 2),
+    ("is_nil", 2),
+    ("is_binary", 2),
+    ("is_constant", 2),
+    ("is_list", 2),
+    ("is_nonempty_list", 2),
+    ("is_tuple", 2),
+    ("test_arity", 3),
+    ("select_val", 3),
+    ("select_tuple_arity", 3),
+    ("jump", 1),
+    ("catch", 2),
+    ("catch_end", 1),
+    ("move", 2),
+    ("get_list", 3),
+    ("get_tuple_element", 3),
+    ("set_tuple_element", 3),
+    ("put_string", 3),
+    ("put_list", 3),
+    ("put_tuple", 2),
+    ("put", 1),
+    ("badmatch", 1),
+    ("if_end", 0),
+    ("case_end", 1),
+    ("call_fun", 1),
+    ("make_fun", 3),
+    ("is_function", 2),
+    ("call_ext_only", 2),
+    ("bs_start_match", 2),
+    ("bs_get_integer", 5),
+    ("bs_get_float", 5),
+    ("bs_get_binary", 5),
+    ("bs_skip_bits", 4),
+    ("bs_test_tail", 2),
+    ("bs_save", 1),
+    ("bs_restore", 1),
+    ("bs_init", 2),
+    ("bs_final", 2),
+    ("bs_put_integer", 5),
+    ("bs_put_binary", 5),
+    ("bs_put_float", 5),
+    ("bs_put_string", 2),
+    ("bs_need_buf", 1),
+    ("fclearerror", 0),
+    ("fcheckerror", 1),
+    ("fmove", 2),
+    ("fconv", 2),
+    ("fadd", 4),
+    ("fsub", 4),
+    ("fmul", 4),
+    ("fdiv", 4),
+    ("fnegate", 3),
+    ("make_fun2", 1),
+    ("try", 2),
+    ("try_end", 1),
+    ("try_case", 1),
+    ("try_case_end", 1),
+    ("raise", 2),
+    ("bs_init2", 6),
+    ("bs_bits_to_bytes", 3),
+    ("bs_add", 5),
+    ("apply", 1),
+    ("apply_last", 2),
+    ("is_boolean", 2),
+    ("is_function2", 3),
+    ("bs_start_match2", 5),
+    ("bs_get_integer2", 7),
+    ("bs_get_float2", 7),
+    ("bs_get_binary2", 7),
+    ("bs_skip_bits2", 5),
+    ("bs_test_tail2", 3),
+    ("bs_save2", 2),
+    ("bs_restore2", 2),
+    ("gc_bif1", 5),
+    ("gc_bif2", 6),
+    ("bs_final2", 2),
+    ("bs_bits_to_bytes2", 2),
+    ("put_literal", 2),
+    ("is_bitstr", 2),
+    ("bs_context_to_binary", 1),
+    ("bs_test_unit", 3),
+    ("bs_match_string", 4),
+    ("bs_init_writable", 0),
+    ("bs_append", 8),
+    ("bs_private_append", 6),
+    ("trim", 2),
+    ("bs_init_bits", 6),
+    ("bs_get_utf8", 5),
+    ("bs_skip_utf8", 4),
+    ("bs_get_utf16", 5),
+    ("bs_skip_utf16", 4),
+    ("bs_get_utf32", 5),
+    ("bs_skip_utf32", 4),
+    ("bs_utf8_size", 3),
+    ("bs_put_utf8", 3),
+    ("bs_utf16_size", 3),
+    ("bs_put_utf16", 3),
+    ("bs_put_utf32", 3),
+    ("on_load", 0),
+    ("recv_mark", 1),
+    ("recv_set", 1),
+    ("gc_bif3", 7),
+    ("line", 1),
+    ("put_map_assoc", 5),
+    ("put_map_exact", 5),
+    ("is_map", 2),
+    ("has_map_fields", 3),
+    ("get_map_elements", 3),
+    ("is_tagged_tuple", 4),
+    ("build_stacktrace", 0),
+    ("raw_raise", 0),
+    ("get_hd", 2),
+    ("get_tl", 2),
+    ("put_tuple2", 2),
+    ("bs_get_tail", 3),
+    ("bs_start_match3", 4),
+    ("bs_get_position", 3),
+    ("bs_set_position", 2),
+    ("swap", 2),
+    ("bs_start_match4", 4),
+    ("make_fun3", 3),
+    ("init_yregs", 1),
+    ("recv_marker_bind", 2),
+    ("recv_marker_clear", 1),
+    ("recv_marker_reserve", 1),
+    ("recv_marker_use", 1),
+    ("bs_create_bin", 6),
+    ("call_fun2", 3),
+    ("nif_start", 0),
+    ("badrecord", 1),
+    ("update_record", 5),
+    ("bs_match", 3),
+    ("executable_line", 2),
+];
+
+/// Opcodes referenced by the decoder and analyses
+pub(crate) mod op {
+    pub const LABEL: u8 = 1;
+    pub const FUNC_INFO: u8 = 2;
+    pub const INT_CODE_END: u8 = 3;
+    pub const LINE: u8 = 153;
+}
+
+/// Name of the instruction with the given opcode
+pub(crate) fn opcode_name(opcode: u8) -> Option<&'static str> {
+    OPCODES
+        .get(opcode as usize)
+        .map(|(name, _)| *name)
+        .filter(|name| !name.is_empty())
+}
+
+/// An instruction argument in the compact term encoding
+#[derive(Clone, PartialEq, Debug)]
+pub enum Operand<A> {
+    /// Untagged value, such as an arity or an index into the import table
+    Unsigned(u64),
+    Integer(i64),
+    /// Integer not fitting in `i64`, in big-endian two's complement
+    BigInteger(Vec<u8>),
+    Atom(A),
+    Nil,
+    X(u32),
+    Y(u32),
+    Label(u32),
+    Char(u32),
+    FloatRegister(u32),
+    /// Index into the literal table, see `LitTChunk`
+    Literal(u32),
+    List(Vec<Operand<A>>),
+    /// Allocation list of `(kind, count)` pairs
+    AllocList(Vec<(u32, u64)>),
+    /// Register annotated with an index into the `Type` chunk
+    TypedRegister {
+        register: Box<Operand<A>>,
+        type_index: u32,
+    },
+    Float(f64),
+}
+
+/// A decoded instruction
+#[derive(Clone, PartialEq, Debug)]
+pub struct Instruction<A> {
+    pub opcode: u8,
+    pub args: Vec<Operand<A>>,
+    /// Offset of the encoded instruction from the start of the code
+    pub offset: usize,
+    /// Size of the encoded instruction in bytes
+    pub len: usize,
+}
+
+impl<A> Instruction<A> {
+    pub fn name(&self) -> &'static str {
+        opcode_name(self.opcode).unwrap_or("")
+    }
+
+    /// Index into the import table called by `call_ext` and the BIF instructions
+    pub fn import(&self) -> Option<u32> {
+        match self.args.get(self.import_position()?)? {
+            Operand::Unsigned(index) => u32::try_from(*index).ok(),
+            _ => None,
+        }
+    }
+
+    /// Position of the argument referring to the import table
+    ///
+    /// `call_ext` and friends take the arity first, `bif1` and `bif2` the
+    /// fail label, and the `gc_bif` instructions the fail label and the
+    /// number of live registers.
+    pub(crate) fn import_position(&self) -> Option<usize> {
+        match self.opcode {
+            7 | 8 | 78 => Some(1),
+            9 => Some(0),
+            10 | 11 => Some(1),
+            124 | 125 | 152 => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// The bytecode of the `Code` chunk
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct CodeChunk<A> {
+    pub header: CodeHeader,
+    pub instructions: Vec<Instruction<A>>,
+    /// Encoded instructions, which `Instruction::offset` refers to
+    pub code: Vec<u8>,
+}
+
+/// The `Code` chunk along with the imports and literals its instructions refer to
+#[derive(PartialEq, Debug)]
+pub(crate) struct LinkedCode<A> {
+    pub code: CodeChunk<A>,
+    /// Indexed by the import argument of calls, see `Instruction::import`
+    pub imports: Vec<Import<A>>,
+    /// Indexed by `Operand::Literal`
+    pub literals: Vec<Term>,
+}
+
+impl<A: Clone> ContextChunk for LinkedCode<A> {
+    const ID: Id = CodeHeader::ID;
+    type Atom = A;
+
+    fn decode_in_context<R: Read>(reader: R, context: &mut DecodeContext<'_, A>) -> Result<Self> {
+        Ok(LinkedCode {
+            code: CodeChunk::decode(reader, context.atoms())?,
+            imports: context
+                .read_opt::<ImpTChunk<A>>()?
+                .map_or_else(Vec::new, |chunk| chunk.imports),
+            literals: context
+                .literals()?
+                .map_or_else(Vec::new, |chunk| chunk.literals),
+        })
+    }
+}
+
+/// Instructions of a single function
+///
+/// Starts with the labels and line preceding `func_info`.
+#[derive(Debug)]
+pub(crate) struct FunctionCode<'a, A> {
+    pub name: &'a A,
+    pub arity: u32,
+    /// Entry label, following `func_info`
+    pub label: Option<u32>,
+    pub instructions: &'a [Instruction<A>],
+}
+
+impl<'a, A> FunctionCode<'a, A> {
+    /// Encoded instructions of the function
+    pub fn code<'c>(&self, chunk: &'c CodeChunk<A>) -> &'c [u8] {
+        match (self.instructions.first(), self.instructions.last()) {
+            (Some(first), Some(last)) => &chunk.code[first.offset..last.offset + last.len],
+            _ => &[],
+        }
+    }
+}
+
+impl<A> CodeChunk<A> {
+    /// Splits the instructions into functions, in the order they're defined
+    pub fn functions(&self) -> Vec<FunctionCode<'_, A>> {
+        let instructions = &self.instructions;
+        let mut starts = Vec::new();
+        for (i, instruction) in instructions.iter().enumerate() {
+            if instruction.opcode == op::FUNC_INFO {
+                let mut start = i;
+                while start > 0 && matches!(instructions[start - 1].opcode, op::LABEL | op::LINE) {
+                    start -= 1;
+                }
+                starts.push((start, i));
+            }
+        }
+        let end = instructions
+            .iter()
+            .position(|instruction| instruction.opcode == op::INT_CODE_END)
+            .unwrap_or(instructions.len());
+
+        let mut functions = Vec::with_capacity(starts.len());
+        for (n, &(start, func_info)) in starts.iter().enumerate() {
+            let next = starts.get(n + 1).map_or(end, |&(next, _)| next);
+            let (name, arity) = match &instructions[func_info].args[..] {
+                [_, Operand::Atom(name), Operand::Unsigned(arity)] => (name, *arity as u32),
+                _ => continue,
+            };
+            let label = instructions[func_info + 1..next]
+                .iter()
+                .find(|instruction| instruction.opcode == op::LABEL)
+                .and_then(|instruction| match instruction.args.first() {
+                    Some(Operand::Unsigned(label)) => Some(*label as u32),
+                    _ => None,
+                });
+            functions.push(FunctionCode {
+                name,
+                arity,
+                label,
+                instructions: &instructions[start..next],
+            });
+        }
+        functions
+    }
+}
+
+impl<A: Clone> Chunk for CodeChunk<A> {
+    const ID: Id = CodeHeader::ID;
+    type Atom = A;
+
+    fn decode<R: Read>(mut reader: R, atom_index: &[A]) -> Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let (header, code) = split_header(&data)?;
+        let instructions = decode_instructions(code, atom_index, Vec::with_capacity)?;
+
+        Ok(CodeChunk {
+            header,
+            instructions,
+            code: code.to_vec(),
+        })
+    }
+}
+
+/// Splits the `Code` chunk into its header and the encoded instructions
+pub(crate) fn split_header(data: &[u8]) -> Result<(CodeHeader, &[u8])> {
+    let header = CodeHeader::decode(data)?;
+    let header_size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let code = data
+        .get(4 + header_size..)
+        .ok_or_else(|| malformed("header past the end of the chunk"))?;
+    Ok((header, code))
+}
+
+/// Decodes the instructions up to `int_code_end`
+///
+/// `instructions` creates the collection with the capacity to preallocate.
+pub(crate) fn decode_instructions<A: Clone, C: Extend<Instruction<A>>>(
+    code: &[u8],
+    atom_index: &[A],
+    instructions: impl FnOnce(usize) -> C,
+) -> Result<C> {
+    let mut decoder = Decoder {
+        chunk: CodeHeader::ID,
+        data: code,
+        position: 0,
+        atom_index,
+    };
+    let mut decoded = instructions(cautious_capacity::<Instruction<A>>(code.len() / 2));
+    while decoder.position < code.len() {
+        let instruction = decoder.instruction()?;
+        let end = instruction.opcode == op::INT_CODE_END;
+        decoded.extend(Some(instruction));
+        if end {
+            break;
+        }
+    }
+    Ok(decoded)
+}
+
+pub(crate) const TAG_U: u8 = 0;
+pub(crate) const TAG_I: u8 = 1;
+pub(crate) const TAG_A: u8 = 2;
+pub(crate) const TAG_X: u8 = 3;
+pub(crate) const TAG_Y: u8 = 4;
+pub(crate) const TAG_F: u8 = 5;
+pub(crate) const TAG_H: u8 = 6;
+pub(crate) const TAG_Z: u8 = 7;
+
+fn malformed(reason: &'static str) -> BeamFileError {
+    BeamFileError::MalformedChunk {
+        chunk: CodeHeader::ID,
+        reason,
+    }
+}
+
+/// Value of a compact term before interpretation according to its tag
+pub(crate) enum Value<'a> {
+    Small(u64),
+    /// Big-endian bytes of a value longer than 8 bytes
+    Big(&'a [u8]),
+}
+
+/// Reader of the compact term encoding, shared with the `Line` chunk
+pub(crate) struct Decoder<'d, 'a, A> {
+    pub(crate) chunk: Id,
+    pub(crate) data: &'d [u8],
+    pub(crate) position: usize,
+    pub(crate) atom_index: &'a [A],
+}
+
+impl<'d, 'a, A: Clone> Decoder<'d, 'a, A> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or_else(|| self.malformed("unexpected end of code"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'d [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.saturating_add(len))
+            .ok_or_else(|| self.malformed("unexpected end of code"))?;
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn instruction(&mut self) -> Result<Instruction<A>> {
+        let offset = self.position;
+        let opcode = self.byte()?;
+        let arity = match OPCODES.get(opcode as usize) {
+            Some((name, arity)) if !name.is_empty() => *arity,
+            _ => return Err(self.malformed("unknown opcode")),
+        };
+        let mut args = Vec::with_capacity(arity as usize);
+        for _ in 0..arity {
+            args.push(self.operand()?);
+        }
+        Ok(Instruction {
+            opcode,
+            args,
+            offset,
+            len: self.position - offset,
+        })
+    }
+
+    /// Reads the tag and value of the next compact term
+    pub(crate) fn tagged(&mut self) -> Result<(u8, Value<'d>, u8)> {
+        let first = self.byte()?;
+        let tag = first & 0x07;
+        if tag == TAG_Z {
+            return Ok((tag, Value::Small(0), first));
+        }
+        Ok((tag, self.value(first)?, first))
+    }
+
+    fn value(&mut self, first: u8) -> Result<Value<'d>> {
+        if first & 0x08 == 0 {
+            return Ok(Value::Small((first >> 4) as u64));
+        }
+        if first & 0x10 == 0 {
+            let low = self.byte()?;
+            return Ok(Value::Small((((first & 0xe0) as u64) << 3) | low as u64));
+        }
+
+        let mut len = (first >> 5) as usize + 2;
+        if len == 9 {
+            let (tag, extra, _) = self.tagged()?;
+            len = match (tag, extra) {
+                (TAG_U, Value::Small(extra)) => usize::try_from(extra)
+                    .ok()
+                    .and_then(|extra| extra.checked_add(9))
+                    .ok_or_else(|| self.malformed("integer too large"))?,
+                _ => return Err(self.malformed("invalid integer length")),
+            };
+        }
+        let bytes = self.bytes(len)?;
+        if len > 8 {
+            return Ok(Value::Big(bytes));
+        }
+        Ok(Value::Small(
+            bytes
+                .iter()
+                .fold(0u64, |acc, &byte| (acc << 8) | byte as u64),
+        ))
+    }
+
+    /// Reads a value that must be a small unsigned integer
+    pub(crate) fn unsigned(&mut self) -> Result<u64> {
+        match self.tagged()? {
+            (_, Value::Small(value), first) if first & 0x07 != TAG_Z => Ok(value),
+            _ => Err(self.malformed("expected an unsigned integer")),
+        }
+    }
+
+    fn malformed(&self, reason: &'static str) -> BeamFileError {
+        BeamFileError::MalformedChunk {
+            chunk: self.chunk,
+            reason,
+        }
+    }
+
+    fn index(&self, value: u64) -> Result<u32> {
+        u32::try_from(value).map_err(|_| self.malformed("index too large"))
+    }
+
+    pub(crate) fn operand(&mut self) -> Result<Operand<A>> {
+        let (tag, value, first) = self.tagged()?;
+        if tag == TAG_Z {
+            return self.extended(first >> 4);
+        }
+
+        let value = match value {
+            Value::Small(value) => value,
+            Value::Big(bytes) if tag == TAG_I => return Ok(Operand::BigInteger(bytes.to_vec())),
+            Value::Big(bytes) => {
+                // Unsigned values may carry zero bytes for a positive sign
+                let start = bytes
+                    .iter()
+                    .position(|&byte| byte != 0)
+                    .unwrap_or(bytes.len());
+                if bytes.len() - start > 8 {
+                    return Err(self.malformed("integer too large"));
+                }
+                bytes[start..]
+                    .iter()
+                    .fold(0u64, |acc, &byte| (acc << 8) | byte as u64)
+            }
+        };
+
+        Ok(match tag {
+            TAG_U => Operand::Unsigned(value),
+            TAG_I => Operand::Integer(self.signed(first, value)),
+            TAG_A if value == 0 => Operand::Nil,
+            TAG_A => {
+                let atom = (value as usize)
+                    .checked_sub(1)
+                    .and_then(|i| self.atom_index.get(i))
+                    .cloned()
+                    .ok_or(BeamFileError::InvalidAtomIndex {
+                        chunk: self.chunk,
+                        index: value as u32,
+                    })?;
+                Operand::Atom(atom)
+            }
+            TAG_X => Operand::X(self.index(value)?),
+            TAG_Y => Operand::Y(self.index(value)?),
+            TAG_F => Operand::Label(self.index(value)?),
+            TAG_H => Operand::Char(self.index(value)?),
+            _ => unreachable!("tags are 3 bits"),
+        })
+    }
+
+    /// Interprets a multi-byte integer as two's complement
+    fn signed(&self, first: u8, value: u64) -> i64 {
+        if first & 0x18 != 0x18 {
+            return value as i64;
+        }
+        let len = (first >> 5) as u32 + 2;
+        if len >= 8 {
+            return value as i64;
+        }
+        let shift = 64 - 8 * len;
+        ((value << shift) as i64) >> shift
+    }
+
+    fn extended(&mut self, kind: u8) -> Result<Operand<A>> {
+        match kind {
+            0 => {
+                let bytes = self.bytes(8)?;
+                let mut float = [0; 8];
+                float.copy_from_slice(bytes);
+                Ok(Operand::Float(f64::from_be_bytes(float)))
+            }
+            1 => {
+                let len = self.unsigned()? as usize;
+                let mut elements = Vec::with_capacity(cautious_capacity::<Operand<A>>(len));
+                for _ in 0..len {
+                    let element = self.operand()?;
+                    if matches!(element, Operand::List(_)) {
+                        return Err(self.malformed("nested list"));
+                    }
+                    elements.push(element);
+                }
+                Ok(Operand::List(elements))
+            }
+            2 => Ok(Operand::FloatRegister(
+                self.unsigned().and_then(|value| self.index(value))?,
+            )),
+            3 => {
+                let len = self.unsigned()? as usize;
+                let mut entries = Vec::with_capacity(cautious_capacity::<(u32, u64)>(len));
+                for _ in 0..len {
+                    let kind = self.unsigned().and_then(|value| self.index(value))?;
+                    entries.push((kind, self.unsigned()?));
+                }
+                Ok(Operand::AllocList(entries))
+            }
+            4 => Ok(Operand::Literal(
+                self.unsigned().and_then(|value| self.index(value))?,
+            )),
+            5 => {
+                let register = self.operand()?;
+                if !matches!(register, Operand::X(_) | Operand::Y(_)) {
+                    return Err(self.malformed("expected a register"));
+                }
+                Ok(Operand::TypedRegister {
+                    register: Box::new(register),
+                    type_index: self.unsigned().and_then(|value| self.index(value))?,
+                })
+            }
+            _ => Err(self.malformed("unknown extended tag")),
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+use flate2::read::DeflateDecoder;
+
+use crate::Result;
+
+/// Extension of the archives applications can be packaged in
+const ARCHIVE_EXTENSION: &str = "ez";
+
+/// Ordered directories searched for modules, like the code path of the VM
+///
+/// Directories may be inside an `.ez` archive, with the path continuing
+/// past the archive, for example `lib/app-1.0.ez/app-1.0/ebin`.
+#[derive(Clone, Debug, Default)]
+pub struct CodePath {
+    roots: Vec<PathBuf>,
+}
+
+/// Location of a module found on a `CodePath`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ModulePath {
+    File(PathBuf),
+    /// Entry of an `.ez` archive, named by its path within the archive
+    Archive {
+        archive: PathBuf,
+        entry: String,
+    },
+}
+
+impl CodePath {
+    pub fn new<P: Into<PathBuf>>(roots: impl IntoIterator<Item = P>) -> Self {
+        CodePath {
+            roots: roots.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Finds the file the code server would load `module` from
+    ///
+    /// The first root containing `<module>.beam` wins. Roots that don't
+    /// exist are skipped, as the VM does, but unreadable archives fail.
+    pub fn resolve(&self, module: &str) -> Result<Option<ModulePath>> {
+        let file_name = format!("{}.beam", module);
+        for root in &self.roots {
+            let found = match split_archive(root) {
+                Some((archive, inner)) => {
+                    let entry = inner.join(&file_name);
+                    let entry = entry_name(&entry);
+                    let found = match File::open(&archive) {
+                        Ok(mut file) => find_entry(&mut file, &entry)?.is_some(),
+                        Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+                        Err(err) => return Err(err.into()),
+                    };
+                    found.then_some(ModulePath::Archive { archive, entry })
+                }
+                None => {
+                    let path = root.join(&file_name);
+                    path.is_file().then_some(ModulePath::File(path))
+                }
+            };
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl ModulePath {
+    /// Reads the contents of the module, extracting it from the archive if needed
+    pub fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            ModulePath::File(path) => Ok(fs::read(path)?),
+            ModulePath::Archive { archive, entry } => {
+                let mut file = File::open(archive)?;
+                let found = find_entry(&mut file, entry)?
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+                Ok(read_entry(&mut file, &found)?)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ModulePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModulePath::File(path) => write!(f, "{}", path.display()),
+            ModulePath::Archive { archive, entry } => {
+                write!(f, "{}/{}", archive.display(), entry)
+            }
+        }
+    }
+}
+
+/// Splits a path at the first `.ez` component into the archive and the path inside it
+fn split_archive(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    let mut archive = PathBuf::new();
+    let mut components = path.components();
+    for component in components.by_ref() {
+        archive.push(component);
+        if Path::new(component.as_os_str()).extension() == Some(ARCHIVE_EXTENSION.as_ref()) {
+            return Some((archive, components.collect()));
+        }
+    }
+    None
+}
+
+/// Name of a path within an archive, which always uses `/` as the separator
+fn entry_name(path: &Path) -> String {
+    let names: Vec<_> = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    names.join("/")
+}
+
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const END_OF_DIRECTORY_LEN: usize = 22;
+const MAX_COMMENT_LEN: usize = u16::MAX as usize;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// A file in a zip archive, as listed in the central directory
+struct ZipEntry {
+    method: u16,
+    compressed_size: u64,
+    size: u64,
+    header_offset: u64,
+}
+
+fn invalid(reason: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+/// Looks up a file in the central directory of a zip archive
+fn find_entry(file: &mut File, name: &str) -> io::Result<Option<ZipEntry>> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min((END_OF_DIRECTORY_LEN + MAX_COMMENT_LEN) as u64);
+    let mut tail = vec![0; tail_len as usize];
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    file.read_exact(&mut tail)?;
+
+    let end = (0..tail.len().saturating_sub(END_OF_DIRECTORY_LEN - 1))
+        .rev()
+        .find(|&i| LittleEndian::read_u32(&tail[i..]) == END_OF_DIRECTORY)
+        .map(|i| &tail[i..])
+        .ok_or_else(|| invalid("not a zip archive"))?;
+    let count = LittleEndian::read_u16(&end[10..]);
+    let directory_offset = LittleEndian::read_u32(&end[16..]) as u64;
+
+    file.seek(SeekFrom::Start(directory_offset))?;
+    let mut reader = io::BufReader::new(file);
+    let mut header = [0; 46];
+    for _ in 0..count {
+        reader.read_exact(&mut header)?;
+        if LittleEndian::read_u32(&header) != DIRECTORY_ENTRY {
+            return Err(invalid("malformed zip directory"));
+        }
+        let name_len = LittleEndian::read_u16(&header[28..]) as usize;
+        let skip_len = LittleEndian::read_u16(&header[30..]) as i64
+            + LittleEndian::read_u16(&header[32..]) as i64;
+        let mut entry_name = vec![0; name_len];
+        reader.read_exact(&mut entry_name)?;
+        reader.seek_relative(skip_len)?;
+
+        if entry_name == name.as_bytes() {
+            let fields = [20, 24, 42].map(|offset| LittleEndian::read_u32(&header[offset..]));
+            if fields.contains(&u32::MAX) {
+                return Err(invalid("zip64 archives aren't supported"));
+            }
+            let [compressed_size, size, header_offset] = fields.map(u64::from);
+            return Ok(Some(ZipEntry {
+                method: LittleEndian::read_u16(&header[10..]),
+                compressed_size,
+                size,
+                header_offset,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads and decompresses a file from a zip archive
+fn read_entry(file: &mut File, entry: &ZipEntry) -> io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(entry.header_offset))?;
+    if file.read_u32::<LittleEndian>()? != LOCAL_HEADER {
+        return Err(invalid("malformed zip entry"));
+    }
+    let mut header = [0; 26];
+    file.read_exact(&mut header)?;
+    let skip_len =
+        LittleEndian::read_u16(&header[22..]) as i64 + LittleEndian::read_u16(&header[24..]) as i64;
+    file.seek(SeekFrom::Current(skip_len))?;
+
+    let compressed = file.take(entry.compressed_size);
+    let mut data = Vec::new();
+    match entry.method {
+        STORED => compressed.take(entry.size).read_to_end(&mut data)?,
+        DEFLATED => DeflateDecoder::new(compressed)
+            .take(entry.size)
+            .read_to_end(&mut data)?,
+        _ => return Err(invalid("unsupported zip compression")),
+    };
+    if data.len() as u64 != entry.size {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof));
+    }
+    Ok(data)
+}
@@ -0,0 +1,72 @@
+use std::io::{Read, Seek};
+
+use crate::{code::op, BeamFile, CodeChunk, InternerMut, Result};
+
+/// Instructions calling a function, local or external
+const CALLS: [&str; 10] = [
+    "call",
+    "call_last",
+    "call_only",
+    "call_ext",
+    "call_ext_last",
+    "call_ext_only",
+    "call_fun",
+    "call_fun2",
+    "apply",
+    "apply_last",
+];
+
+/// Size and complexity of the code of a single function
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionMetrics {
+    pub name: String,
+    pub arity: u32,
+    /// Number of instructions, leaving out `label` and `line`
+    pub instructions: usize,
+    /// Size of the encoded instructions, in bytes
+    pub bytes: usize,
+    /// Number of basic blocks, one for each label after `func_info`
+    pub blocks: usize,
+    /// Number of calls to other functions or funs, leaving out BIFs
+    pub calls: usize,
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Measures the code of each function, in the order they're defined
+    pub fn function_metrics(&mut self) -> Result<Vec<FunctionMetrics>> {
+        let atoms = self.owned_atoms()?;
+        let code = self.read_with_atoms::<CodeChunk<String>>(&atoms)?;
+        let metrics = code
+            .functions()
+            .iter()
+            .map(|function| {
+                let body = function
+                    .instructions
+                    .iter()
+                    .position(|instruction| instruction.opcode == op::FUNC_INFO)
+                    .map_or(function.instructions, |i| &function.instructions[i..]);
+                FunctionMetrics {
+                    name: function.name.clone(),
+                    arity: function.arity,
+                    instructions: function
+                        .instructions
+                        .iter()
+                        .filter(|instruction| !matches!(instruction.opcode, op::LABEL | op::LINE))
+                        .count(),
+                    bytes: function.code(&code).len(),
+                    blocks: body
+                        .iter()
+                        .filter(|instruction| instruction.opcode == op::LABEL)
+                        .count(),
+                    calls: function
+                        .instructions
+                        .iter()
+                        .filter(|instruction| CALLS.contains(&instruction.name()))
+                        .count(),
+                }
+            })
+            .collect();
+        Ok(metrics)
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    io::{Read, Seek},
+};
+
+use crate::{optional, term::AtomName, BeamFile, Id, Import, InternerMut, Result, Term};
+
+/// Chunks that differ between builds of the same source
+///
+/// The compile info contains the compile time options, including paths,
+/// unless compiled with `deterministic`.
+const NONDETERMINISTIC_CHUNKS: [Id; 1] = [Id::C_INF];
+
+/// Options for `BeamFile::diff`
+#[derive(Clone, Debug, Default)]
+pub struct DiffOptions {
+    pub(crate) ignore_nondeterministic: bool,
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to ignore the compile info, which differs between otherwise
+    /// reproducible builds, `false` by default
+    pub fn ignore_nondeterministic(&mut self, ignore: bool) -> &mut Self {
+        self.ignore_nondeterministic = ignore;
+        self
+    }
+}
+
+/// Options for `BeamFile::semantically_eq`
+#[derive(Clone, Debug, Default)]
+pub struct EqOptions {
+    pub(crate) ignored: Vec<Id>,
+}
+
+impl EqOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leaves a chunk out of the comparison, can be called repeatedly
+    ///
+    /// Ignoring either `Atom` or `AtU8` ignores the atom table, whichever
+    /// chunk it's stored in.
+    pub fn ignore_chunk(&mut self, id: Id) -> &mut Self {
+        self.ignored.push(id);
+        self
+    }
+
+    fn is_ignored(&self, id: Id) -> bool {
+        self.ignored.contains(&id)
+    }
+}
+
+/// Chunks compared by their decoded contents in `BeamFile::semantically_eq`
+const DECODED_CHUNKS: [Id; 6] = [
+    Id::ATOM,
+    Id::AT_U8,
+    Id::EXP_T,
+    Id::IMP_T,
+    Id::ATTR,
+    Id::LIT_T,
+];
+
+/// A difference between two modules
+#[derive(Clone, PartialEq, Debug)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Change {
+    ChunkAdded(Id),
+    ChunkRemoved(Id),
+    /// Contents of the chunk differ, sizes in bytes
+    ChunkChanged {
+        chunk: Id,
+        old_size: u64,
+        new_size: u64,
+    },
+    ExportAdded {
+        function: String,
+        arity: u32,
+    },
+    ExportRemoved {
+        function: String,
+        arity: u32,
+    },
+    AttributeAdded {
+        name: String,
+        value: Term,
+    },
+    AttributeRemoved {
+        name: String,
+        value: Term,
+    },
+    AttributeChanged {
+        name: String,
+        old: Term,
+        new: Term,
+    },
+    LiteralAdded(Term),
+    LiteralRemoved(Term),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::ChunkAdded(chunk) => write!(f, "+ chunk {}", chunk),
+            Change::ChunkRemoved(chunk) => write!(f, "- chunk {}", chunk),
+            Change::ChunkChanged {
+                chunk,
+                old_size,
+                new_size,
+            } => write!(f, "~ chunk {}, {} -> {} bytes", chunk, old_size, new_size),
+            Change::ExportAdded { function, arity } => {
+                write!(f, "+ export {}/{}", AtomName(function), arity)
+            }
+            Change::ExportRemoved { function, arity } => {
+                write!(f, "- export {}/{}", AtomName(function), arity)
+            }
+            Change::AttributeAdded { name, value } => {
+                write!(f, "+ attribute {}: {}", AtomName(name), value)
+            }
+            Change::AttributeRemoved { name, value } => {
+                write!(f, "- attribute {}: {}", AtomName(name), value)
+            }
+            Change::AttributeChanged { name, old, new } => {
+                write!(f, "~ attribute {}: {} -> {}", AtomName(name), old, new)
+            }
+            Change::LiteralAdded(literal) => write!(f, "+ literal {}", literal),
+            Change::LiteralRemoved(literal) => write!(f, "- literal {}", literal),
+        }
+    }
+}
+
+/// The result of `BeamFile::diff`
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BeamDiff {
+    pub changes: Vec<Change>,
+}
+
+impl BeamDiff {
+    /// Returns `true` if the modules are equivalent
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// Values of each attribute, in the order they're declared
+///
+/// Attributes like `behaviour` can be repeated, their values are paired by
+/// position.
+fn attributes_by_name(attributes: &[(String, Term)]) -> BTreeMap<&str, Vec<&Term>> {
+    let mut by_name = BTreeMap::<_, Vec<_>>::new();
+    for (name, value) in attributes {
+        by_name.entry(name.as_str()).or_default().push(value);
+    }
+    by_name
+}
+
+/// Parts of a module compared by `BeamFile::diff`
+struct Summary {
+    chunks: BTreeMap<Id, Vec<u8>>,
+    exports: BTreeSet<(String, u32)>,
+    attributes: Vec<(String, Term)>,
+    literals: Vec<Term>,
+}
+
+/// Parts of a module compared by `BeamFile::semantically_eq`, `None` if ignored
+#[derive(PartialEq)]
+struct Contents {
+    atoms: Option<Vec<String>>,
+    exports: Option<BTreeSet<(String, u32, u32)>>,
+    imports: Option<Vec<Import<String>>>,
+    attributes: Option<Vec<(String, Term)>>,
+    literals: Option<Vec<Term>>,
+    /// Other chunks, compared byte by byte
+    chunks: BTreeMap<Id, Vec<u8>>,
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Compares the decoded contents of this module with `other`
+    ///
+    /// Unlike byte equality, this tolerates differences in encoding that
+    /// don't change the module: the legacy `Atom` chunk against `AtU8`, the
+    /// order of exports and the compression of literals. Chunks without
+    /// a decoded representation are compared byte by byte.
+    pub fn semantically_eq<S: Read + Seek, J: InternerMut>(
+        &mut self,
+        other: &mut BeamFile<S, J>,
+        options: &EqOptions,
+    ) -> Result<bool> {
+        Ok(self.contents(options)? == other.contents(options)?)
+    }
+
+    fn contents(&mut self, options: &EqOptions) -> Result<Contents> {
+        let chunks = self
+            .iter_raw()
+            .filter(|(id, _)| !DECODED_CHUNKS.contains(id) && !options.is_ignored(*id))
+            .map(|(id, data)| Ok((id, data?)))
+            .collect::<Result<_>>()?;
+
+        let atoms = self.owned_atoms()?;
+        let exports = match options.is_ignored(Id::EXP_T) {
+            true => None,
+            false => Some(
+                self.owned_exports(&atoms)?
+                    .into_iter()
+                    .map(|export| (export.function, export.arity, export.label))
+                    .collect(),
+            ),
+        };
+        let imports = match options.is_ignored(Id::IMP_T) {
+            true => None,
+            false => Some(self.owned_imports(&atoms)?),
+        };
+        let attributes = match options.is_ignored(Id::ATTR) {
+            true => None,
+            false => optional(self.attributes())?.map(|chunk| chunk.attributes),
+        };
+        let literals = match options.is_ignored(Id::LIT_T) {
+            true => None,
+            false => optional(self.literals())?.map(|chunk| chunk.literals),
+        };
+        let atoms = match options.is_ignored(Id::ATOM) || options.is_ignored(Id::AT_U8) {
+            true => None,
+            false => Some(atoms),
+        };
+
+        Ok(Contents {
+            atoms,
+            exports,
+            imports,
+            attributes,
+            literals,
+            chunks,
+        })
+    }
+
+    /// Compares this module, as the old version, against `other`
+    ///
+    /// Changed chunks are listed along with the exports, attributes and
+    /// literals that changed within them.
+    pub fn diff<S: Read + Seek, J: InternerMut>(
+        &mut self,
+        other: &mut BeamFile<S, J>,
+        options: &DiffOptions,
+    ) -> Result<BeamDiff> {
+        let old = self.summary_for_diff(options)?;
+        let new = other.summary_for_diff(options)?;
+        let mut changes = Vec::new();
+
+        for (id, data) in &old.chunks {
+            match new.chunks.get(id) {
+                None => changes.push(Change::ChunkRemoved(*id)),
+                Some(other) if other != data => changes.push(Change::ChunkChanged {
+                    chunk: *id,
+                    old_size: data.len() as u64,
+                    new_size: other.len() as u64,
+                }),
+                Some(_) => {}
+            }
+        }
+        for id in new.chunks.keys() {
+            if !old.chunks.contains_key(id) {
+                changes.push(Change::ChunkAdded(*id));
+            }
+        }
+
+        for (function, arity) in old.exports.difference(&new.exports) {
+            changes.push(Change::ExportRemoved {
+                function: function.clone(),
+                arity: *arity,
+            });
+        }
+        for (function, arity) in new.exports.difference(&old.exports) {
+            changes.push(Change::ExportAdded {
+                function: function.clone(),
+                arity: *arity,
+            });
+        }
+
+        let old_attributes = attributes_by_name(&old.attributes);
+        let new_attributes = attributes_by_name(&new.attributes);
+        for (name, values) in &old_attributes {
+            let others = new_attributes.get(name).map_or(&[][..], Vec::as_slice);
+            for (i, value) in values.iter().enumerate() {
+                match others.get(i) {
+                    None => changes.push(Change::AttributeRemoved {
+                        name: name.to_string(),
+                        value: (*value).clone(),
+                    }),
+                    Some(other) if other != value => changes.push(Change::AttributeChanged {
+                        name: name.to_string(),
+                        old: (*value).clone(),
+                        new: (*other).clone(),
+                    }),
+                    Some(_) => {}
+                }
+            }
+        }
+        for (name, values) in &new_attributes {
+            let count = old_attributes.get(name).map_or(0, Vec::len);
+            for value in values.iter().skip(count) {
+                changes.push(Change::AttributeAdded {
+                    name: name.to_string(),
+                    value: (*value).clone(),
+                });
+            }
+        }
+
+        for literal in &old.literals {
+            if !new.literals.contains(literal) {
+                changes.push(Change::LiteralRemoved(literal.clone()));
+            }
+        }
+        for literal in &new.literals {
+            if !old.literals.contains(literal) {
+                changes.push(Change::LiteralAdded(literal.clone()));
+            }
+        }
+
+        Ok(BeamDiff { changes })
+    }
+
+    fn summary_for_diff(&mut self, options: &DiffOptions) -> Result<Summary> {
+        let chunks = self
+            .iter_raw()
+            .filter(|(id, _)| {
+                !(options.ignore_nondeterministic && NONDETERMINISTIC_CHUNKS.contains(id))
+            })
+            .map(|(id, data)| Ok((id, data?)))
+            .collect::<Result<_>>()?;
+
+        let atoms = self.owned_atoms()?;
+        let exports = self
+            .owned_exports(&atoms)?
+            .into_iter()
+            .map(|export| (export.function, export.arity))
+            .collect();
+        let attributes =
+            optional(self.attributes())?.map_or_else(Vec::new, |chunk| chunk.attributes);
+        let literals = optional(self.literals())?.map_or_else(Vec::new, |chunk| chunk.literals);
+
+        Ok(Summary {
+            chunks,
+            exports,
+            attributes,
+            literals,
+        })
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    io::{Read, Seek},
+};
+
+use crate::{
+    code::op, optional, term::AtomName, BeamFile, CodeChunk, Import, Instruction, InternerMut,
+    LineChunk, Operand, Result, Term,
+};
+
+/// Options for `BeamFile::disassemble`
+#[derive(Clone, Debug, Default)]
+pub struct DisasmOptions {
+    pub(crate) literals: bool,
+    pub(crate) lines: bool,
+}
+
+impl DisasmOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to print literals instead of their index, `false` by default
+    pub fn resolve_literals(&mut self, resolve: bool) -> &mut Self {
+        self.literals = resolve;
+        self
+    }
+
+    /// Whether to print source locations of `line` instructions, `false` by default
+    pub fn resolve_lines(&mut self, resolve: bool) -> &mut Self {
+        self.lines = resolve;
+        self
+    }
+}
+
+/// Disassembled code of a module, displayed in the syntax of `erlc -S`
+pub struct Disassembly {
+    pub module: String,
+    pub functions: Vec<DisassembledFunction>,
+}
+
+pub struct DisassembledFunction {
+    pub name: String,
+    pub arity: u32,
+    pub label: Option<u32>,
+    /// Instructions rendered as Erlang terms
+    pub instructions: Vec<String>,
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{{module, {}}}.", AtomName(&self.module))?;
+        for function in &self.functions {
+            writeln!(f)?;
+            writeln!(f, "{}", function)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DisassembledFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{function, {}, {}, ", AtomName(&self.name), self.arity)?;
+        match self.label {
+            Some(label) => write!(f, "{}}}.", label)?,
+            None => f.write_str("undefined}.")?,
+        }
+        for instruction in &self.instructions {
+            let indent = if instruction.starts_with("{label,") {
+                2
+            } else {
+                4
+            };
+            write!(f, "\n{:indent$}{}.", "", instruction, indent = indent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Tables used to resolve instruction arguments
+struct Context<'a> {
+    module: &'a str,
+    imports: &'a [Import<String>],
+    literals: Option<&'a [Term]>,
+    lines: Option<&'a LineChunk>,
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Disassembles the `Code` chunk
+    ///
+    /// Calls to imported functions are always resolved, literals and line
+    /// information according to `options`.
+    pub fn disassemble(&mut self, options: &DisasmOptions) -> Result<Disassembly> {
+        let atoms = self.owned_atoms()?;
+
+        let code = self.read_with_atoms::<CodeChunk<String>>(&atoms)?;
+        let imports = self.owned_imports(&atoms)?;
+        let literals = match options.literals {
+            true => optional(self.literals())?,
+            false => None,
+        };
+        let lines = match options.lines {
+            true => optional(self.lines())?,
+            false => None,
+        };
+
+        let module = atoms.first().cloned().unwrap_or_default();
+        let context = Context {
+            module: &module,
+            imports: &imports,
+            literals: literals.as_ref().map(|chunk| &chunk.literals[..]),
+            lines: lines.as_ref(),
+        };
+
+        let functions = code
+            .functions()
+            .into_iter()
+            .map(|function| DisassembledFunction {
+                name: function.name.clone(),
+                arity: function.arity,
+                label: function.label,
+                instructions: function
+                    .instructions
+                    .iter()
+                    .map(|instruction| context.render(instruction))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Disassembly { module, functions })
+    }
+}
+
+impl Context<'_> {
+    fn render(&self, instruction: &Instruction<String>) -> String {
+        let import = instruction.import_position();
+        let mut text = format!("{{{}", instruction.name());
+        for (i, arg) in instruction.args.iter().enumerate() {
+            text.push(',');
+            match (arg, import) {
+                (Operand::Unsigned(index), Some(position)) if position == i => {
+                    self.write_import(&mut text, *index)
+                }
+                (Operand::Unsigned(index), _) if instruction.opcode == op::LINE => {
+                    self.write_line(&mut text, *index)
+                }
+                (arg, _) => self.write_operand(&mut text, arg),
+            }
+        }
+        text.push('}');
+        text
+    }
+
+    fn write_import(&self, text: &mut String, index: u64) {
+        match self.imports.get(index as usize) {
+            Some(import) => {
+                let _ = write!(
+                    text,
+                    "{{extfunc,{},{},{}}}",
+                    AtomName(&import.module),
+                    AtomName(&import.function),
+                    import.arity
+                );
+            }
+            None => {
+                let _ = write!(text, "{}", index);
+            }
+        }
+    }
+
+    fn write_line(&self, text: &mut String, index: u64) {
+        let lines = match self.lines {
+            Some(lines) => lines,
+            None => {
+                let _ = write!(text, "{}", index);
+                return;
+            }
+        };
+        match lines.location(index as u32) {
+            Some(location) => {
+                let file = match lines.file(location.file) {
+                    Some(file) => Cow::Borrowed(file),
+                    None => Cow::Owned(format!("{}.erl", self.module)),
+                };
+                let _ = write!(
+                    text,
+                    "[{{location,{},{}}}]",
+                    Term::String(file.as_bytes().to_vec()),
+                    location.line
+                );
+            }
+            None => text.push_str("[]"),
+        }
+    }
+
+    fn write_operand(&self, text: &mut String, operand: &Operand<String>) {
+        let _ = match operand {
+            Operand::Unsigned(value) => write!(text, "{}", value),
+            Operand::Integer(value) => write!(text, "{{integer,{}}}", value),
+            Operand::BigInteger(bytes) => write!(text, "{{integer,{}}}", big_integer(bytes)),
+            Operand::Atom(name) => write!(text, "{{atom,{}}}", AtomName(name)),
+            Operand::Nil => write!(text, "nil"),
+            Operand::X(register) => write!(text, "{{x,{}}}", register),
+            Operand::Y(register) => write!(text, "{{y,{}}}", register),
+            Operand::Label(label) => write!(text, "{{f,{}}}", label),
+            Operand::Char(char) => write!(text, "{{char,{}}}", char),
+            Operand::FloatRegister(register) => write!(text, "{{fr,{}}}", register),
+            Operand::Literal(index) => {
+                match self
+                    .literals
+                    .and_then(|literals| literals.get(*index as usize))
+                {
+                    Some(literal) => write!(text, "{{literal,{}}}", literal),
+                    None => write!(text, "{{literal_index,{}}}", index),
+                }
+            }
+            Operand::List(elements) => {
+                text.push_str("{list,[");
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        text.push(',');
+                    }
+                    self.write_operand(text, element);
+                }
+                write!(text, "]}}")
+            }
+            Operand::AllocList(entries) => {
+                text.push_str("{alloc,[");
+                for (i, (kind, count)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        text.push(',');
+                    }
+                    let kind = match kind {
+                        0 => "words",
+                        1 => "floats",
+                        2 => "funs",
+                        _ => "unknown",
+                    };
+                    let _ = write!(text, "{{{},{}}}", kind, count);
+                }
+                write!(text, "]}}")
+            }
+            Operand::TypedRegister {
+                register,
+                type_index,
+            } => {
+                text.push_str("{tr,");
+                self.write_operand(text, register);
+                write!(text, ",{}}}", type_index)
+            }
+            Operand::Float(value) => write!(text, "{{float,{}}}", Term::Float(*value)),
+        };
+    }
+}
+
+/// Converts big-endian two's complement to a displayable term
+fn big_integer(bytes: &[u8]) -> Term {
+    let negative = bytes.first().is_some_and(|&b| b & 0x80 != 0);
+    let mut digits: Vec<u8> = bytes.iter().rev().copied().collect();
+    if negative {
+        // Negate the two's complement
+        let mut carry = true;
+        for digit in digits.iter_mut() {
+            let (value, overflow) = (!*digit).overflowing_add(carry as u8);
+            *digit = value;
+            carry = overflow;
+        }
+    }
+    Term::BigInt { negative, digits }
+}
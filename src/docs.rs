@@ -0,0 +1,372 @@
+use std::{
+    fmt,
+    io::{Read, Seek},
+};
+
+use crate::{
+    optional, term::AtomName, BeamFile, BeamFileError, Export, Id, InternerMut, Result, Term,
+};
+
+/// Functions every module exports, which aren't expected to be documented
+const GENERATED_FUNCTIONS: [(&str, u32); 2] = [("module_info", 0), ("module_info", 1)];
+
+/// Documentation from the `Docs` chunk, in the format of EEP 48
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocsChunk {
+    /// Language the module was written in, such as `erlang` or `elixir`
+    pub language: String,
+    /// Mime type of the documentation, such as `text/markdown`
+    pub format: String,
+    pub module_doc: Doc,
+    pub metadata: Term,
+    pub entries: Vec<DocEntry>,
+}
+
+/// Documentation of a function, type or callback
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocEntry {
+    /// Kind of the entry, such as `function`, `type` or `callback`
+    pub kind: String,
+    pub name: String,
+    pub arity: u32,
+    pub signature: Vec<String>,
+    pub doc: Doc,
+    pub metadata: Term,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Doc {
+    /// Not documented
+    None,
+    /// Explicitly hidden from documentation
+    Hidden,
+    /// Documentation per language, like `"en"`
+    ///
+    /// The content is a binary for text formats, and a list of elements
+    /// for `application/erlang+html`.
+    Text(Vec<(String, Term)>),
+}
+
+impl Doc {
+    /// Returns the documentation in `language` as text
+    ///
+    /// Markdown is returned as is, `application/erlang+html` is converted to
+    /// markdown-like plain text.
+    pub fn text(&self, language: &str) -> Option<String> {
+        match self {
+            Doc::Text(docs) => {
+                docs.iter()
+                    .find(|(lang, _)| lang == language)
+                    .map(|(_, content)| {
+                        let mut text = String::new();
+                        render(&mut text, content, false);
+                        text.trim_end().to_string()
+                    })
+            }
+            _ => None,
+        }
+    }
+
+    fn decode(term: &Term) -> Result<Self> {
+        match term {
+            Term::Atom(name) if name == "none" => Ok(Doc::None),
+            Term::Atom(name) if name == "hidden" => Ok(Doc::Hidden),
+            Term::Map(entries) => entries
+                .iter()
+                .map(|(language, content)| Ok((binary(language)?, content.clone())))
+                .collect::<Result<_>>()
+                .map(Doc::Text),
+            _ => Err(malformed("invalid doc")),
+        }
+    }
+}
+
+impl DocsChunk {
+    pub const ID: Id = Id::DOCS;
+
+    /// Decodes the chunk, decompressing at most `max_size` bytes
+    pub fn decode<R: Read>(reader: R, max_size: u64) -> Result<Self> {
+        let fields = match Term::decode_limited(reader, max_size)? {
+            Term::Tuple(fields) if fields.len() == 7 && fields[0].as_atom() == Some("docs_v1") => {
+                fields
+            }
+            _ => return Err(malformed("expected docs_v1")),
+        };
+
+        let language = fields[2]
+            .as_atom()
+            .ok_or_else(|| malformed("invalid language"))?
+            .to_string();
+        let format = binary(&fields[3])?;
+        let module_doc = Doc::decode(&fields[4])?;
+        let entries = fields[6]
+            .as_list()
+            .ok_or_else(|| malformed("invalid entries"))?
+            .iter()
+            .map(DocEntry::decode)
+            .collect::<Result<_>>()?;
+
+        Ok(DocsChunk {
+            language,
+            format,
+            module_doc,
+            metadata: fields[5].clone(),
+            entries,
+        })
+    }
+
+    /// Entries of the function `name`, of any arity if `arity` is `None`
+    pub fn function(&self, name: &str, arity: Option<u32>) -> impl Iterator<Item = &DocEntry> {
+        let name = name.to_string();
+        self.entries.iter().filter(move |entry| {
+            entry.kind == "function" && entry.name == name && arity.is_none_or(|a| a == entry.arity)
+        })
+    }
+}
+
+impl DocEntry {
+    fn decode(term: &Term) -> Result<Self> {
+        let (key, fields) = match term {
+            Term::Tuple(fields) if fields.len() == 5 => match &fields[0] {
+                Term::Tuple(key) if key.len() == 3 => (key, fields),
+                _ => return Err(malformed("invalid entry")),
+            },
+            _ => return Err(malformed("invalid entry")),
+        };
+
+        let atom = |term: &Term| {
+            term.as_atom()
+                .map(str::to_string)
+                .ok_or_else(|| malformed("invalid entry"))
+        };
+        let arity = match key[2] {
+            Term::Integer(arity) if (0..=255).contains(&arity) => arity as u32,
+            _ => return Err(malformed("invalid arity")),
+        };
+        let signature = fields[2]
+            .as_list()
+            .ok_or_else(|| malformed("invalid signature"))?
+            .iter()
+            .map(binary)
+            .collect::<Result<_>>()?;
+
+        Ok(DocEntry {
+            kind: atom(&key[0])?,
+            name: atom(&key[1])?,
+            arity,
+            signature,
+            doc: Doc::decode(&fields[3])?,
+            metadata: fields[4].clone(),
+        })
+    }
+}
+
+/// Documentation coverage of the exported functions of a module
+///
+/// Displays as a report listing the functions lacking documentation.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocCoverage {
+    pub module: String,
+    /// Number of documented functions
+    pub documented: usize,
+    /// Functions without documentation, including modules without a `Docs` chunk
+    pub undocumented: Vec<(String, u32)>,
+    /// Functions hidden from documentation, all of them for hidden modules
+    pub hidden: Vec<(String, u32)>,
+}
+
+impl DocCoverage {
+    /// Number of exported functions, not counting `module_info`
+    pub fn total(&self) -> usize {
+        self.documented + self.undocumented.len() + self.hidden.len()
+    }
+
+    /// Fraction of the functions that are documented, 1 for modules without any
+    pub fn ratio(&self) -> f64 {
+        ratio(self.documented, self.total())
+    }
+}
+
+pub(crate) fn ratio(documented: usize, total: usize) -> f64 {
+    match total {
+        0 => 1.0,
+        total => documented as f64 / total as f64,
+    }
+}
+
+impl fmt::Display for DocCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {}/{} documented ({:.1}%)",
+            AtomName(&self.module),
+            self.documented,
+            self.total(),
+            self.ratio() * 100.0
+        )?;
+        for (label, functions) in [
+            ("undocumented", &self.undocumented),
+            ("hidden", &self.hidden),
+        ] {
+            if functions.is_empty() {
+                continue;
+            }
+            let functions: Vec<_> = functions
+                .iter()
+                .map(|(name, arity)| format!("{}/{}", AtomName(name), arity))
+                .collect();
+            writeln!(f, "  {}: {}", label, functions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Documentation coverage of a set of modules, see `BeamSet::doc_coverage`
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocCoverageReport {
+    pub modules: Vec<DocCoverage>,
+}
+
+impl DocCoverageReport {
+    /// Number of documented functions in all modules
+    pub fn documented(&self) -> usize {
+        self.modules.iter().map(|module| module.documented).sum()
+    }
+
+    /// Number of exported functions in all modules
+    pub fn total(&self) -> usize {
+        self.modules.iter().map(DocCoverage::total).sum()
+    }
+
+    /// Fraction of the functions that are documented, 1 for a set without any
+    pub fn ratio(&self) -> f64 {
+        ratio(self.documented(), self.total())
+    }
+}
+
+impl fmt::Display for DocCoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for module in &self.modules {
+            write!(f, "{}", module)?;
+        }
+        writeln!(
+            f,
+            "total: {}/{} documented ({:.1}%)",
+            self.documented(),
+            self.total(),
+            self.ratio() * 100.0
+        )
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Reports which exported functions are documented in the `Docs` chunk
+    pub fn doc_coverage(&mut self) -> Result<DocCoverage> {
+        let atoms = self.owned_atoms()?;
+        let exports = self.owned_exports(&atoms)?;
+        self.doc_coverage_of(atoms.into_iter().next().unwrap_or_default(), exports)
+    }
+
+    /// Like `doc_coverage`, for exports that were already decoded
+    pub(crate) fn doc_coverage_of(
+        &mut self,
+        module: String,
+        exports: Vec<Export<String>>,
+    ) -> Result<DocCoverage> {
+        let docs = optional(self.docs())?;
+        let module_hidden = docs
+            .as_ref()
+            .is_some_and(|docs| docs.module_doc == Doc::Hidden);
+
+        let mut coverage = DocCoverage {
+            module,
+            documented: 0,
+            undocumented: Vec::new(),
+            hidden: Vec::new(),
+        };
+        for export in exports {
+            if GENERATED_FUNCTIONS.contains(&(export.function.as_str(), export.arity)) {
+                continue;
+            }
+            let doc = docs.as_ref().and_then(|docs| {
+                docs.function(&export.function, Some(export.arity))
+                    .next()
+                    .map(|entry| &entry.doc)
+            });
+            let function = (export.function, export.arity);
+            match doc {
+                _ if module_hidden => coverage.hidden.push(function),
+                Some(Doc::Text(_)) => coverage.documented += 1,
+                Some(Doc::Hidden) => coverage.hidden.push(function),
+                Some(Doc::None) | None => coverage.undocumented.push(function),
+            }
+        }
+        Ok(coverage)
+    }
+}
+
+fn binary(term: &Term) -> Result<String> {
+    match term {
+        Term::Binary(bytes) => Ok(String::from_utf8_lossy(bytes).into_owned()),
+        _ => Err(malformed("expected a binary")),
+    }
+}
+
+fn malformed(reason: &'static str) -> BeamFileError {
+    BeamFileError::MalformedChunk {
+        chunk: DocsChunk::ID,
+        reason,
+    }
+}
+
+/// Renders binaries and `application/erlang+html` elements as text
+///
+/// `pre` is set within preformatted blocks, where code isn't quoted.
+fn render(text: &mut String, content: &Term, pre: bool) {
+    match content {
+        Term::Binary(bytes) => text.push_str(&String::from_utf8_lossy(bytes)),
+        Term::Tuple(element) if element.len() == 3 => {
+            let tag = element[0].as_atom().unwrap_or("");
+            let mut inner = String::new();
+            for child in element[2].as_list().unwrap_or_default() {
+                render(&mut inner, child, pre || tag == "pre");
+            }
+            match tag {
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                    let level = tag[1..].parse().unwrap_or(1);
+                    text.push_str(&format!("{} {}\n\n", "#".repeat(level), inner.trim()));
+                }
+                "p" | "div" | "blockquote" => {
+                    text.push_str(inner.trim());
+                    text.push_str("\n\n");
+                }
+                "pre" => {
+                    text.push_str("```\n");
+                    text.push_str(inner.trim_end());
+                    text.push_str("\n```\n\n");
+                }
+                "ul" | "ol" | "dl" => {
+                    text.push_str(&inner);
+                    text.push('\n');
+                }
+                "li" | "dt" => text.push_str(&format!("- {}\n", inner.trim())),
+                "dd" => text.push_str(&format!("  {}\n", inner.trim())),
+                "code" if !pre => text.push_str(&format!("`{}`", inner)),
+                "em" | "i" => text.push_str(&format!("*{}*", inner)),
+                "strong" | "b" => text.push_str(&format!("**{}**", inner)),
+                "br" => text.push('\n'),
+                _ => text.push_str(&inner),
+            }
+        }
+        term => {
+            for child in term.as_list().unwrap_or_default() {
+                render(text, child, pre);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    fmt,
+    io::{Read, Seek},
+};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use crate::{BeamFile, Id, InternerMut, Result};
+
+const BYTES_PER_ROW: usize = 16;
+
+/// A labelled range of the data in a `HexDump`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Annotation {
+    /// Offset from the start of the chunk data
+    pub offset: usize,
+    pub len: usize,
+    pub label: String,
+}
+
+/// Raw contents of a chunk, displayed as a hex dump with file offsets
+///
+/// For known chunks, the fields in the data are labelled, as far as the
+/// data allows. Fields are found from the bytes alone, so malformed chunks
+/// can be dumped as well.
+#[derive(Clone, Debug)]
+pub struct HexDump {
+    pub id: Id,
+    /// Position of the chunk data in the file
+    pub offset: u64,
+    pub data: Vec<u8>,
+    /// Labelled fields in the order of their offset, not overlapping
+    pub annotations: Vec<Annotation>,
+}
+
+impl HexDump {
+    /// Dumps the data of chunk `id`, which starts at `offset` in the file
+    pub fn new(id: Id, offset: u64, data: Vec<u8>) -> Self {
+        let annotations = annotate(id, &data);
+        HexDump {
+            id,
+            offset,
+            data,
+            annotations,
+        }
+    }
+
+    fn write_rows(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        start: usize,
+        len: usize,
+        label: &str,
+    ) -> fmt::Result {
+        let bytes = &self.data[start..start + len];
+        for (row, chunk) in bytes.chunks(BYTES_PER_ROW).enumerate() {
+            let offset = self.offset + (start + row * BYTES_PER_ROW) as u64;
+            let hex: Vec<_> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let hex = hex.join(" ");
+            match row {
+                0 if !label.is_empty() => writeln!(
+                    f,
+                    "{:08x}  {:<width$}  {}",
+                    offset,
+                    hex,
+                    label,
+                    width = BYTES_PER_ROW * 3 - 1
+                )?,
+                _ => writeln!(f, "{:08x}  {}", offset, hex)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for HexDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} at offset {}, {} bytes",
+            self.id,
+            self.offset,
+            self.data.len()
+        )?;
+        let mut position = 0;
+        for annotation in &self.annotations {
+            if annotation.offset > position {
+                self.write_rows(f, position, annotation.offset - position, "")?;
+            }
+            self.write_rows(f, annotation.offset, annotation.len, &annotation.label)?;
+            position = annotation.offset + annotation.len;
+        }
+        if position < self.data.len() {
+            self.write_rows(f, position, self.data.len() - position, "")?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Reads a chunk for display as an annotated hex dump
+    pub fn hex_dump(&mut self, id: Id) -> Result<HexDump> {
+        let data = self.read_raw(id)?;
+        let offset = self.index[&id].position;
+        Ok(HexDump::new(id, offset, data))
+    }
+}
+
+/// Labels the fields of known chunks
+fn annotate(id: Id, data: &[u8]) -> Vec<Annotation> {
+    let mut fields = Fields {
+        data,
+        position: 0,
+        annotations: Vec::new(),
+    };
+    match id {
+        Id::ATOM | Id::AT_U8 => fields.atoms(),
+        Id::CODE => fields.code_header(),
+        Id::IMP_T => fields.table("import", &["module", "function", "arity"], 2),
+        Id::EXP_T => fields.table("export", &["function", "arity", "label"], 1),
+        Id::LOC_T => fields.table("local", &["function", "arity", "label"], 1),
+        Id::FUN_T => fields.table(
+            "fun",
+            &["function", "arity", "label", "index", "free", "old uniq"],
+            1,
+        ),
+        Id::LIT_T => fields.u32("uncompressed size").map(|_| ()),
+        _ => None,
+    };
+    fields.annotations
+}
+
+/// Walks the data of a chunk, stopping at the first field that doesn't fit
+struct Fields<'a> {
+    data: &'a [u8],
+    position: usize,
+    annotations: Vec<Annotation>,
+}
+
+impl Fields<'_> {
+    fn field(&mut self, len: usize, label: String) -> Option<()> {
+        self.data.get(self.position..)?.get(..len)?;
+        self.annotations.push(Annotation {
+            offset: self.position,
+            len,
+            label,
+        });
+        self.position += len;
+        Some(())
+    }
+
+    fn peek_u32(&self) -> Option<u32> {
+        let bytes = self.data.get(self.position..)?.get(..4)?;
+        Some(BigEndian::read_u32(bytes))
+    }
+
+    fn u32(&mut self, name: &str) -> Option<u32> {
+        let value = self.peek_u32()?;
+        self.field(4, format!("{} = {}", name, value))?;
+        Some(value)
+    }
+
+    fn atoms(&mut self) -> Option<()> {
+        let count = self.u32("atom count")?;
+        for index in 1..=count {
+            let len = *self.data.get(self.position)? as usize;
+            let name = self.data.get(self.position + 1..)?.get(..len)?;
+            let label = format!("atom {} = {}", index, String::from_utf8_lossy(name));
+            self.field(1 + len, label)?;
+        }
+        Some(())
+    }
+
+    fn code_header(&mut self) -> Option<()> {
+        self.u32("header size")?;
+        self.u32("instruction set")?;
+        self.u32("max opcode")?;
+        self.u32("label count")?;
+        self.u32("function count")?;
+        Some(())
+    }
+
+    /// A count followed by entries of `u32` fields, the first `atoms` of them atom indices
+    fn table(&mut self, entry: &str, names: &[&str], atoms: usize) -> Option<()> {
+        let count = self.u32(&format!("{} count", entry))?;
+        for index in 0..count {
+            let mut values = Vec::with_capacity(names.len());
+            for (position, name) in names.iter().enumerate() {
+                let field = self.position + 4 * position;
+                let value = BigEndian::read_u32(self.data.get(field..)?.get(..4)?);
+                match position < atoms {
+                    true => values.push(format!("{} = atom {}", name, value)),
+                    false => values.push(format!("{} = {}", name, value)),
+                }
+            }
+            let label = format!("{} {}: {}", entry, index, values.join(", "));
+            self.field(4 * names.len(), label)?;
+        }
+        Some(())
+    }
+}
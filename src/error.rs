@@ -0,0 +1,234 @@
+use alloc::boxed::Box;
+use core::{error::Error as StdError, fmt, str};
+#[cfg(feature = "std")]
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use crate::Id;
+
+/// Errors from reading BEAM files
+///
+/// New variants may be added at any time, use `kind` to match on classes of
+/// failures.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum BeamFileError {
+    #[error("Unexpected magic number {0:?}, expected b\"FOR1\"")]
+    UnexpectedMagicNumber(Id),
+
+    #[error("Unexpected form type {0:?}, expected b\"BEAM\"")]
+    UnexpectedFormType(Id),
+
+    #[error("Payload size {declared} exceeds the {available} bytes available")]
+    InvalidPayloadSize { declared: u64, available: u64 },
+
+    #[error("Chunk {chunk:?} at {position} with length {len} extends past the end of the payload")]
+    ChunkOutOfBounds { chunk: Id, position: u64, len: u64 },
+
+    #[error("{0} bytes of unexpected data after the payload")]
+    TrailingData(u64),
+
+    #[error("Chunk {0:?} appears more than once")]
+    DuplicateChunk(Id),
+
+    #[error("Chunk {0:?} not found")]
+    MissingChunk(Id),
+
+    #[error("Atoms weren't indexed, call `index_atoms` first")]
+    AtomsNotIndexed,
+
+    #[error("Invalid atom index {index} in chunk {chunk:?}")]
+    InvalidAtomIndex { chunk: Id, index: u32 },
+
+    #[error("Malformed chunk {chunk:?}: {reason}")]
+    MalformedChunk { chunk: Id, reason: &'static str },
+
+    #[error("Invalid term: {0}")]
+    InvalidTerm(&'static str),
+
+    #[error("{limit} {value} exceeds the limit of {max}")]
+    LimitExceeded {
+        limit: &'static str,
+        value: u64,
+        max: u64,
+    },
+
+    /// The data ended early, when parsing from memory with `BeamSlice` or
+    /// between the forms of a stream read with `Forms`
+    #[error("Unexpected end of data at offset {0}")]
+    UnexpectedEof(u64),
+
+    /// A summary passed to `ModuleSummary::from_bytes` couldn't be loaded
+    #[cfg(feature = "cache")]
+    #[error("Invalid cached summary: {0}")]
+    InvalidCache(&'static str),
+
+    #[error("Invalid atom")]
+    InvalidAtom(#[from] str::Utf8Error),
+
+    #[error("Failed to intern atom")]
+    InternFailed(#[source] Box<dyn StdError + Send + Sync>),
+
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("{context}: {source}")]
+    WithContext {
+        context: ErrorContext,
+        #[source]
+        source: Box<BeamFileError>,
+    },
+}
+
+pub type Result<T> = core::result::Result<T, BeamFileError>;
+
+/// Error parsing an `Id` from a string that isn't 4 bytes long
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Chunk ids are 4 bytes long")]
+pub struct ParseIdError;
+
+/// Class of a `BeamFileError`
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The IFF container or chunk layout is invalid, or a chunk is missing
+    Container,
+    /// The contents of a chunk couldn't be decoded
+    ChunkFormat,
+    /// Atoms couldn't be decoded, interned or resolved
+    Atoms,
+    /// Reading the underlying file failed
+    Io,
+    /// A configured resource limit was exceeded
+    Limits,
+}
+
+/// Where in a file an error occurred
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Path of the file, when opened from the file system
+    #[cfg(feature = "std")]
+    pub path: Option<PathBuf>,
+    pub chunk: Option<Id>,
+    /// Absolute byte offset in the file
+    pub offset: Option<u64>,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut separator = "";
+        #[cfg(feature = "std")]
+        if let Some(path) = &self.path {
+            write!(f, "{}", path.display())?;
+            separator = ", ";
+        }
+        if let Some(chunk) = &self.chunk {
+            write!(f, "{}chunk {:?}", separator, chunk)?;
+            separator = ", ";
+        }
+        if let Some(offset) = &self.offset {
+            write!(f, "{}offset {}", separator, offset)?;
+        }
+        Ok(())
+    }
+}
+
+impl BeamFileError {
+    /// Returns the class of the error, looking through any context
+    pub fn kind(&self) -> ErrorKind {
+        match self.root() {
+            BeamFileError::UnexpectedMagicNumber(_)
+            | BeamFileError::UnexpectedFormType(_)
+            | BeamFileError::InvalidPayloadSize { .. }
+            | BeamFileError::ChunkOutOfBounds { .. }
+            | BeamFileError::TrailingData(_)
+            | BeamFileError::DuplicateChunk(_)
+            | BeamFileError::MissingChunk(_)
+            | BeamFileError::UnexpectedEof(_) => ErrorKind::Container,
+            BeamFileError::MalformedChunk { .. } | BeamFileError::InvalidTerm(_) => {
+                ErrorKind::ChunkFormat
+            }
+            #[cfg(feature = "cache")]
+            BeamFileError::InvalidCache(_) => ErrorKind::ChunkFormat,
+            BeamFileError::AtomsNotIndexed
+            | BeamFileError::InvalidAtomIndex { .. }
+            | BeamFileError::InvalidAtom(_)
+            | BeamFileError::InternFailed(_) => ErrorKind::Atoms,
+            BeamFileError::LimitExceeded { .. } => ErrorKind::Limits,
+            #[cfg(feature = "std")]
+            BeamFileError::Io(_) => ErrorKind::Io,
+            BeamFileError::WithContext { .. } => unreachable!("root has no context"),
+        }
+    }
+
+    /// Returns the location of the error, if known
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            BeamFileError::WithContext { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying error without its context
+    pub fn root(&self) -> &BeamFileError {
+        match self {
+            BeamFileError::WithContext { source, .. } => source.root(),
+            err => err,
+        }
+    }
+
+    /// Converts into the underlying error, discarding its context
+    pub fn into_root(self) -> BeamFileError {
+        match self {
+            BeamFileError::WithContext { source, .. } => source.into_root(),
+            err => err,
+        }
+    }
+
+    /// Attaches the chunk and offset, unless already known
+    pub(crate) fn in_chunk(self, chunk: Id, offset: u64) -> Self {
+        self.with_context(|context| {
+            context.chunk.get_or_insert(chunk);
+            context.offset.get_or_insert(offset);
+        })
+    }
+
+    /// Attaches the path of the file, unless already known
+    #[cfg(feature = "std")]
+    pub(crate) fn in_file(self, path: Option<&Path>) -> Self {
+        match path {
+            Some(path) => self.with_context(|context| {
+                context.path.get_or_insert_with(|| path.to_path_buf());
+            }),
+            None => self,
+        }
+    }
+
+    // Errors identifying their cause on their own stay unwrapped,
+    // so they can be matched on directly.
+    fn with_context(self, update: impl FnOnce(&mut ErrorContext)) -> Self {
+        match self {
+            BeamFileError::MissingChunk(_) | BeamFileError::AtomsNotIndexed => self,
+            BeamFileError::WithContext {
+                mut context,
+                source,
+            } => {
+                update(&mut context);
+                BeamFileError::WithContext { context, source }
+            }
+            err => {
+                let mut context = ErrorContext::default();
+                update(&mut context);
+                BeamFileError::WithContext {
+                    context,
+                    source: Box::new(err),
+                }
+            }
+        }
+    }
+}
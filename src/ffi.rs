@@ -0,0 +1,240 @@
+//! C interface, headers can be generated with `cbindgen`
+//!
+//! Build a library to link against with
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`).
+//!
+//! Files are parsed eagerly when opened, so queries can't fail. Strings and
+//! arrays returned by the functions are owned by the `FbBeamFile` and remain
+//! valid until it's freed with `fast_beam_free`.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CString},
+    ptr, slice,
+};
+
+use fxhash::FxHashMap;
+
+use crate::{BeamFile, Result};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An opened BEAM file
+pub struct FbBeamFile {
+    atoms: Vec<CString>,
+    chunks: Vec<FbChunk>,
+    exports: Vec<FbExport>,
+    imports: Vec<FbImport>,
+}
+
+/// A chunk with its offset and size in bytes
+#[repr(C)]
+pub struct FbChunk {
+    /// The 4 bytes of the id, not nul-terminated
+    pub id: [u8; 4],
+    pub offset: u64,
+    pub size: u64,
+}
+
+#[repr(C)]
+pub struct FbExport {
+    pub function: *const c_char,
+    pub arity: u32,
+    pub label: u32,
+}
+
+#[repr(C)]
+pub struct FbImport {
+    pub module: *const c_char,
+    pub function: *const c_char,
+    pub arity: u32,
+}
+
+impl FbBeamFile {
+    fn open(data: &[u8]) -> Result<Self> {
+        let mut file = BeamFile::from_slice(data)?;
+        let names = file.owned_atoms()?;
+        let atoms: Vec<_> = names.iter().map(|name| c_string(name)).collect();
+        let mut positions = FxHashMap::default();
+        for (position, name) in names.iter().enumerate() {
+            positions.entry(name.as_str()).or_insert(position);
+        }
+        let atom = |name: &str| {
+            positions
+                .get(name)
+                .map_or(ptr::null(), |&position| atoms[position].as_ptr())
+        };
+
+        let chunks = file
+            .chunks()
+            .map(|(id, offset, size)| FbChunk {
+                id: id.0,
+                offset,
+                size,
+            })
+            .collect();
+        let exports = file
+            .owned_exports(&names)?
+            .into_iter()
+            .map(|export| FbExport {
+                function: atom(&export.function),
+                arity: export.arity,
+                label: export.label,
+            })
+            .collect();
+        let imports = file
+            .owned_imports(&names)?
+            .into_iter()
+            .map(|import| FbImport {
+                module: atom(&import.module),
+                function: atom(&import.function),
+                arity: import.arity,
+            })
+            .collect();
+
+        Ok(FbBeamFile {
+            atoms,
+            chunks,
+            exports,
+            imports,
+        })
+    }
+}
+
+/// Atoms can't contain nul bytes in practice, anything after one is dropped
+fn c_string(name: &str) -> CString {
+    let name = name.split('\0').next().unwrap_or_default();
+    CString::new(name).unwrap_or_default()
+}
+
+/// Parses `len` bytes at `data`, returns null on error
+///
+/// The data is only read during the call. The message of the error is
+/// available from `fast_beam_last_error`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_open(data: *const u8, len: usize) -> *mut FbBeamFile {
+    let data = match data.is_null() {
+        true => &[],
+        false => slice::from_raw_parts(data, len),
+    };
+    match FbBeamFile::open(data) {
+        Ok(file) => Box::into_raw(Box::new(file)),
+        Err(err) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = Some(c_string(&err.to_string())));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The message of the last error on this thread, null if there was none
+///
+/// The message is valid until the next failing call on this thread.
+#[no_mangle]
+pub extern "C" fn fast_beam_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |err| err.as_ptr())
+    })
+}
+
+/// Frees a file returned by `fast_beam_open`, null is ignored
+///
+/// # Safety
+///
+/// `file` must be null or returned by `fast_beam_open` and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_free(file: *mut FbBeamFile) {
+    if !file.is_null() {
+        drop(Box::from_raw(file));
+    }
+}
+
+/// The module name, null if the atom table is empty
+///
+/// # Safety
+///
+/// `file` must be a valid file returned by `fast_beam_open`.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_name(file: *const FbBeamFile) -> *const c_char {
+    let file = &*file;
+    file.atoms.first().map_or(ptr::null(), |name| name.as_ptr())
+}
+
+/// The atom at `index`, null if out of bounds
+///
+/// # Safety
+///
+/// `file` must be a valid file returned by `fast_beam_open`.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_atom(file: *const FbBeamFile, index: usize) -> *const c_char {
+    let file = &*file;
+    file.atoms
+        .get(index)
+        .map_or(ptr::null(), |atom| atom.as_ptr())
+}
+
+/// The number of atoms
+///
+/// # Safety
+///
+/// `file` must be a valid file returned by `fast_beam_open`.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_atom_count(file: *const FbBeamFile) -> usize {
+    let file = &*file;
+    file.atoms.len()
+}
+
+/// Chunks in the order they appear in the file, the count is written to `len`
+///
+/// # Safety
+///
+/// `file` must be a valid file returned by `fast_beam_open`, `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_chunks(
+    file: *const FbBeamFile,
+    len: *mut usize,
+) -> *const FbChunk {
+    let file = &*file;
+    *len = file.chunks.len();
+    file.chunks.as_ptr()
+}
+
+/// Exported functions, the count is written to `len`
+///
+/// # Safety
+///
+/// `file` must be a valid file returned by `fast_beam_open`, `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_exports(
+    file: *const FbBeamFile,
+    len: *mut usize,
+) -> *const FbExport {
+    let file = &*file;
+    *len = file.exports.len();
+    file.exports.as_ptr()
+}
+
+/// Imported functions, the count is written to `len`
+///
+/// # Safety
+///
+/// `file` must be a valid file returned by `fast_beam_open`, `len` must be
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn fast_beam_imports(
+    file: *const FbBeamFile,
+    len: *mut usize,
+) -> *const FbImport {
+    let file = &*file;
+    *len = file.imports.len();
+    file.imports.as_ptr()
+}
@@ -0,0 +1,369 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Read, Seek},
+};
+
+use crate::{
+    code::{op, LinkedCode},
+    BeamFile, FunctionCode, Id, Import, Instruction, InternerMut, Operand, Result, Term,
+};
+
+/// A stable 64-bit hash, such as of the code of a function or of a literal
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fingerprint(pub u64);
+
+/// A stable 128-bit hash of the contents of a chunk, see `BeamFile::chunk_hashes`
+///
+/// Displays as 32 hex digits, which is also how it's serialized to
+/// human-readable formats.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ChunkHash(pub u128);
+
+impl fmt::Display for ChunkHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// Hashes of the chunks of a file, see `BeamFile::chunk_hashes`
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkHashes {
+    /// Chunks in file order
+    pub chunks: Vec<(Id, ChunkHash)>,
+}
+
+impl ChunkHashes {
+    pub fn get(&self, id: Id) -> Option<ChunkHash> {
+        self.chunks
+            .iter()
+            .find(|(chunk, _)| *chunk == id)
+            .map(|(_, hash)| *hash)
+    }
+
+    /// Hash of all chunks but those in `exclude`
+    ///
+    /// Chunks are combined in the order of their ids, so moving chunks
+    /// around in the file doesn't change the result. Excluding `Dbgi` and
+    /// `Docs`, for example, gives a hash that only changes with the code.
+    pub fn combined(&self, exclude: &[Id]) -> ChunkHash {
+        let mut chunks: Vec<_> = self
+            .chunks
+            .iter()
+            .filter(|(id, _)| !exclude.contains(id))
+            .collect();
+        chunks.sort_by_key(|(id, _)| *id);
+        let mut hash = Fnv128::default();
+        for (id, chunk) in chunks {
+            hash.write(&id.0);
+            hash.write(&chunk.0.to_le_bytes());
+        }
+        ChunkHash(hash.0)
+    }
+
+    /// Chunks added, removed or changed compared to `previous`, in the order of their ids
+    pub fn changed(&self, previous: &ChunkHashes) -> Vec<Id> {
+        let mut ids: Vec<_> = self
+            .chunks
+            .iter()
+            .chain(&previous.chunks)
+            .map(|(id, _)| *id)
+            .filter(|&id| self.get(id) != previous.get(id))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+}
+
+/// A function along with the fingerprint of its code
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionFingerprint {
+    pub name: String,
+    pub arity: u32,
+    pub fingerprint: Fingerprint,
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Fingerprints the code of each function, in the order they're defined
+    ///
+    /// The fingerprint only changes when the function does: labels are
+    /// numbered from the start of the function, calls to local functions and
+    /// imports are hashed by name, and literals by value. Line information is
+    /// left out, so functions moved in the source keep their fingerprint.
+    /// Indices into the fun table are hashed as they are.
+    ///
+    /// Fingerprints use FNV-1a, so they can be stored and compared across
+    /// builds and platforms.
+    pub fn function_fingerprints(&mut self) -> Result<Vec<FunctionFingerprint>> {
+        let atoms = self.owned_atoms()?;
+        let LinkedCode {
+            code,
+            imports,
+            literals,
+        } = self.read_in_context_with_atoms::<LinkedCode<String>>(&atoms)?;
+
+        let functions = code.functions();
+        let mut owners = HashMap::new();
+        for (index, function) in functions.iter().enumerate() {
+            for label in function.instructions.iter().filter_map(defined_label) {
+                owners.insert(label, index);
+            }
+        }
+
+        let fingerprints = functions
+            .iter()
+            .map(|function| {
+                let mut hasher = FunctionHasher {
+                    hash: Fnv::default(),
+                    functions: &functions,
+                    owners: &owners,
+                    labels: HashMap::new(),
+                    imports: &imports,
+                    literals: &literals,
+                };
+                for label in function.instructions.iter().filter_map(defined_label) {
+                    let next = hasher.labels.len() as u64;
+                    hasher.labels.insert(label, next);
+                }
+                for instruction in function.instructions {
+                    hasher.instruction(instruction);
+                }
+                FunctionFingerprint {
+                    name: function.name.clone(),
+                    arity: function.arity,
+                    fingerprint: Fingerprint(hasher.hash.0),
+                }
+            })
+            .collect();
+        Ok(fingerprints)
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Hashes the contents of each chunk, reading the file sequentially
+    ///
+    /// Hashes use the 128-bit FNV-1a, so they can be stored and compared
+    /// across builds and platforms, and accidental collisions are unlikely
+    /// even among many files. FNV isn't a cryptographic hash though: chunks
+    /// with the same hash are easy to craft, so the hashes must not be used
+    /// as cache keys for files that may not be trusted.
+    pub fn chunk_hashes(&mut self) -> Result<ChunkHashes> {
+        let chunks = self
+            .iter_raw()
+            .map(|(id, data)| {
+                let mut hash = Fnv128::default();
+                hash.write(&data?);
+                Ok((id, ChunkHash(hash.0)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(ChunkHashes { chunks })
+    }
+}
+
+/// Fingerprints a term by its text, which is also what's hashed for literals in code
+pub(crate) fn term_fingerprint(text: &str) -> Fingerprint {
+    let mut hash = Fnv::default();
+    hash.write_str(text);
+    Fingerprint(hash.0)
+}
+
+/// Label defined by a `label` instruction
+fn defined_label(instruction: &Instruction<String>) -> Option<u32> {
+    match (instruction.opcode, instruction.args.first()) {
+        (op::LABEL, Some(Operand::Unsigned(label))) => Some(*label as u32),
+        _ => None,
+    }
+}
+
+/// The 64-bit FNV-1a hash, which unlike `Hash` is stable across platforms
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_u64(value.len() as u64);
+        self.write(value.as_bytes());
+    }
+}
+
+/// The 128-bit FNV-1a hash, for chunks where 64 bits would collide too easily
+struct Fnv128(u128);
+
+impl Default for Fnv128 {
+    fn default() -> Self {
+        Fnv128(0x6c62_272e_07bb_0142_62b8_2175_6295_c58d)
+    }
+}
+
+impl Fnv128 {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 =
+                (self.0 ^ byte as u128).wrapping_mul(0x0000_0000_0100_0000_0000_0000_0000_013b);
+        }
+    }
+}
+
+struct FunctionHasher<'a> {
+    hash: Fnv,
+    functions: &'a [FunctionCode<'a, String>],
+    /// Function defining each label of the module
+    owners: &'a HashMap<u32, usize>,
+    /// Labels of the function being hashed, numbered in order of definition
+    labels: HashMap<u32, u64>,
+    imports: &'a [Import<String>],
+    literals: &'a [Term],
+}
+
+impl FunctionHasher<'_> {
+    fn instruction(&mut self, instruction: &Instruction<String>) {
+        self.hash.write(&[instruction.opcode]);
+        match instruction.opcode {
+            op::LINE => return,
+            op::LABEL => {
+                if let Some(label) = defined_label(instruction) {
+                    self.label(label);
+                    return;
+                }
+            }
+            _ => {}
+        }
+        let import = instruction.import_position();
+        for (i, arg) in instruction.args.iter().enumerate() {
+            match (arg, import) {
+                (Operand::Unsigned(index), Some(position)) if position == i => {
+                    match self.imports.get(*index as usize) {
+                        Some(import) => {
+                            self.hash.write(b"i");
+                            self.hash.write_str(&import.module);
+                            self.hash.write_str(&import.function);
+                            self.hash.write_u64(import.arity as u64);
+                        }
+                        None => self.operand(arg),
+                    }
+                }
+                _ => self.operand(arg),
+            }
+        }
+    }
+
+    fn operand(&mut self, operand: &Operand<String>) {
+        match operand {
+            Operand::Unsigned(value) => {
+                self.hash.write(b"u");
+                self.hash.write_u64(*value);
+            }
+            Operand::Integer(value) => {
+                self.hash.write(b"n");
+                self.hash.write_u64(*value as u64);
+            }
+            Operand::BigInteger(bytes) => {
+                self.hash.write(b"b");
+                self.hash.write_u64(bytes.len() as u64);
+                self.hash.write(bytes);
+            }
+            Operand::Atom(name) => {
+                self.hash.write(b"a");
+                self.hash.write_str(name);
+            }
+            Operand::Nil => self.hash.write(b"0"),
+            Operand::X(register) => {
+                self.hash.write(b"x");
+                self.hash.write_u64(*register as u64);
+            }
+            Operand::Y(register) => {
+                self.hash.write(b"y");
+                self.hash.write_u64(*register as u64);
+            }
+            Operand::Label(label) => self.label(*label),
+            Operand::Char(value) => {
+                self.hash.write(b"c");
+                self.hash.write_u64(*value as u64);
+            }
+            Operand::FloatRegister(register) => {
+                self.hash.write(b"r");
+                self.hash.write_u64(*register as u64);
+            }
+            Operand::Literal(index) => match self.literals.get(*index as usize) {
+                Some(literal) => {
+                    self.hash.write(b"t");
+                    self.hash.write_str(&literal.to_string());
+                }
+                None => {
+                    self.hash.write(b"l");
+                    self.hash.write_u64(*index as u64);
+                }
+            },
+            Operand::List(operands) => {
+                self.hash.write(b"[");
+                self.hash.write_u64(operands.len() as u64);
+                for operand in operands {
+                    self.operand(operand);
+                }
+            }
+            Operand::AllocList(entries) => {
+                self.hash.write(b"{");
+                self.hash.write_u64(entries.len() as u64);
+                for (kind, count) in entries {
+                    self.hash.write_u64(*kind as u64);
+                    self.hash.write_u64(*count);
+                }
+            }
+            Operand::TypedRegister {
+                register,
+                type_index,
+            } => {
+                self.hash.write(b"T");
+                self.hash.write_u64(*type_index as u64);
+                self.operand(register);
+            }
+            Operand::Float(value) => {
+                self.hash.write(b"f");
+                self.hash.write_u64(value.to_bits());
+            }
+        }
+    }
+
+    /// Hashes labels of the function by position, others by the function they're in
+    fn label(&mut self, label: u32) {
+        if let Some(local) = self.labels.get(&label) {
+            self.hash.write(b"L");
+            self.hash.write_u64(*local);
+            return;
+        }
+        match self.owners.get(&label).map(|&index| &self.functions[index]) {
+            Some(function) => {
+                self.hash.write(b"F");
+                self.hash.write_str(function.name);
+                self.hash.write_u64(function.arity as u64);
+                self.hash.write_u64(match function.label {
+                    Some(entry) => (entry != label) as u64,
+                    None => 1,
+                });
+            }
+            None => {
+                self.hash.write(b"?");
+                self.hash.write_u64(label as u64);
+            }
+        }
+    }
+}
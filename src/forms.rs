@@ -0,0 +1,109 @@
+use std::io::{self, Cursor, Read};
+
+use crate::{chunk::cautious_capacity, BeamFile, BeamFileError, BeamFileOptions, Limits, Result};
+
+/// Iterator over BEAM files stored back to back in a stream
+///
+/// Some packing tools, and tools passing modules through pipes, write
+/// several `FOR1` forms one after another. Each form is read into memory
+/// and indexed on its own, so offsets reported by a `BeamFile` are relative
+/// to the start of its form, see `Forms::offset`. The stream only needs to
+/// implement `Read`.
+///
+/// Iteration stops at the end of the stream, or after the first error.
+pub struct Forms<R> {
+    reader: R,
+    options: BeamFileOptions,
+    offset: u64,
+    done: bool,
+}
+
+impl<R: Read> Forms<R> {
+    pub fn new(reader: R) -> Self {
+        Self::with_options(reader, &BeamFileOptions::default())
+    }
+
+    pub(crate) fn with_options(reader: R, options: &BeamFileOptions) -> Self {
+        Forms {
+            reader,
+            options: options.clone(),
+            offset: 0,
+            done: false,
+        }
+    }
+
+    /// Position of the next form in the stream
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the underlying stream, positioned after the last form read
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_form(&mut self) -> Result<Option<BeamFile<Cursor<Vec<u8>>>>> {
+        let mut header = [0; 8];
+        let header_len = read_full(&mut self.reader, &mut header)?;
+        match header_len {
+            0 => return Ok(None),
+            8 => {}
+            _ => {
+                return Err(BeamFileError::UnexpectedEof(
+                    self.offset + header_len as u64,
+                ))
+            }
+        }
+        if header[0..4] != *b"FOR1" {
+            let mut magic_number = [0; 4];
+            magic_number.copy_from_slice(&header[0..4]);
+            return Err(BeamFileError::UnexpectedMagicNumber(magic_number.into()));
+        }
+        let payload_size = u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        let limits = &self.options.limits;
+        Limits::check("Total chunk size", payload_size, limits.max_total_size)?;
+
+        let mut data = Vec::with_capacity(8 + cautious_capacity::<u8>(payload_size as usize));
+        data.extend_from_slice(&header);
+        (&mut self.reader)
+            .take(payload_size)
+            .read_to_end(&mut data)?;
+        let available = data.len() as u64 - 8;
+        if available < payload_size {
+            return Err(BeamFileError::InvalidPayloadSize {
+                declared: payload_size,
+                available,
+            });
+        }
+
+        self.offset += data.len() as u64;
+        BeamFile::parse(Cursor::new(data), &self.options).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for Forms<R> {
+    type Item = Result<BeamFile<Cursor<Vec<u8>>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.read_form().transpose();
+        self.done = !matches!(result, Some(Ok(_)));
+        result
+    }
+}
+
+/// Fills `buffer` unless the stream ends first, returning the bytes read
+fn read_full<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buffer.len() {
+        match reader.read(&mut buffer[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
@@ -0,0 +1,86 @@
+use arbitrary::{Arbitrary, Unstructured};
+
+/// A randomly generated, well-formed BEAM file
+///
+/// Contains the chunks required by `BeamFile::verify` with consistent atom
+/// references and labels, so analyses can be fuzzed past the parser. The
+/// contents of the code itself are random.
+#[derive(Clone, Debug)]
+pub struct ArbitraryBeam(pub Vec<u8>);
+
+impl<'a> Arbitrary<'a> for ArbitraryBeam {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let atom_count = u.int_in_range(1..=32u32)?;
+        let label_count = u.int_in_range(2..=64u32)?;
+
+        let mut atoms = atom_count.to_be_bytes().to_vec();
+        for _ in 0..atom_count {
+            let len = u.int_in_range(1..=16u8)?;
+            atoms.push(len);
+            for _ in 0..len {
+                atoms.push(u.int_in_range(b'a'..=b'z')?);
+            }
+        }
+
+        let mut code = Vec::new();
+        for field in &[16, 0, u.int_in_range(1..=200)?, label_count] {
+            code.extend_from_slice(&u32::to_be_bytes(*field));
+        }
+        code.extend_from_slice(&u.int_in_range(0..=label_count)?.to_be_bytes());
+        let code_len = u.int_in_range(0..=64)?;
+        code.extend_from_slice(u.bytes(code_len)?);
+
+        let mut imports = Vec::new();
+        let import_count = u.int_in_range(0..=8u32)?;
+        imports.extend_from_slice(&import_count.to_be_bytes());
+        for _ in 0..import_count {
+            for field in &[
+                u.int_in_range(1..=atom_count)?,
+                u.int_in_range(1..=atom_count)?,
+                u.int_in_range(0..=255)?,
+            ] {
+                imports.extend_from_slice(&u32::to_be_bytes(*field));
+            }
+        }
+
+        let mut functions = || -> arbitrary::Result<Vec<u8>> {
+            let count = u.int_in_range(0..=8u32)?;
+            let mut data = count.to_be_bytes().to_vec();
+            for _ in 0..count {
+                for field in &[
+                    u.int_in_range(1..=atom_count)?,
+                    u.int_in_range(0..=255)?,
+                    u.int_in_range(1..=label_count - 1)?,
+                ] {
+                    data.extend_from_slice(&u32::to_be_bytes(*field));
+                }
+            }
+            Ok(data)
+        };
+        let exports = functions()?;
+        let locals = functions()?;
+
+        let strings_len = u.int_in_range(0..=32)?;
+        let strings = u.bytes(strings_len)?.to_vec();
+
+        let mut payload = b"BEAM".to_vec();
+        for (id, data) in &[
+            (b"AtU8", atoms),
+            (b"Code", code),
+            (b"StrT", strings),
+            (b"ImpT", imports),
+            (b"ExpT", exports),
+            (b"LocT", locals),
+        ] {
+            payload.extend_from_slice(&id[..]);
+            payload.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            payload.extend_from_slice(data);
+            payload.resize(4 * payload.len().div_ceil(4), 0);
+        }
+
+        let mut file = b"FOR1".to_vec();
+        file.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        file.extend_from_slice(&payload);
+        Ok(ArbitraryBeam(file))
+    }
+}
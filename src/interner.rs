@@ -0,0 +1,324 @@
+use std::{
+    convert::Infallible,
+    error::Error,
+    hash::{Hash, Hasher},
+    str,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use fxhash::{FxHashSet, FxHasher};
+
+#[cfg(feature = "lasso")]
+mod lasso;
+#[cfg(feature = "string-interner")]
+mod string_interner;
+mod symbol;
+#[cfg(feature = "ustr")]
+mod ustr;
+
+#[cfg(feature = "string-interner")]
+pub use self::string_interner::SyncStringInterner;
+pub use self::symbol::{Symbol, SymbolTable};
+#[cfg(feature = "ustr")]
+pub use self::ustr::UstrInterner;
+
+/// String interner used for efficiently reading atoms
+pub trait Interner {
+    type Atom;
+
+    /// Intern an atom
+    fn intern(&self, name: &str) -> Self::Atom;
+
+    /// Efficiently intern many atoms at once.
+    ///
+    /// Can be useful to avoid repeated locking-unlocking when interning
+    /// in a tight loop
+    fn intern_many<'a>(&self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        iter.map(|name| self.intern(name)).collect()
+    }
+
+    /// Intern an atom whose name isn't valid UTF-8
+    ///
+    /// Called for atoms that fail UTF-8 validation, e.g. Latin-1 names from
+    /// the legacy `Atom` chunk. Returning `None` fails the atom indexing,
+    /// which is the default.
+    fn intern_bytes(&self, _name: &[u8]) -> Option<Self::Atom> {
+        None
+    }
+}
+
+/// String interner that needs exclusive access for interning
+///
+/// Lets simple single-threaded interners (e.g. a plain hash map) avoid interior mutability.
+/// Every `Interner` is also an `InternerMut`.
+pub trait InternerMut {
+    type Atom;
+
+    /// Intern an atom
+    fn intern_mut(&mut self, name: &str) -> Self::Atom;
+
+    /// Intern many atoms at once
+    fn intern_many_mut<'a>(&mut self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        iter.map(|name| self.intern_mut(name)).collect()
+    }
+
+    /// Intern an atom whose name isn't valid UTF-8
+    ///
+    /// See `Interner::intern_bytes`.
+    fn intern_bytes_mut(&mut self, _name: &[u8]) -> Option<Self::Atom> {
+        None
+    }
+}
+
+impl<T: Interner> InternerMut for T {
+    type Atom = T::Atom;
+
+    fn intern_mut(&mut self, name: &str) -> Self::Atom {
+        self.intern(name)
+    }
+
+    fn intern_many_mut<'a>(&mut self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        self.intern_many(iter)
+    }
+
+    fn intern_bytes_mut(&mut self, name: &[u8]) -> Option<Self::Atom> {
+        self.intern_bytes(name)
+    }
+}
+
+/// String interner that may fail to intern an atom
+///
+/// Useful for interners with capacity limits, validation, or FFI-backed tables.
+/// Every `Interner` is also an infallible `TryInterner`.
+pub trait TryInterner {
+    type Atom;
+    type Error: Error + Send + Sync + 'static;
+
+    /// Try to intern an atom
+    fn try_intern(&self, name: &str) -> Result<Self::Atom, Self::Error>;
+
+    /// Try to intern many atoms at once, stopping at the first failure
+    fn try_intern_many<'a>(
+        &self,
+        iter: impl Iterator<Item = &'a str>,
+    ) -> Result<Vec<Self::Atom>, Self::Error> {
+        iter.map(|name| self.try_intern(name)).collect()
+    }
+
+    /// Try to intern an atom whose name isn't valid UTF-8
+    ///
+    /// See `Interner::intern_bytes`. Returning `None` leaves the atom to
+    /// `BeamFileOptions::invalid_atoms`, which is the default.
+    fn try_intern_bytes(&self, _name: &[u8]) -> Option<Result<Self::Atom, Self::Error>> {
+        None
+    }
+}
+
+impl<T: Interner> TryInterner for T {
+    type Atom = T::Atom;
+    type Error = Infallible;
+
+    fn try_intern(&self, name: &str) -> Result<Self::Atom, Self::Error> {
+        Ok(self.intern(name))
+    }
+
+    fn try_intern_many<'a>(
+        &self,
+        iter: impl Iterator<Item = &'a str>,
+    ) -> Result<Vec<Self::Atom>, Self::Error> {
+        Ok(self.intern_many(iter))
+    }
+
+    fn try_intern_bytes(&self, name: &[u8]) -> Option<Result<Self::Atom, Self::Error>> {
+        self.intern_bytes(name).map(Ok)
+    }
+}
+
+/// Reverse lookup for atoms produced by an `Interner`
+///
+/// Needed by anything turning atoms back into text, e.g. encoding or printing.
+pub trait Resolver: Interner {
+    /// Returns the name of an interned atom
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str;
+}
+
+/// Interner whose atoms may borrow from the atom chunk buffer
+///
+/// Used with `RawAtoms::intern_borrowed` to decode chunks without allocating atom names.
+pub trait BorrowingInterner {
+    type Atom<'a>;
+
+    /// Intern an atom borrowed from the atom chunk
+    fn intern_borrowed<'a>(&self, name: &'a str) -> Self::Atom<'a>;
+}
+
+/// A borrowing interner that uses the names from the atom chunk as-is
+#[derive(Default, Clone, Copy)]
+pub struct StrInterner;
+
+impl BorrowingInterner for StrInterner {
+    type Atom<'a> = &'a str;
+
+    fn intern_borrowed<'a>(&self, name: &'a str) -> Self::Atom<'a> {
+        name
+    }
+}
+
+/// A "naive" interner that just allocates the string
+#[derive(Default)]
+pub struct NaiveInterner;
+
+impl Interner for NaiveInterner {
+    type Atom = String;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        name.to_string()
+    }
+}
+
+impl Resolver for NaiveInterner {
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        atom
+    }
+}
+
+/// A deduplicating interner handing out shared `Arc<str>` atoms
+///
+/// Interning the same name twice returns pointers to the same allocation,
+/// so atoms are cheap to clone and can be compared with `Arc::ptr_eq`.
+#[derive(Default)]
+pub struct ArcInterner {
+    atoms: Mutex<FxHashSet<Arc<str>>>,
+}
+
+impl ArcInterner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct atoms interned so far
+    pub fn len(&self) -> usize {
+        self.atoms.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn intern_locked(atoms: &mut FxHashSet<Arc<str>>, name: &str) -> Arc<str> {
+        match atoms.get(name) {
+            Some(atom) => atom.clone(),
+            None => {
+                let atom: Arc<str> = name.into();
+                atoms.insert(atom.clone());
+                atom
+            }
+        }
+    }
+}
+
+impl Interner for ArcInterner {
+    type Atom = Arc<str>;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        Self::intern_locked(&mut self.atoms.lock().unwrap(), name)
+    }
+
+    fn intern_many<'a>(&self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        let mut atoms = self.atoms.lock().unwrap();
+        iter.map(|name| Self::intern_locked(&mut atoms, name))
+            .collect()
+    }
+}
+
+impl Resolver for ArcInterner {
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        atom
+    }
+}
+
+/// A deduplicating `Arc<str>` interner split into independently locked shards
+///
+/// Designed for indexing many files from many threads at once, where a single
+/// lock would become the contention point.
+pub struct ShardedInterner {
+    shards: Box<[Mutex<FxHashSet<Arc<str>>>]>,
+}
+
+impl ShardedInterner {
+    pub fn new() -> Self {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Self::with_shards(threads * 4)
+    }
+
+    /// Creates an interner with at least `count` shards
+    ///
+    /// The count is rounded up to a power of two.
+    pub fn with_shards(count: usize) -> Self {
+        let count = count.max(1).next_power_of_two();
+        Self {
+            shards: (0..count).map(|_| Mutex::default()).collect(),
+        }
+    }
+
+    /// Number of distinct atoms interned so far
+    pub fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn shard(&self, name: &str) -> &Mutex<FxHashSet<Arc<str>>> {
+        let mut hasher = FxHasher::default();
+        name.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize & (self.shards.len() - 1)]
+    }
+}
+
+impl Default for ShardedInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interner for ShardedInterner {
+    type Atom = Arc<str>;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        ArcInterner::intern_locked(&mut self.shard(name).lock().unwrap(), name)
+    }
+}
+
+impl Resolver for ShardedInterner {
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        atom
+    }
+}
+
+impl<T: Interner> Interner for &T {
+    type Atom = T::Atom;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        (**self).intern(name)
+    }
+
+    fn intern_many<'a>(&self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        (**self).intern_many(iter)
+    }
+
+    fn intern_bytes(&self, name: &[u8]) -> Option<Self::Atom> {
+        (**self).intern_bytes(name)
+    }
+}
+
+impl<T: Resolver> Resolver for &T {
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        (**self).resolve(atom)
+    }
+}
@@ -0,0 +1,19 @@
+use std::hash::{BuildHasher, Hash};
+
+use ::lasso::{Key, ThreadedRodeo};
+
+use crate::{Interner, Resolver};
+
+impl<K: Key + Hash, S: BuildHasher + Clone> Interner for ThreadedRodeo<K, S> {
+    type Atom = K;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        self.get_or_intern(name)
+    }
+}
+
+impl<K: Key + Hash, S: BuildHasher + Clone> Resolver for ThreadedRodeo<K, S> {
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        ThreadedRodeo::resolve(self, atom)
+    }
+}
@@ -0,0 +1,72 @@
+use std::{hash::BuildHasher, sync::Mutex};
+
+use ::string_interner::{
+    backend::Backend, DefaultBackend, DefaultHashBuilder, StringInterner, Symbol,
+};
+
+use crate::{Interner, InternerMut};
+
+impl<B, H> InternerMut for StringInterner<B, H>
+where
+    B: Backend,
+    B::Symbol: Symbol,
+    H: BuildHasher,
+{
+    type Atom = B::Symbol;
+
+    fn intern_mut(&mut self, name: &str) -> Self::Atom {
+        self.get_or_intern(name)
+    }
+}
+
+/// An `Interner` backed by a `string_interner::StringInterner`
+///
+/// `StringInterner` requires `&mut self` for interning, so it's kept behind a mutex.
+/// Recover it with `into_inner` to resolve the symbols.
+/// Prefer using `StringInterner` directly with `BeamFile::index_atoms_mut` when no sharing is needed.
+pub struct SyncStringInterner<B: Backend = DefaultBackend, H = DefaultHashBuilder> {
+    inner: Mutex<StringInterner<B, H>>,
+}
+
+impl<B: Backend, H> SyncStringInterner<B, H> {
+    pub fn into_inner(self) -> StringInterner<B, H> {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+impl<B: Backend, H> From<StringInterner<B, H>> for SyncStringInterner<B, H> {
+    fn from(inner: StringInterner<B, H>) -> Self {
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+}
+
+impl<B, H> Default for SyncStringInterner<B, H>
+where
+    B: Backend,
+    B::Symbol: Symbol,
+    H: BuildHasher + Default,
+{
+    fn default() -> Self {
+        StringInterner::new().into()
+    }
+}
+
+impl<B, H> Interner for SyncStringInterner<B, H>
+where
+    B: Backend,
+    B::Symbol: Symbol,
+    H: BuildHasher,
+{
+    type Atom = B::Symbol;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        self.inner.lock().unwrap().get_or_intern(name)
+    }
+
+    fn intern_many<'a>(&self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        let mut inner = self.inner.lock().unwrap();
+        iter.map(|name| inner.get_or_intern(name)).collect()
+    }
+}
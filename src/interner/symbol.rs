@@ -0,0 +1,158 @@
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{
+    fmt,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use fxhash::FxHashMap;
+
+use crate::{Interner, Resolver};
+
+/// Number of names in the first segment of a `SymbolTable`, each following
+/// segment is twice as large
+const FIRST_SEGMENT: usize = 64;
+/// Enough segments for every `u32` symbol
+const SEGMENTS: usize = 27;
+
+/// Names of consecutive symbols, allocated when the first of them is interned
+type Segment = OnceLock<Box<[OnceLock<Arc<str>>]>>;
+
+/// Id of the next `SymbolTable`, to catch symbols resolved in the wrong table
+#[cfg(debug_assertions)]
+static NEXT_TABLE: AtomicU32 = AtomicU32::new(0);
+
+/// A compact atom referring to a name in a `SymbolTable`
+///
+/// Only meaningful together with the table that produced it. Debug builds
+/// also record the table, so resolving in another table is caught.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct Symbol {
+    index: u32,
+    #[cfg(debug_assertions)]
+    table: u32,
+}
+
+impl Symbol {
+    pub fn as_u32(self) -> u32 {
+        self.index
+    }
+
+    /// Segment of the table holding the name, and its position within it
+    fn slot(self) -> (usize, usize) {
+        let n = self.index as usize / FIRST_SEGMENT + 1;
+        let segment = (usize::BITS - 1 - n.leading_zeros()) as usize;
+        let offset = self.index as usize - FIRST_SEGMENT * ((1 << segment) - 1);
+        (segment, offset)
+    }
+}
+
+/// A string table shared across many files, handing out `Symbol` atoms
+///
+/// Share a single table (by reference) between all `BeamFile`s of an analysis,
+/// so atoms from different modules can be compared as integers.
+///
+/// Names are stored in segments that are never moved or freed while the
+/// table is alive, so resolving a symbol doesn't take the lock.
+pub struct SymbolTable {
+    ids: Mutex<FxHashMap<Arc<str>, Symbol>>,
+    names: [Segment; SEGMENTS],
+    #[cfg(debug_assertions)]
+    id: u32,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable {
+            ids: Mutex::default(),
+            names: Default::default(),
+            #[cfg(debug_assertions)]
+            id: NEXT_TABLE.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the name of a symbol, or `None` if the table has none for it
+    ///
+    /// Symbols of a different table are only detected in debug builds, and
+    /// may otherwise resolve to an unrelated name.
+    pub fn try_resolve(&self, symbol: &Symbol) -> Option<&str> {
+        #[cfg(debug_assertions)]
+        if symbol.table != self.id {
+            return None;
+        }
+        let (segment, offset) = symbol.slot();
+        let name = self.names[segment].get()?.get(offset)?.get()?;
+        Some(name)
+    }
+
+    /// Looks up the symbol of an already interned name
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.ids.lock().unwrap().get(name).copied()
+    }
+
+    /// Number of distinct names in the table
+    pub fn len(&self) -> usize {
+        self.ids.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn intern_locked(&self, ids: &mut FxHashMap<Arc<str>, Symbol>, name: &str) -> Symbol {
+        if let Some(symbol) = ids.get(name) {
+            return *symbol;
+        }
+        let symbol = Symbol {
+            index: ids.len() as u32,
+            #[cfg(debug_assertions)]
+            table: self.id,
+        };
+        let name: Arc<str> = name.into();
+        let (segment, offset) = symbol.slot();
+        let names = self.names[segment].get_or_init(|| {
+            (0..FIRST_SEGMENT << segment)
+                .map(|_| OnceLock::new())
+                .collect()
+        });
+        let _ = names[offset].set(name.clone());
+        ids.insert(name, symbol);
+        symbol
+    }
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for SymbolTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SymbolTable")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Interner for SymbolTable {
+    type Atom = Symbol;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        let mut ids = self.ids.lock().unwrap();
+        self.intern_locked(&mut ids, name)
+    }
+
+    fn intern_many<'a>(&self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
+        let mut ids = self.ids.lock().unwrap();
+        iter.map(|name| self.intern_locked(&mut ids, name))
+            .collect()
+    }
+}
+
+impl Resolver for SymbolTable {
+    /// Panics if the table has no name for the symbol, see `try_resolve`
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        self.try_resolve(atom).expect("symbol not in the table")
+    }
+}
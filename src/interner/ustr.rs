@@ -0,0 +1,23 @@
+use ::ustr::Ustr;
+
+use crate::{Interner, Resolver};
+
+/// An interner backed by the process-global `ustr` string cache
+///
+/// Atoms are `Copy` and comparable by pointer across files and threads.
+#[derive(Default, Clone, Copy)]
+pub struct UstrInterner;
+
+impl Interner for UstrInterner {
+    type Atom = Ustr;
+
+    fn intern(&self, name: &str) -> Self::Atom {
+        Ustr::from(name)
+    }
+}
+
+impl Resolver for UstrInterner {
+    fn resolve<'a>(&'a self, atom: &'a Self::Atom) -> &'a str {
+        atom.as_str()
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    borrow::Cow,
+    fmt::Write,
+    io::{Read, Seek},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    optional, AttrChunk, BeamFile, CInfChunk, Chunk, CodeHeader, ExpTChunk, Id, ImpTChunk,
+    InternerMut, LocTChunk, Result, Warning,
+};
+
+/// Chunks with a structured representation in `to_json`
+const DECODED_CHUNKS: [Id; 8] = [
+    Id::AT_U8,
+    Id::ATOM,
+    CodeHeader::ID,
+    ImpTChunk::<()>::ID,
+    ExpTChunk::<()>::ID,
+    LocTChunk::<()>::ID,
+    AttrChunk::ID,
+    CInfChunk::ID,
+];
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Dumps the whole module as a JSON document
+    ///
+    /// Contains the chunk inventory, atoms, imports, exports, local functions,
+    /// attributes, compile information and warnings. Missing chunks are `null`,
+    /// chunks without a structured representation are included hex-encoded
+    /// under `raw`. Atoms are decoded independently of `index_atoms`.
+    pub fn to_json(&mut self) -> Result<Value> {
+        let mut entries: Vec<_> = self.index.iter().collect();
+        entries.sort_by_key(|(_, entry)| entry.position);
+        let chunks: Vec<Value> = entries
+            .iter()
+            .map(|(id, entry)| json!({"id": id, "offset": entry.position, "size": entry.len}))
+            .collect();
+        let other: Vec<Id> = entries
+            .iter()
+            .map(|(id, _)| **id)
+            .filter(|id| !DECODED_CHUNKS.contains(id))
+            .collect();
+
+        let mut warnings: Vec<Warning> = self
+            .warnings
+            .iter()
+            .filter(|warning| !matches!(warning, Warning::LossyAtom { .. }))
+            .cloned()
+            .collect();
+        let raw_atoms = self.atoms_raw()?;
+        let atoms: Vec<String> =
+            Self::decode_atom_bytes(&raw_atoms, self.invalid_atoms, &mut warnings)
+                .map_err(|err| err.in_file(self.path.as_deref()))?
+                .into_iter()
+                .map(Cow::into_owned)
+                .collect();
+
+        let code = optional(self.code_header())?;
+        let imports = self.owned_imports(&atoms)?;
+        let exports = self.owned_exports(&atoms)?;
+        let locals = optional(self.read_with_atoms::<LocTChunk<String>>(&atoms))?;
+        let attributes = optional(self.attributes())?;
+        let compile_info = optional(self.compile_info())?;
+
+        let mut raw = Map::new();
+        for id in other {
+            raw.insert(id_key(id), Value::String(hex(&self.read_raw(id)?)));
+        }
+
+        Ok(json!({
+            "name": atoms.first(),
+            "chunks": chunks,
+            "atoms": atoms,
+            "code": code,
+            "imports": imports,
+            "exports": exports,
+            "locals": locals.map(|chunk| chunk.locals),
+            "attributes": attributes.map(|chunk| chunk.attributes),
+            "compile_info": compile_info.map(|chunk| chunk.info),
+            "raw": raw,
+            "warnings": warnings,
+        }))
+    }
+}
+
+fn id_key(id: Id) -> String {
+    match serde_json::to_value(id) {
+        Ok(Value::String(key)) => key,
+        _ => hex(&id.0),
+    }
+}
+
+fn hex(data: &[u8]) -> String {
+    let mut hex = String::with_capacity(data.len() * 2);
+    for byte in data {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    hex
+}
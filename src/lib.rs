@@ -1,54 +1,228 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use core::{convert::TryFrom, fmt, str};
+#[cfg(feature = "std")]
 use std::{
-    fmt,
+    borrow::Cow,
     fs::File,
-    io::{Cursor, Read, Seek, SeekFrom},
-    path::Path,
-    str,
+    io::{self, Cursor, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
+#[cfg(feature = "std")]
 use byteorder::{BigEndian, ReadBytesExt};
+#[cfg(feature = "std")]
 use fxhash::{FxHashMap, FxHashSet};
-use thiserror::Error;
 
+#[cfg(feature = "std")]
+use crate::{metrics::metrics, scan::Scanner};
+
+#[cfg(feature = "std")]
+mod abstract_code;
+#[cfg(feature = "arena")]
+mod arena;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+mod async_file;
+#[cfg(feature = "std")]
+mod atoms;
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "std")]
 mod chunk;
+#[cfg(feature = "std")]
+mod code;
+#[cfg(feature = "std")]
+mod code_path;
+#[cfg(feature = "std")]
+mod complexity;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod disasm;
+#[cfg(feature = "std")]
+mod docs;
+#[cfg(feature = "std")]
+mod dump;
+mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "std")]
+mod forms;
+#[cfg(feature = "fuzzing")]
+mod fuzzing;
+#[cfg(feature = "std")]
+mod interner;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "std")]
+mod line;
+#[cfg(feature = "std")]
+mod literals;
+#[cfg(feature = "std")]
+mod metrics;
+mod options;
+#[cfg(feature = "std")]
+mod parallel;
+#[cfg(feature = "std")]
+mod pretty;
+mod scan;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod slice;
+#[cfg(feature = "std")]
+mod source;
+#[cfg(feature = "std")]
+mod staleness;
+#[cfg(feature = "std")]
+mod stub;
+#[cfg(feature = "std")]
+mod summary;
+#[cfg(feature = "std")]
+mod term;
+#[cfg(feature = "std")]
+mod typespec;
+#[cfg(feature = "std")]
+mod verify;
+mod warning;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+mod xref;
 
+#[cfg(feature = "std")]
+pub use abstract_code::*;
+#[cfg(feature = "arena")]
+pub use arena::*;
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+pub use async_file::*;
+#[cfg(feature = "std")]
+pub use atoms::*;
+#[cfg(feature = "cache")]
+pub use cache::*;
+#[cfg(feature = "std")]
 pub use chunk::*;
+#[cfg(feature = "std")]
+pub(crate) use code::{CodeChunk, FunctionCode};
+#[cfg(feature = "arena")]
+pub use code::{Instruction, Operand};
+#[cfg(all(feature = "std", not(feature = "arena")))]
+pub(crate) use code::{Instruction, Operand};
+#[cfg(feature = "std")]
+pub use code_path::*;
+#[cfg(feature = "std")]
+pub use complexity::*;
+#[cfg(feature = "std")]
+pub use diff::*;
+#[cfg(feature = "std")]
+pub use disasm::*;
+#[cfg(feature = "std")]
+pub use docs::*;
+#[cfg(feature = "std")]
+pub use dump::*;
+pub use error::*;
+#[cfg(feature = "std")]
+pub use fingerprint::*;
+#[cfg(feature = "std")]
+pub use forms::*;
+#[cfg(feature = "fuzzing")]
+pub use fuzzing::*;
+#[cfg(feature = "std")]
+pub use interner::*;
+#[cfg(feature = "std")]
+pub use line::*;
+#[cfg(feature = "std")]
+pub use literals::*;
+#[cfg(feature = "std")]
+pub use metrics::{set_metrics, Metrics};
+pub use options::*;
+#[cfg(feature = "std")]
+pub use parallel::*;
+pub use slice::*;
+#[cfg(feature = "std")]
+pub use source::*;
+#[cfg(feature = "std")]
+pub use staleness::*;
+#[cfg(feature = "std")]
+pub use stub::*;
+#[cfg(feature = "std")]
+pub use summary::*;
+#[cfg(feature = "std")]
+pub use term::*;
+#[cfg(feature = "std")]
+pub use typespec::*;
+#[cfg(feature = "std")]
+pub use verify::*;
+pub use warning::*;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
+#[cfg(feature = "std")]
+pub use writer::*;
+#[cfg(feature = "std")]
+pub use xref::*;
 
-/// String interner used for efficiently reading atoms
-pub trait Interner {
-    type Atom;
-
-    /// Intern an atom
-    fn intern(&self, name: &str) -> Self::Atom;
+/// The identifier which indicates the type of a chunk.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Id([u8; 4]);
 
-    /// Efficiently intern many atoms at once.
-    ///
-    /// Can be useful to avoid repeated locking-unlocking when interning
-    /// in a tight loop
-    fn intern_many<'a>(&self, iter: impl Iterator<Item = &'a str>) -> Vec<Self::Atom> {
-        iter.map(|name| self.intern(name)).collect()
+impl From<[u8; 4]> for Id {
+    fn from(data: [u8; 4]) -> Self {
+        Self(data)
     }
 }
 
-/// A "naive" interner that just allocates the string
-#[derive(Default)]
-pub struct NaiveInterner;
-
-impl Interner for NaiveInterner {
-    type Atom = String;
-
-    fn intern(&self, name: &str) -> Self::Atom {
-        name.to_string()
+impl From<Id> for [u8; 4] {
+    fn from(id: Id) -> Self {
+        id.0
     }
 }
 
-/// The identifier which indicates the type of a chunk.
-#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
-pub struct Id([u8; 4]);
+impl Id {
+    /// Atom table with Latin-1 names, from releases before OTP 21
+    pub const ATOM: Id = Id::new(*b"Atom");
+    /// Atom table with UTF-8 names
+    pub const AT_U8: Id = Id::new(*b"AtU8");
+    pub const CODE: Id = Id::new(*b"Code");
+    /// String table
+    pub const STR_T: Id = Id::new(*b"StrT");
+    /// Import table
+    pub const IMP_T: Id = Id::new(*b"ImpT");
+    /// Export table
+    pub const EXP_T: Id = Id::new(*b"ExpT");
+    /// Local function table
+    pub const LOC_T: Id = Id::new(*b"LocT");
+    /// Fun table
+    pub const FUN_T: Id = Id::new(*b"FunT");
+    /// Literal table
+    pub const LIT_T: Id = Id::new(*b"LitT");
+    /// Module attributes
+    pub const ATTR: Id = Id::new(*b"Attr");
+    /// Compile information
+    pub const C_INF: Id = Id::new(*b"CInf");
+    /// Abstract code, from releases before OTP 20
+    pub const ABST: Id = Id::new(*b"Abst");
+    /// Debug information, usually abstract code
+    pub const DBGI: Id = Id::new(*b"Dbgi");
+    /// Documentation in the format of EEP 48
+    pub const DOCS: Id = Id::new(*b"Docs");
+    /// Elixir checker information
+    pub const EX_CK: Id = Id::new(*b"ExCk");
+    /// Line table
+    pub const LINE: Id = Id::new(*b"Line");
+    /// Type information for the JIT
+    pub const TYPE: Id = Id::new(*b"Type");
+    /// Features enabled when compiling the module, from OTP 25
+    pub const META: Id = Id::new(*b"Meta");
 
-impl From<[u8; 4]> for Id {
-    fn from(data: [u8; 4]) -> Self {
-        Self(data)
+    pub const fn new(id: [u8; 4]) -> Self {
+        Self(id)
     }
 }
 
@@ -61,42 +235,208 @@ impl fmt::Debug for Id {
     }
 }
 
-#[derive(Error, Debug)]
-pub enum BeamFileError {
-    #[error("Unexpected magic number {0:?}, expected b\"FOR1\"")]
-    UnexpectedMagicNumber(Id),
+/// Prints the id as text, escaping bytes that aren't printable ASCII
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match str::from_utf8(&self.0) {
+            Ok(str) if str.chars().all(|c| c.is_ascii_graphic() || c == ' ') => f.pad(str),
+            _ => {
+                let escaped: alloc::string::String = self
+                    .0
+                    .iter()
+                    .flat_map(|byte| core::ascii::escape_default(*byte))
+                    .map(char::from)
+                    .collect();
+                f.pad(&escaped)
+            }
+        }
+    }
+}
+
+impl str::FromStr for Id {
+    type Err = ParseIdError;
 
-    #[error("Unexpected form type {0:?}, expected b\"BEAM\"")]
-    UnexpectedFormType(Id),
+    fn from_str(id: &str) -> core::result::Result<Self, ParseIdError> {
+        <[u8; 4]>::try_from(id.as_bytes())
+            .map(Id)
+            .map_err(|_| ParseIdError)
+    }
+}
 
-    #[error("Chunk {0:?} not found")]
-    MissingChunk(Id),
+/// Largest gap between chunks `BeamFile::prefetch` reads through instead of seeking
+#[cfg(feature = "std")]
+const MAX_PREFETCH_GAP: u64 = 4096;
 
-    #[error("Invalid atom")]
-    InvalidAtom(#[from] str::Utf8Error),
+/// Chunk ids produced by the compiler, see `Warning::UnknownChunk`
+pub(crate) const KNOWN_CHUNKS: [Id; 18] = [
+    Id::ATOM,
+    Id::AT_U8,
+    Id::CODE,
+    Id::STR_T,
+    Id::IMP_T,
+    Id::EXP_T,
+    Id::LOC_T,
+    Id::FUN_T,
+    Id::LIT_T,
+    Id::ATTR,
+    Id::C_INF,
+    Id::ABST,
+    Id::DBGI,
+    Id::DOCS,
+    Id::EX_CK,
+    Id::LINE,
+    Id::TYPE,
+    Id::META,
+];
 
-    #[error(transparent)]
-    Io(#[from] std::io::Error),
+/// Layout of the IFF container holding the chunks
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Container {
+    /// Position of the `FOR1` header
+    pub start: u64,
+    /// Size of the payload declared in the header, excluding the header itself
+    pub declared_payload_size: u64,
+    /// Where the indexed payload ends, which differs from the declared end
+    /// for files read with options relaxing the size checks
+    pub payload_end: u64,
+    /// Size of the whole file, including any data around the container
+    pub file_size: u64,
 }
 
-pub type Result<T> = std::result::Result<T, BeamFileError>;
+impl Container {
+    /// Number of bytes after the payload, only allowed with
+    /// `BeamFileOptions::allow_trailing_data`
+    pub fn trailing_len(&self) -> u64 {
+        self.file_size.saturating_sub(self.payload_end)
+    }
+}
 
 #[derive(Clone, Debug)]
-struct IndexEntry {
+pub(crate) struct IndexEntry {
     position: u64,
     len: u64,
 }
 
-type Index = FxHashMap<Id, IndexEntry>;
+#[cfg(feature = "std")]
+pub(crate) type Index = FxHashMap<Id, IndexEntry>;
+#[cfg(not(feature = "std"))]
+pub(crate) type Index = alloc::collections::BTreeMap<Id, IndexEntry>;
 
-#[derive(Clone)]
-pub struct BeamFile<R, I: Interner> {
+/// Entries of the index in the order they appear in the file
+pub(crate) fn in_file_order(index: &Index) -> alloc::vec::Vec<(Id, &IndexEntry)> {
+    let mut entries: alloc::vec::Vec<_> = index.iter().map(|(id, entry)| (*id, entry)).collect();
+    entries.sort_by_key(|(_, entry)| entry.position);
+    entries
+}
+
+/// Decodes the first atom of the atom chunk starting at `position`
+#[cfg(feature = "std")]
+fn peek_name_at<S: Read + Seek>(
+    reader: &mut S,
+    position: u64,
+    chunk_len: u64,
+) -> Result<Option<String>> {
+    reader.seek(SeekFrom::Start(position))?;
+
+    let count = reader.read_u32::<BigEndian>()?;
+    if count == 0 {
+        return Ok(None);
+    }
+
+    let len = reader.read_u8()? as usize;
+    if 5 + len as u64 > chunk_len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+
+    match String::from_utf8(buf) {
+        Ok(name) => Ok(Some(name)),
+        Err(err) => Err(err.utf8_error().into()),
+    }
+}
+
+/// An indexed BEAM file
+///
+/// The interner `I` determines the type of decoded atoms.
+/// Construct files with the default `NaiveInterner` and switch with `with_interner`.
+///
+/// Clones share the index. Files created with `from_shared` also share the
+/// data, while each clone reads at its own position, so they can be handed
+/// to separate threads.
+#[cfg(feature = "std")]
+pub struct BeamFile<R, I: InternerMut = NaiveInterner> {
     reader: R,
-    index: Index,
+    path: Option<PathBuf>,
+    index: Arc<Index>,
+    duplicates: Arc<[(Id, IndexEntry)]>,
     atom_index: Option<Vec<I::Atom>>,
+    invalid_atoms: InvalidAtoms,
+    warnings: Vec<Warning>,
+    truncated: bool,
+    container: Container,
+    limits: Limits,
+    /// Chunks read ahead by `BeamFile::prefetch`
+    prefetched: FxHashMap<Id, Prefetched>,
+}
+
+/// A chunk read by `BeamFile::prefetch`, sharing the buffer of its run
+#[cfg(feature = "std")]
+#[derive(Clone)]
+struct Prefetched {
+    run: Arc<[u8]>,
+    range: Range<usize>,
+}
+
+#[cfg(feature = "std")]
+impl Prefetched {
+    fn data(&self) -> &[u8] {
+        &self.run[self.range.clone()]
+    }
 }
 
-impl<R, I: Interner> fmt::Debug for BeamFile<R, I>
+/// A file held entirely in memory, see `BeamFile::freeze`
+///
+/// Doesn't borrow or own any reader, so it can be stored in caches or sent
+/// to other threads. Clones share the data.
+#[cfg(feature = "std")]
+pub type OwnedBeamFile<I = NaiveInterner> = BeamFile<Cursor<Arc<[u8]>>, I>;
+
+/// A `BeamFile` taken apart by `BeamFile::into_parts`
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct BeamFileParts<R, A> {
+    pub reader: R,
+    /// Chunks in file order, as `(id, offset, len)`
+    pub chunks: Vec<(Id, u64, u64)>,
+    /// The atoms, if they were indexed
+    pub atoms: Option<Vec<A>>,
+}
+
+#[cfg(feature = "std")]
+impl<R: Clone, I: InternerMut> Clone for BeamFile<R, I>
+where
+    I::Atom: Clone,
+{
+    fn clone(&self) -> Self {
+        BeamFile {
+            reader: self.reader.clone(),
+            path: self.path.clone(),
+            index: self.index.clone(),
+            duplicates: self.duplicates.clone(),
+            atom_index: self.atom_index.clone(),
+            invalid_atoms: self.invalid_atoms,
+            warnings: self.warnings.clone(),
+            truncated: self.truncated,
+            container: self.container,
+            limits: self.limits,
+            prefetched: self.prefetched.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, I: InternerMut> fmt::Debug for BeamFile<R, I>
 where
     I::Atom: fmt::Debug,
 {
@@ -108,152 +448,3907 @@ where
     }
 }
 
-impl<I: Interner> BeamFile<File, I> {
+#[cfg(feature = "std")]
+impl BeamFile<File> {
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let file = File::open(path)?;
-        Self::from_reader(file)
+        Self::open(path.as_ref(), &BeamFileOptions::default())
     }
-}
 
-impl<R: Read + Seek, I: Interner> BeamFile<R, I> {
-    pub fn from_reader(mut reader: R) -> Result<Self> {
-        let mut magic_number = [0; 4];
-        reader.read_exact(&mut magic_number)?;
+    pub(crate) fn open(path: &Path, options: &BeamFileOptions) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("open", path = %path.display()).entered();
+        let result = File::open(path)
+            .map_err(BeamFileError::from)
+            .and_then(|file| Self::parse(file, options));
 
-        if magic_number != *b"FOR1" {
-            return Err(BeamFileError::UnexpectedMagicNumber(magic_number.into()));
+        match result {
+            Ok(mut file) => {
+                file.path = Some(path.to_path_buf());
+                Ok(file)
+            }
+            Err(err) => Err(err.in_file(Some(path))),
         }
+    }
+}
+
+#[cfg(feature = "std")]
+impl BeamFile<Cursor<Vec<u8>>> {
+    /// Indexes a file held in memory, taking ownership of the data
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        Self::from_reader(Cursor::new(data))
+    }
+}
 
-        let payload_size = reader.read_u32::<BigEndian>()? as u64;
+#[cfg(feature = "std")]
+impl BeamFile<Cursor<Arc<[u8]>>> {
+    /// Indexes a file in shared memory, making clones of the file cheap
+    pub fn from_shared(data: Arc<[u8]>) -> Result<Self> {
+        Self::from_reader(Cursor::new(data))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> BeamFile<Cursor<&'a [u8]>> {
+    /// Indexes a file held in memory, borrowing the data
+    ///
+    /// Chunks are still copied when read, `BeamSlice` avoids that.
+    pub fn from_slice(data: &'a [u8]) -> Result<Self> {
+        Self::from_reader(Cursor::new(data))
+    }
+}
 
-        let mut type_id = [0; 4];
-        reader.read_exact(&mut type_id)?;
+#[cfg(feature = "std")]
+impl TryFrom<&Path> for BeamFile<File> {
+    type Error = BeamFileError;
 
-        if type_id != *b"BEAM" {
-            return Err(BeamFileError::UnexpectedFormType(type_id.into()));
-        }
+    fn try_from(path: &Path) -> Result<Self> {
+        Self::from_file(path)
+    }
+}
 
-        let mut position = reader.seek(SeekFrom::Current(0))?;
+#[cfg(feature = "std")]
+impl<'a> TryFrom<&'a [u8]> for BeamFile<Cursor<&'a [u8]>> {
+    type Error = BeamFileError;
 
-        let mut index = Index::default();
+    fn try_from(data: &'a [u8]) -> Result<Self> {
+        Self::from_slice(data)
+    }
+}
 
-        while position < payload_size {
-            let mut chunk_id = [0; 4];
-            reader.read_exact(&mut chunk_id)?;
-            let chunk_len = reader.read_u32::<BigEndian>()? as u64;
+#[cfg(feature = "std")]
+impl<R: Read + Seek> BeamFile<R> {
+    pub fn from_reader(reader: R) -> Result<Self> {
+        Self::parse(reader, &BeamFileOptions::default())
+    }
 
-            index.insert(
-                chunk_id.into(),
-                IndexEntry {
-                    position: position + 8,
-                    len: chunk_len,
-                },
-            );
+    pub(crate) fn parse(mut reader: R, options: &BeamFileOptions) -> Result<Self> {
+        let start = reader.stream_position()?;
+        let file_size = reader.seek(SeekFrom::End(0))?;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("index", file_size).entered();
 
-            position = reader.seek(SeekFrom::Start(
-                position + 8 + 4 * ((chunk_len + 4 - 1) / 4),
-            ))?;
+        let mut scanner = Scanner::new(start, file_size, options);
+        let mut buffer = [0; 12];
+        while let Some((position, len)) = scanner.next_read() {
+            reader.seek(SeekFrom::Start(position))?;
+            reader.read_exact(&mut buffer[..len])?;
+            metrics().bytes_read(len as u64);
+            scanner.feed(&buffer[..len])?;
         }
 
-        Ok(Self {
+        Ok(Self::from_scan(reader, scanner, options))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, I: InternerMut> BeamFile<R, I> {
+    pub(crate) fn from_scan(reader: R, scanner: Scanner, options: &BeamFileOptions) -> Self {
+        metrics().file_parsed();
+        Self {
             reader,
-            index,
+            path: None,
+            index: Arc::new(scanner.index),
+            duplicates: scanner.duplicates.into(),
             atom_index: None,
-        })
+            invalid_atoms: options.invalid_atoms,
+            warnings: scanner.warnings,
+            truncated: scanner.truncated,
+            container: scanner.container,
+            limits: options.limits,
+            prefetched: FxHashMap::default(),
+        }
     }
+}
 
+#[cfg(feature = "std")]
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
     /// Reads a structured chunk representation
     ///
-    /// Panics if the atoms weren't index with `index_atoms`.
+    /// Fails with `AtomsNotIndexed` if the atoms weren't indexed with `index_atoms`.
     pub fn read<C: Chunk<Atom = I::Atom> + Sized>(&mut self) -> Result<C> {
-        let raw = self.read_raw(C::ID)?;
-        let reader = Cursor::new(raw);
-        let atom_index = self.atom_index.as_deref().unwrap();
-        C::decode(reader, atom_index)
+        let atom_index = self
+            .atom_index
+            .as_deref()
+            .ok_or(BeamFileError::AtomsNotIndexed)?;
+        let entry = self
+            .index
+            .get(&C::ID)
+            .ok_or(BeamFileError::MissingChunk(C::ID))?;
+        let path = self.path.as_deref();
+        let data = Self::read_cached(&mut self.reader, &self.prefetched, C::ID, entry, path)?;
+        let mut reader = Cursor::new(data);
+        let result = C::decode(&mut reader, atom_index);
+        self.finish_decode(result, C::ID, entry.position + reader.position())
+    }
+
+    /// Reads a structured chunk representation, indexing the atoms first if needed
+    ///
+    /// `interner` is only used if the atoms weren't indexed yet.
+    pub fn read_with<C: Chunk<Atom = I::Atom> + Sized>(&mut self, interner: I) -> Result<C> {
+        if self.atom_index.is_none() {
+            self.index_atoms(interner)?;
+        }
+        self.read()
+    }
+
+    /// Reads a structured chunk representation of a chunk that may be absent
+    ///
+    /// Like `read`, but returns `None` instead of failing with `MissingChunk`.
+    pub fn read_opt<C: Chunk<Atom = I::Atom> + Sized>(&mut self) -> Result<Option<C>> {
+        optional(self.read())
+    }
+
+    /// Reads a structured chunk representation using a caller-provided atom table
+    ///
+    /// The atom table doesn't need to come from `index_atoms`, which allows
+    /// decoding with atoms borrowed from `RawAtoms::intern_borrowed`.
+    pub fn read_with_atoms<C: Chunk>(&mut self, atom_index: &[C::Atom]) -> Result<C> {
+        let (mut reader, position) = self.chunk_reader(C::ID)?;
+        let result = C::decode(&mut reader, atom_index);
+        self.finish_decode(result, C::ID, position + reader.position())
+    }
+
+    /// Reads a structured chunk representation, allocating its collections in `bump`
+    ///
+    /// The chunk is decoded in place if the reader has the contents in memory,
+    /// see `AsBytes`, or was read by `prefetch`. Otherwise it's read into `bump`.
+    /// Fails with `AtomsNotIndexed` if the atoms weren't indexed with `index_atoms`.
+    #[cfg(feature = "arena")]
+    pub fn read_in<'bump, C: ArenaChunk<'bump, Atom = I::Atom>>(
+        &mut self,
+        bump: &'bump bumpalo::Bump,
+    ) -> Result<C>
+    where
+        R: AsBytes,
+    {
+        let atom_index = self
+            .atom_index
+            .as_deref()
+            .ok_or(BeamFileError::AtomsNotIndexed)?;
+        let id = C::ID;
+        let entry = self.index.get(&id).ok_or(BeamFileError::MissingChunk(id))?;
+        let path = self.path.as_deref();
+        let range = entry.position as usize..(entry.position + entry.len) as usize;
+        let data = if let Some(chunk) = self.prefetched.get(&id) {
+            chunk.data()
+        } else if self.reader.as_bytes().is_some() {
+            self.reader
+                .as_bytes()
+                .and_then(|bytes| bytes.get(range))
+                .ok_or_else(|| {
+                    BeamFileError::from(io::Error::from(io::ErrorKind::UnexpectedEof))
+                        .in_chunk(id, entry.position)
+                        .in_file(path)
+                })?
+        } else {
+            let data = bump.alloc_slice_fill_copy(entry.len as usize, 0);
+            Self::read_entry_into(&mut self.reader, id, entry, path, data)?;
+            data
+        };
+        let mut reader = Cursor::new(data);
+        let result = C::decode_in(&mut reader, atom_index, bump);
+        let offset = entry.position + reader.position();
+        self.finish_decode(result, id, offset)
+    }
+
+    /// Decodes several chunks, reading them in the order they appear in the file
+    ///
+    /// `T` is a tuple of chunk types, like `(ImpTChunk<_>, ExpTChunk<_>)`.
+    /// Fails with `AtomsNotIndexed` if the atoms weren't indexed with `index_atoms`.
+    pub fn read_many<T: ChunkTuple<I::Atom>>(&mut self) -> Result<T> {
+        let atom_index = self
+            .atom_index
+            .as_deref()
+            .ok_or(BeamFileError::AtomsNotIndexed)?;
+        let index = &self.index;
+        let mut entries = T::ids()
+            .into_iter()
+            .map(|id| match index.get(&id) {
+                Some(entry) => Ok((id, entry)),
+                None => Err(BeamFileError::MissingChunk(id)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        entries.sort_by_key(|(_, entry)| entry.position);
+        entries.dedup_by_key(|(id, _)| *id);
+
+        let reader = &mut self.reader;
+        let path = self.path.as_deref();
+        let prefetched = &self.prefetched;
+        let chunks = entries
+            .into_iter()
+            .map(|(id, entry)| {
+                let data = Self::read_cached(reader, prefetched, id, entry, path)?;
+                Ok((id, entry.position, data))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        T::decode_all(self, &chunks, atom_index)
     }
 
     pub fn read_raw(&mut self, id: Id) -> Result<Vec<u8>> {
         let entry = self.index.get(&id).ok_or(BeamFileError::MissingChunk(id))?;
-        Self::read_entry(&mut self.reader, entry)
+        Self::read_cached(
+            &mut self.reader,
+            &self.prefetched,
+            id,
+            entry,
+            self.path.as_deref(),
+        )
+    }
+
+    /// Reads chunks into memory in a single pass, ignoring ids not present in the file
+    ///
+    /// Chunks are read in file order, and small gaps between them are read
+    /// through instead of seeking, so reading several chunks of a file on
+    /// disk takes one sequential sweep. Later reads of the chunks don't
+    /// touch the reader, until `clear_prefetched` is called.
+    pub fn prefetch(&mut self, ids: &[Id]) -> Result<()> {
+        let mut entries: Vec<_> = ids
+            .iter()
+            .filter(|id| !self.prefetched.contains_key(id))
+            .filter_map(|id| Some((*id, self.index.get(id)?.clone())))
+            .collect();
+        entries.sort_by_key(|(_, entry)| entry.position);
+        entries.dedup_by_key(|(id, _)| *id);
+
+        let mut rest = &entries[..];
+        while let Some(((first, start), _)) = rest.split_first() {
+            let run = 1 + rest
+                .windows(2)
+                .take_while(|pair| {
+                    let end = pair[0].1.position + pair[0].1.len;
+                    pair[1].1.position.saturating_sub(end) <= MAX_PREFETCH_GAP
+                })
+                .count();
+            let (chunks, next) = rest.split_at(run);
+            let end = chunks
+                .iter()
+                .map(|(_, entry)| entry.position + entry.len)
+                .max()
+                .unwrap_or(start.position);
+            let span = IndexEntry {
+                position: start.position,
+                len: end - start.position,
+            };
+            let run: Arc<[u8]> =
+                Self::read_entry(&mut self.reader, *first, &span, self.path.as_deref())?.into();
+            for (id, entry) in chunks {
+                let offset = (entry.position - span.position) as usize;
+                let chunk = Prefetched {
+                    run: run.clone(),
+                    range: offset..offset + entry.len as usize,
+                };
+                self.prefetched.insert(*id, chunk);
+            }
+            rest = next;
+        }
+        Ok(())
+    }
+
+    /// Drops the chunks read by `prefetch`
+    pub fn clear_prefetched(&mut self) {
+        self.prefetched = FxHashMap::default();
+    }
+
+    /// Reads the whole file into memory, dropping the reader
+    ///
+    /// The index, indexed atoms and warnings are kept, so offsets reported
+    /// by the frozen file are the same as before. The whole file, not only
+    /// its chunks, is bounded by `Limits::max_total_size`.
+    pub fn freeze(mut self) -> Result<OwnedBeamFile<I>> {
+        let file_size = self.container.file_size;
+        Limits::check("File size", file_size, self.limits.max_total_size)
+            .map_err(|err| err.in_file(self.path.as_deref()))?;
+        let mut data = vec![0; file_size as usize];
+        self.reader
+            .seek(SeekFrom::Start(0))
+            .and_then(|_| self.reader.read_exact(&mut data))
+            .map_err(|err| BeamFileError::from(err).in_file(self.path.as_deref()))?;
+        metrics().bytes_read(data.len() as u64);
+
+        Ok(BeamFile {
+            reader: Cursor::new(data.into()),
+            path: self.path,
+            index: self.index,
+            duplicates: self.duplicates,
+            atom_index: self.atom_index,
+            invalid_atoms: self.invalid_atoms,
+            warnings: self.warnings,
+            truncated: self.truncated,
+            container: self.container,
+            limits: self.limits,
+            prefetched: FxHashMap::default(),
+        })
+    }
+
+    /// Decodes a chunk needing other chunks of the file, see `ContextChunk`
+    ///
+    /// Fails with `AtomsNotIndexed` if the atoms weren't indexed with `index_atoms`.
+    pub fn read_in_context<C: ContextChunk<Atom = I::Atom>>(&mut self) -> Result<C> {
+        let atom_index = self
+            .atom_index
+            .take()
+            .ok_or(BeamFileError::AtomsNotIndexed)?;
+        let result = self.read_in_context_with_atoms(&atom_index);
+        self.atom_index = Some(atom_index);
+        result
+    }
+
+    /// Like `read_in_context`, using a caller-provided atom table
+    pub(crate) fn read_in_context_with_atoms<C: ContextChunk>(
+        &mut self,
+        atom_index: &[C::Atom],
+    ) -> Result<C> {
+        let (mut reader, position) = self.chunk_reader(C::ID)?;
+        let BeamFile {
+            reader: source,
+            path,
+            index,
+            limits,
+            prefetched,
+            ..
+        } = self;
+        let path = path.as_deref();
+        let mut read = |id| {
+            let entry = index.get(&id).ok_or(BeamFileError::MissingChunk(id))?;
+            let data = Self::read_cached(source, prefetched, id, entry, path)?;
+            Ok((data, entry.position))
+        };
+        let mut context = DecodeContext {
+            atom_index,
+            index,
+            limits,
+            read: &mut read,
+        };
+        let result = C::decode_in_context(&mut reader, &mut context);
+        self.finish_decode(result, C::ID, position + reader.position())
+    }
+
+    /// Reads a chunk, borrowing it if the whole file is in memory
+    ///
+    /// Only readers implementing `AsBytes` with the contents, like `Cursor`,
+    /// and chunks read by `prefetch` avoid the copy.
+    pub fn read_raw_cow(&mut self, id: Id) -> Result<Cow<'_, [u8]>>
+    where
+        R: AsBytes,
+    {
+        if self.prefetched.contains_key(&id) {
+            return Ok(Cow::Borrowed(self.prefetched[&id].data()));
+        }
+        if self.reader.as_bytes().is_none() {
+            return self.read_raw(id).map(Cow::Owned);
+        }
+        let entry = self.index.get(&id).ok_or(BeamFileError::MissingChunk(id))?;
+        let range = entry.position as usize..(entry.position + entry.len) as usize;
+        match self.reader.as_bytes().and_then(|bytes| bytes.get(range)) {
+            Some(data) => Ok(Cow::Borrowed(data)),
+            None => Err(
+                BeamFileError::from(io::Error::from(io::ErrorKind::UnexpectedEof))
+                    .in_chunk(id, entry.position)
+                    .in_file(self.path.as_deref()),
+            ),
+        }
     }
 
+    /// Iterates over the chunks in the order they appear in the file
+    ///
+    /// Chunks are read sequentially, without seeking backwards.
     pub fn iter_raw(&mut self) -> impl Iterator<Item = (Id, Result<Vec<u8>>)> + '_ {
+        let entries = in_file_order(&self.index);
+        let reader = &mut self.reader;
+        let prefetched = &self.prefetched;
+        let path = self.path.as_deref();
+        entries.into_iter().map(move |(id, entry)| {
+            let data = Self::read_cached(reader, prefetched, id, entry, path);
+            (id, data)
+        })
+    }
+
+    /// Consumes the file, reading the chunks in the order they appear in the file
+    ///
+    /// Unlike `iter_raw`, the iterator owns the reader, so it can be sent to
+    /// other threads or kept after the file is gone.
+    pub fn into_chunks(self) -> impl Iterator<Item = (Id, Result<Vec<u8>>)> {
+        let entries: Vec<_> = in_file_order(&self.index)
+            .into_iter()
+            .map(|(id, entry)| (id, entry.clone()))
+            .collect();
+        let mut reader = self.reader;
+        let prefetched = self.prefetched;
+        let path = self.path;
+        entries.into_iter().map(move |(id, entry)| {
+            let data = Self::read_cached(&mut reader, &prefetched, id, &entry, path.as_deref());
+            (id, data)
+        })
+    }
+
+    /// Reads the data after the payload, see `BeamFileOptions::allow_trailing_data`
+    ///
+    /// Tools signing or packing modules sometimes append data there.
+    pub fn read_trailing(&mut self) -> Result<Vec<u8>> {
+        let mut data = vec![0; self.container.trailing_len() as usize];
+        self.reader
+            .seek(SeekFrom::Start(self.container.payload_end))
+            .and_then(|_| self.reader.read_exact(&mut data))
+            .map_err(|err| BeamFileError::from(err).in_file(self.path.as_deref()))?;
+        metrics().bytes_read(data.len() as u64);
+        Ok(data)
+    }
+
+    /// Iterates over occurrences of duplicated chunk ids not kept in the index
+    ///
+    /// Which occurrences are kept is controlled by `BeamFileOptions::duplicate_chunks`.
+    pub fn iter_duplicates_raw(&mut self) -> impl Iterator<Item = (Id, Result<Vec<u8>>)> + '_ {
         let reader = &mut self.reader;
-        self.index
+        let path = self.path.as_deref();
+        self.duplicates
             .iter()
-            .map(move |(id, entry)| (*id, Self::read_entry(reader, entry)))
+            .map(move |(id, entry)| (*id, Self::read_entry(reader, *id, entry, path)))
     }
 
-    fn read_entry(reader: &mut R, entry: &IndexEntry) -> Result<Vec<u8>> {
-        reader.seek(SeekFrom::Start(entry.position))?;
+    /// Reads a chunk, taking it from the chunks read by `prefetch` if present
+    fn read_cached(
+        reader: &mut R,
+        prefetched: &FxHashMap<Id, Prefetched>,
+        id: Id,
+        entry: &IndexEntry,
+        path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        match prefetched.get(&id) {
+            Some(chunk) => Ok(chunk.data().to_vec()),
+            None => Self::read_entry(reader, id, entry, path),
+        }
+    }
 
+    fn read_entry(
+        reader: &mut R,
+        id: Id,
+        entry: &IndexEntry,
+        path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
         let mut data = vec![0; entry.len as usize];
-        reader.read_exact(&mut data)?;
-
+        Self::read_entry_into(reader, id, entry, path, &mut data)?;
         Ok(data)
     }
 
+    fn read_entry_into(
+        reader: &mut R,
+        id: Id,
+        entry: &IndexEntry,
+        path: Option<&Path>,
+        data: &mut [u8],
+    ) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(chunk = ?id, offset = entry.position, len = entry.len, "read chunk");
+        reader
+            .seek(SeekFrom::Start(entry.position))
+            .and_then(|_| reader.read_exact(data))
+            .map_err(|err| {
+                BeamFileError::from(err)
+                    .in_chunk(id, entry.position)
+                    .in_file(path)
+            })?;
+        metrics().bytes_read(entry.len);
+        Ok(())
+    }
+
+    /// Reads a chunk for decoding, along with its position in the file
+    fn chunk_reader(&mut self, id: Id) -> Result<(Cursor<Vec<u8>>, u64)> {
+        let raw = self.read_raw(id)?;
+        Ok((Cursor::new(raw), self.index[&id].position))
+    }
+
+    /// Decodes the header of the `Code` chunk
+    pub fn code_header(&mut self) -> Result<CodeHeader> {
+        let (mut reader, position) = self.chunk_reader(CodeHeader::ID)?;
+        let result = CodeHeader::decode(&mut reader);
+        self.finish_decode(result, CodeHeader::ID, position + reader.position())
+    }
+
+    /// Decodes the module attributes from the `Attr` chunk
+    pub fn attributes(&mut self) -> Result<AttrChunk> {
+        let (mut reader, position) = self.chunk_reader(AttrChunk::ID)?;
+        let result = AttrChunk::decode(&mut reader);
+        self.finish_decode(result, AttrChunk::ID, position + reader.position())
+    }
+
+    /// Decodes the compilation information from the `CInf` chunk
+    pub fn compile_info(&mut self) -> Result<CInfChunk> {
+        let (mut reader, position) = self.chunk_reader(CInfChunk::ID)?;
+        let result = CInfChunk::decode(&mut reader);
+        self.finish_decode(result, CInfChunk::ID, position + reader.position())
+    }
+
+    /// Decodes the literal table from the `LitT` chunk
+    ///
+    /// The decompressed size is bounded by `Limits::max_decompressed_size`.
+    pub fn literals(&mut self) -> Result<LitTChunk> {
+        let (mut reader, position) = self.chunk_reader(LitTChunk::ID)?;
+        let result = LitTChunk::decode(&mut reader, self.limits.max_decompressed_size);
+        self.finish_decode(result, LitTChunk::ID, position + reader.position())
+    }
+
+    /// Decodes the documentation from the `Docs` chunk
+    ///
+    /// The decompressed size is bounded by `Limits::max_decompressed_size`.
+    pub fn docs(&mut self) -> Result<DocsChunk> {
+        let (mut reader, position) = self.chunk_reader(DocsChunk::ID)?;
+        let result = DocsChunk::decode(&mut reader, self.limits.max_decompressed_size);
+        self.finish_decode(result, DocsChunk::ID, position + reader.position())
+    }
+
+    /// Decodes the line table from the `Line` chunk
+    pub(crate) fn lines(&mut self) -> Result<LineChunk> {
+        let (mut reader, position) = self.chunk_reader(LineChunk::ID)?;
+        let result = LineChunk::decode(&mut reader);
+        self.finish_decode(result, LineChunk::ID, position + reader.position())
+    }
+
+    /// Reads the atom chunk without decoding the atoms
+    ///
+    /// Useful for streaming atom names without interning them.
+    pub fn atoms_raw(&mut self) -> Result<RawAtoms> {
+        let id = self.atom_chunk_id();
+        let raw = self.read_raw(id)?;
+        let position = self.index[&id].position;
+        RawAtoms::new(id, position, raw)
+            .and_then(|raw| {
+                Limits::check("Atom count", raw.len() as u64, self.limits.max_atoms as u64)
+                    .map_err(|err| err.in_chunk(id, position))?;
+                Ok(raw)
+            })
+            .map_err(|err| err.in_file(self.path.as_deref()))
+    }
+
+    /// Reads the module name without indexing all atoms
+    ///
+    /// Only the first atom of the atom chunk is decoded.
+    /// Returns `None` if the atom table is empty.
+    pub fn peek_name(&mut self) -> Result<Option<String>> {
+        let id = self.atom_chunk_id();
+        let entry = self.index.get(&id).ok_or(BeamFileError::MissingChunk(id))?;
+        let position = entry.position;
+        let len = entry.len;
+        let result = match self.prefetched.get(&id) {
+            Some(chunk) => peek_name_at(&mut Cursor::new(chunk.data()), 0, len),
+            None => peek_name_at(&mut self.reader, position, len),
+        };
+        result.map_err(|err| self.chunk_error(err, id, position))
+    }
+
     /// Decodes the atom chunk and stores the result for further processing
-    pub fn index_atoms(&mut self, interner: I) -> Result<()> {
-        let raw = match self.read_raw((*b"AtU8").into()) {
-            Ok(raw) => raw,
-            Err(BeamFileError::MissingChunk(_)) => self.read_raw((*b"Atom").into())?,
-            Err(err) => return Err(err),
+    ///
+    /// Names that aren't valid UTF-8 are handled according to
+    /// `BeamFileOptions::invalid_atoms`.
+    pub fn index_atoms(&mut self, mut interner: I) -> Result<()> {
+        self.index_atoms_mut(&mut interner)
+    }
+
+    /// Decodes the atom chunk with an interner requiring exclusive access
+    pub fn index_atoms_mut<T: InternerMut<Atom = I::Atom>>(
+        &mut self,
+        interner: &mut T,
+    ) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("index_atoms").entered();
+        let raw = self.atoms_raw()?;
+        self.clear_atom_warnings();
+        let atoms = match raw.iter().collect::<Result<Vec<_>>>() {
+            Ok(names) => interner.intern_many_mut(names.into_iter()),
+            Err(err) if matches!(err.root(), BeamFileError::InvalidAtom(_)) => {
+                let policy = self.invalid_atoms;
+                Self::intern_atom_bytes(&raw, interner, policy, &mut self.warnings)
+                    .map_err(|err| err.in_file(self.path.as_deref()))?
+            }
+            Err(err) => return Err(err.in_file(self.path.as_deref())),
         };
-        let mut reader = Cursor::new(raw);
 
-        let count = reader.read_u32::<BigEndian>()? as usize;
-        let mut atoms = Vec::with_capacity(count);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(atoms = atoms.len(), "indexed atoms");
+        self.atom_index = Some(atoms);
+
+        Ok(())
+    }
+
+    /// Slow path for atom tables containing names that aren't valid UTF-8
+    fn intern_atom_bytes<T: InternerMut>(
+        raw: &RawAtoms,
+        interner: &mut T,
+        policy: InvalidAtoms,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<T::Atom>> {
+        let mut atoms = Vec::with_capacity(raw.capacity_hint());
+
+        let mut names = raw.iter_bytes();
+        loop {
+            let offset = names.offset();
+            let name = match names.next() {
+                Some(name) => name?,
+                None => break,
+            };
+            let atom = match str::from_utf8(name) {
+                Ok(name) => interner.intern_mut(name),
+                Err(err) => match interner.intern_bytes_mut(name) {
+                    Some(atom) => atom,
+                    None => {
+                        let name = policy
+                            .decode(name)
+                            .ok_or_else(|| BeamFileError::from(err).in_chunk(names.id(), offset))?;
+                        warnings.push(Warning::LossyAtom {
+                            index: atoms.len(),
+                            offset,
+                        });
+                        interner.intern_mut(&name)
+                    }
+                },
+            };
+            atoms.push(atom);
+        }
 
-        let mut buf = Vec::new();
+        Ok(atoms)
+    }
 
-        for _ in 0..count {
-            let len = reader.read_u8()? as usize;
-            buf.resize(len, 0);
-            reader.read_exact(&mut buf)?;
-            let name = str::from_utf8(&buf)?;
-            atoms.push(interner.intern(name));
+    /// Decodes the atom chunk with a fallible interner
+    ///
+    /// Failures of the interner are reported as `BeamFileError::InternFailed`.
+    pub fn try_index_atoms<T: TryInterner<Atom = I::Atom>>(&mut self, interner: T) -> Result<()> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("index_atoms").entered();
+        let raw = self.atoms_raw()?;
+        self.clear_atom_warnings();
+        let path = self.path.as_deref();
+        let atoms = match raw.iter().collect::<Result<Vec<_>>>() {
+            Ok(names) => interner
+                .try_intern_many(names.into_iter())
+                .map_err(|err| BeamFileError::InternFailed(Box::new(err))),
+            Err(err) if matches!(err.root(), BeamFileError::InvalidAtom(_)) => {
+                Self::try_intern_atom_bytes(&raw, &interner, self.invalid_atoms, &mut self.warnings)
+            }
+            Err(err) => Err(err),
         }
+        .map_err(|err| err.in_file(path))?;
 
+        #[cfg(feature = "tracing")]
+        tracing::debug!(atoms = atoms.len(), "indexed atoms");
         self.atom_index = Some(atoms);
 
         Ok(())
     }
+
+    /// Slow path of `try_index_atoms`, like `intern_atom_bytes`
+    fn try_intern_atom_bytes<T: TryInterner>(
+        raw: &RawAtoms,
+        interner: &T,
+        policy: InvalidAtoms,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<T::Atom>> {
+        let intern_failed = |err| BeamFileError::InternFailed(Box::new(err));
+        let mut atoms = Vec::with_capacity(raw.capacity_hint());
+
+        let mut names = raw.iter_bytes();
+        loop {
+            let offset = names.offset();
+            let name = match names.next() {
+                Some(name) => name?,
+                None => break,
+            };
+            let atom = match str::from_utf8(name) {
+                Ok(name) => interner.try_intern(name),
+                Err(err) => match interner.try_intern_bytes(name) {
+                    Some(atom) => atom,
+                    None => {
+                        let name = policy
+                            .decode(name)
+                            .ok_or_else(|| BeamFileError::from(err).in_chunk(names.id(), offset))?;
+                        warnings.push(Warning::LossyAtom {
+                            index: atoms.len(),
+                            offset,
+                        });
+                        interner.try_intern(&name)
+                    }
+                },
+            };
+            atoms.push(atom.map_err(intern_failed)?);
+        }
+
+        Ok(atoms)
+    }
+
+    /// Decodes atom names that may not be valid UTF-8 according to `policy`
+    fn decode_atom_bytes<'a>(
+        raw: &'a RawAtoms,
+        policy: InvalidAtoms,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<Vec<Cow<'a, str>>> {
+        let mut decoded = Vec::with_capacity(raw.capacity_hint());
+
+        let mut names = raw.iter_bytes();
+        loop {
+            let offset = names.offset();
+            let name = match names.next() {
+                Some(name) => name?,
+                None => break,
+            };
+            let name = match str::from_utf8(name) {
+                Ok(name) => Cow::Borrowed(name),
+                Err(err) => {
+                    let name = policy
+                        .decode(name)
+                        .ok_or_else(|| BeamFileError::from(err).in_chunk(names.id(), offset))?;
+                    warnings.push(Warning::LossyAtom {
+                        index: decoded.len(),
+                        offset,
+                    });
+                    Cow::Owned(name)
+                }
+            };
+            decoded.push(name);
+        }
+
+        Ok(decoded)
+    }
+
+    /// Decodes the atom table into owned strings, independently of the interner
+    pub(crate) fn owned_atoms(&mut self) -> Result<Vec<String>> {
+        let raw = self.atoms_raw()?;
+        let atoms = Self::decode_atom_bytes(&raw, self.invalid_atoms, &mut Vec::new())
+            .map_err(|err| err.in_file(self.path.as_deref()))?;
+        Ok(atoms.into_iter().map(Cow::into_owned).collect())
+    }
+
+    /// Decodes the exports with atoms from `owned_atoms`, empty without an `ExpT` chunk
+    pub(crate) fn owned_exports(&mut self, atoms: &[String]) -> Result<Vec<Export<String>>> {
+        Ok(optional(self.read_with_atoms::<ExpTChunk<String>>(atoms))?
+            .map_or_else(Vec::new, |chunk| chunk.exports))
+    }
+
+    /// Decodes the imports with atoms from `owned_atoms`, empty without an `ImpT` chunk
+    pub(crate) fn owned_imports(&mut self, atoms: &[String]) -> Result<Vec<Import<String>>> {
+        Ok(optional(self.read_with_atoms::<ImpTChunk<String>>(atoms))?
+            .map_or_else(Vec::new, |chunk| chunk.imports))
+    }
+
+    /// Drops warnings from a previous `index_atoms` call
+    fn clear_atom_warnings(&mut self) {
+        self.warnings
+            .retain(|warning| !matches!(warning, Warning::LossyAtom { .. }));
+    }
 }
 
-impl<R, I: Interner> BeamFile<R, I> {
+#[cfg(feature = "std")]
+impl<R, I: InternerMut> BeamFile<R, I> {
+    /// Switches to a different interner
+    ///
+    /// Any previously indexed atoms are discarded.
+    pub fn with_interner<J: InternerMut>(self) -> BeamFile<R, J> {
+        BeamFile {
+            reader: self.reader,
+            path: self.path,
+            index: self.index,
+            duplicates: self.duplicates,
+            atom_index: None,
+            invalid_atoms: self.invalid_atoms,
+            warnings: self.warnings,
+            truncated: self.truncated,
+            container: self.container,
+            limits: self.limits,
+            prefetched: self.prefetched,
+        }
+    }
+
+    /// Returns the underlying reader
+    ///
+    /// The position of the reader is unspecified.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Returns the underlying reader along with the index and the atoms
+    pub fn into_parts(self) -> BeamFileParts<R, I::Atom> {
+        BeamFileParts {
+            chunks: self.chunks().collect(),
+            reader: self.reader,
+            atoms: self.atom_index,
+        }
+    }
+
+    /// Path of the file, when opened from the file system
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Chunks in the order they appear in the file, as `(id, offset, len)`
+    ///
+    /// The offset is the absolute position of the chunk data, after its header.
+    pub fn chunks(&self) -> impl Iterator<Item = (Id, u64, u64)> + '_ {
+        in_file_order(&self.index)
+            .into_iter()
+            .map(|(id, entry)| (id, entry.position, entry.len))
+    }
+
+    /// Whether the file contains a chunk with the id
+    pub fn has_chunk(&self, id: Id) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    /// Length of the chunk data in bytes, excluding the header and padding
+    ///
+    /// Returns `None` if the file doesn't contain the chunk.
+    pub fn chunk_size(&self, id: Id) -> Option<u64> {
+        self.index.get(&id).map(|entry| entry.len)
+    }
+
+    /// Decodes a chunk read by `read_many`
+    pub(crate) fn decode_raw<C: Chunk>(
+        &self,
+        chunks: &[(Id, u64, Vec<u8>)],
+        atom_index: &[C::Atom],
+    ) -> Result<C> {
+        let (_, position, data) = chunks
+            .iter()
+            .find(|(id, _, _)| *id == C::ID)
+            .ok_or(BeamFileError::MissingChunk(C::ID))?;
+        let mut reader = Cursor::new(data.as_slice());
+        let result = C::decode(&mut reader, atom_index);
+        self.finish_decode(result, C::ID, position + reader.position())
+    }
+
+    /// Reports the outcome of decoding a chunk, attaching the location to errors
+    fn finish_decode<T>(&self, result: Result<T>, id: Id, offset: u64) -> Result<T> {
+        match result {
+            Ok(chunk) => {
+                metrics().chunk_decoded(id);
+                Ok(chunk)
+            }
+            Err(err) => {
+                metrics().decode_failed(id);
+                Err(self.chunk_error(err, id, offset))
+            }
+        }
+    }
+
+    /// Attaches the location to an error from decoding a chunk
+    fn chunk_error(&self, err: BeamFileError, id: Id, offset: u64) -> BeamFileError {
+        err.in_chunk(id, offset).in_file(self.path.as_deref())
+    }
+
+    /// Id of the atom chunk, preferring the UTF-8 `AtU8` over the legacy `Atom`
+    fn atom_chunk_id(&self) -> Id {
+        let utf8 = Id::AT_U8;
+        if self.has_chunk(utf8) {
+            utf8
+        } else {
+            Id::ATOM
+        }
+    }
+
     /// Returns the module name
     ///
     /// Returns `None` if atoms weren't indexed yet.
     /// Relies on the fact that the module name is the first atom in the atom table.
     pub fn name(&self) -> Option<&I::Atom> {
-        self.atom_index.as_ref().and_then(|index| index.get(0))
+        self.atom_index.as_ref().and_then(|index| index.first())
     }
 
     pub fn atom_index(&self) -> Option<&[I::Atom]> {
         self.atom_index.as_deref()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Whether the file ended before the declared end of the payload
+    ///
+    /// Only possible with `BeamFileOptions::recover_truncated`.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
 
-    #[test]
-    fn index_atoms() {
-        let mut file = BeamFile::<_, NaiveInterner>::from_file("fixtures/test.beam").unwrap();
+    /// Sizes of the container, including any trailing data
+    pub fn container(&self) -> Container {
+        self.container
+    }
+
+    /// Recoverable anomalies found so far
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+}
+
+/// Maps `MissingChunk` to `None`, for chunks that may be legitimately absent
+#[cfg(feature = "std")]
+pub(crate) fn optional<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Err(BeamFileError::MissingChunk(_)) => Ok(None),
+        result => result.map(Some),
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn beam(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut payload = b"BEAM".to_vec();
+        for (id, data) in chunks {
+            payload.extend_from_slice(&id[..]);
+            payload.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            payload.extend_from_slice(data);
+            payload.resize(4 * payload.len().div_ceil(4), 0);
+        }
+
+        let mut file = b"FOR1".to_vec();
+        file.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        file.extend_from_slice(&payload);
+        file
+    }
+
+    /// Imports of `bif_module`, as the atom of the `erlang` function and its arity
+    const BIF_IMPORTS: [(u32, u32); 4] = [(4, 0), (5, 1), (6, 2), (7, 2)];
+
+    /// A module calling `hd/1` with `bif1`, `element/2` with `bif2` and `+/2` with `gc_bif2`
+    ///
+    /// With `BIF_IMPORTS`, the first import, `erlang:unused/0`, isn't called.
+    fn bif_module(imports: &[(u32, u32)], code: &[u8]) -> Vec<u8> {
+        let atoms = b"\x00\x00\x00\x07\x01m\x01f\x06erlang\x06unused\x02hd\x07element\x01+";
+        let mut table = (imports.len() as u32).to_be_bytes().to_vec();
+        for &(function, arity) in imports {
+            for field in [3, function, arity] {
+                table.extend_from_slice(&field.to_be_bytes());
+            }
+        }
+        let mut chunk = b"\x00\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\xb7\
+                          \x00\x00\x00\x03\x00\x00\x00\x01"
+            .to_vec();
+        chunk.extend_from_slice(code);
+        beam(&[(b"AtU8", atoms), (b"ImpT", &table), (b"Code", &chunk)])
+    }
+
+    /// Code for `bif_module`: label 1, func_info m f 0, label 2, the BIF calls, return
+    const BIF_CODE: &[u8] = b"\x01\x10\x02\x12\x22\x00\x01\x20\
+                              \x0a\x05\x10\x03\x03\
+                              \x0b\x05\x20\x03\x13\x03\
+                              \x7d\x05\x00\x30\x03\x13\x03\
+                              \x13\x03";
+
+    /// Builders for terms in the external term format
+    mod etf {
+        pub fn atom(name: &str) -> Vec<u8> {
+            let mut term = vec![0x77, name.len() as u8];
+            term.extend_from_slice(name.as_bytes());
+            term
+        }
+
+        pub fn binary(data: &str) -> Vec<u8> {
+            let mut term = vec![0x6d];
+            term.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            term.extend_from_slice(data.as_bytes());
+            term
+        }
+
+        pub fn int(value: u8) -> Vec<u8> {
+            vec![0x61, value]
+        }
+
+        pub fn tuple(elements: &[Vec<u8>]) -> Vec<u8> {
+            let mut term = vec![0x68, elements.len() as u8];
+            term.extend(elements.concat());
+            term
+        }
+
+        pub fn list(elements: &[Vec<u8>]) -> Vec<u8> {
+            if elements.is_empty() {
+                return vec![0x6a];
+            }
+            let mut term = vec![0x6c];
+            term.extend_from_slice(&(elements.len() as u32).to_be_bytes());
+            term.extend(elements.concat());
+            term.push(0x6a);
+            term
+        }
+
+        pub fn map(pairs: &[(Vec<u8>, Vec<u8>)]) -> Vec<u8> {
+            let mut term = vec![0x74];
+            term.extend_from_slice(&(pairs.len() as u32).to_be_bytes());
+            for (key, value) in pairs {
+                term.extend_from_slice(key);
+                term.extend_from_slice(value);
+            }
+            term
+        }
+
+        /// Prefixes the version byte and compresses the term
+        pub fn compressed(term: &[u8]) -> Vec<u8> {
+            use flate2::{write::ZlibEncoder, Compression};
+            use std::io::Write;
+
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(term).unwrap();
+            let mut data = vec![131, 80];
+            data.extend_from_slice(&(term.len() as u32).to_be_bytes());
+            data.extend_from_slice(&encoder.finish().unwrap());
+            data
+        }
+    }
+
+    #[test]
+    fn index_atoms() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
 
         assert_eq!(file.name(), None);
-        file.index_atoms(NaiveInterner::default()).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
         assert_eq!(file.name(), Some(&"test".to_string()));
 
-        assert_eq!(file.atom_index().unwrap().len(), 4);
+        assert_eq!(file.atom_index().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn resolve_atoms() {
+        let interner = NaiveInterner;
+        let mut file = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&NaiveInterner>();
+        file.index_atoms(&interner).unwrap();
+
+        let name = file.name().unwrap();
+        assert_eq!(interner.resolve(name), "test");
+    }
+
+    #[test]
+    fn arc_interner() {
+        let interner = ArcInterner::new();
+        let mut first = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&ArcInterner>();
+        let mut second = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&ArcInterner>();
+        first.index_atoms(&interner).unwrap();
+        second.index_atoms(&interner).unwrap();
+
+        assert_eq!(interner.len(), 4);
+        assert!(std::sync::Arc::ptr_eq(
+            first.name().unwrap(),
+            second.name().unwrap()
+        ));
+    }
+
+    #[test]
+    fn sharded_interner() {
+        let interner = ShardedInterner::with_shards(3);
+        let names: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut file = BeamFile::from_file("fixtures/test.beam")
+                            .unwrap()
+                            .with_interner::<&ShardedInterner>();
+                        file.index_atoms(&interner).unwrap();
+                        file.name().unwrap().clone()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert_eq!(interner.len(), 4);
+        assert!(names
+            .iter()
+            .all(|name| std::sync::Arc::ptr_eq(name, &names[0])));
+    }
+
+    #[test]
+    fn symbol_table() {
+        let table = SymbolTable::new();
+        let mut first = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&SymbolTable>();
+        let mut second = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&SymbolTable>();
+        first.index_atoms(&table).unwrap();
+        second.index_atoms(&table).unwrap();
+
+        assert_eq!(table.len(), 4);
+        assert_eq!(first.atom_index(), second.atom_index());
+        assert_eq!(table.resolve(first.name().unwrap()), "test");
+        assert_eq!(table.get("erlang"), Some(first.atom_index().unwrap()[2]));
+
+        // Spans several segments of the table
+        let names: Vec<_> = (0..1000).map(|i| format!("atom_{}", i)).collect();
+        let symbols = table.intern_many(names.iter().map(String::as_str));
+        assert!(symbols
+            .iter()
+            .zip(&names)
+            .all(|(symbol, name)| table.resolve(symbol) == name));
+        assert_eq!(table.len(), 1004);
+        assert_eq!(table.get("atom_999"), Some(symbols[999]));
+
+        let other = SymbolTable::new();
+        let symbol = other.intern("test");
+        assert_eq!(other.try_resolve(&symbol), Some("test"));
+        assert_eq!(SymbolTable::new().try_resolve(&symbol), None);
+        if cfg!(debug_assertions) {
+            assert_eq!(table.try_resolve(&symbol), None);
+        }
+    }
+
+    #[cfg(feature = "lasso")]
+    #[test]
+    fn lasso_interner() {
+        let rodeo = lasso::ThreadedRodeo::default();
+        let mut file = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&lasso::ThreadedRodeo>();
+        file.index_atoms(&rodeo).unwrap();
+
+        assert_eq!(Resolver::resolve(&rodeo, file.name().unwrap()), "test");
+    }
+
+    #[cfg(feature = "string-interner")]
+    #[test]
+    fn string_interner() {
+        let interner = SyncStringInterner::<string_interner::DefaultBackend>::default();
+        let mut file = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<&SyncStringInterner>();
+        file.index_atoms(&interner).unwrap();
+        let name = *file.name().unwrap();
+
+        assert_eq!(interner.into_inner().resolve(name), Some("test"));
+    }
+
+    #[cfg(feature = "ustr")]
+    #[test]
+    fn ustr_interner() {
+        let mut first = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<UstrInterner>();
+        let mut second = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<UstrInterner>();
+        first.index_atoms(UstrInterner).unwrap();
+        second.index_atoms(UstrInterner).unwrap();
+
+        assert_eq!(first.name(), second.name());
+        assert_eq!(first.name().unwrap().as_str(), "test");
+    }
+
+    #[test]
+    fn try_index_atoms() {
+        #[derive(Debug, thiserror::Error)]
+        #[error("atom too long")]
+        struct TooLong;
+
+        struct ShortInterner;
+
+        impl TryInterner for ShortInterner {
+            type Atom = String;
+            type Error = TooLong;
+
+            fn try_intern(&self, name: &str) -> std::result::Result<String, TooLong> {
+                if name.len() > 6 {
+                    Err(TooLong)
+                } else {
+                    Ok(name.to_string())
+                }
+            }
+        }
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let err = file.try_index_atoms(ShortInterner).unwrap_err();
+
+        assert!(matches!(err.root(), BeamFileError::InternFailed(_)));
+        assert_eq!(file.atom_index(), None);
+    }
+
+    #[test]
+    fn index_atoms_mut() {
+        #[derive(Default)]
+        struct MapInterner(FxHashMap<String, usize>);
+
+        impl InternerMut for MapInterner {
+            type Atom = usize;
+
+            fn intern_mut(&mut self, name: &str) -> usize {
+                let next = self.0.len();
+                *self.0.entry(name.to_string()).or_insert(next)
+            }
+        }
+
+        let mut interner = MapInterner::default();
+        let mut first = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<MapInterner>();
+        let mut second = BeamFile::from_file("fixtures/test.beam")
+            .unwrap()
+            .with_interner::<MapInterner>();
+        first.index_atoms_mut(&mut interner).unwrap();
+        second.index_atoms_mut(&mut interner).unwrap();
+
+        assert_eq!(interner.0.len(), 4);
+        assert_eq!(first.atom_index(), Some(&[0, 1, 2, 3][..]));
+        assert_eq!(first.atom_index(), second.atom_index());
+    }
+
+    #[test]
+    fn intern_bytes() {
+        struct BytesInterner;
+
+        impl Interner for BytesInterner {
+            type Atom = Vec<u8>;
+
+            fn intern(&self, name: &str) -> Vec<u8> {
+                name.as_bytes().to_vec()
+            }
+
+            fn intern_bytes(&self, name: &[u8]) -> Option<Vec<u8>> {
+                Some(name.to_vec())
+            }
+        }
+
+        let data = beam(&[(b"Atom", b"\x00\x00\x00\x02\x04test\x04caf\xe9")]);
+
+        let mut file = BeamFile::from_reader(Cursor::new(&data))
+            .unwrap()
+            .with_interner::<BytesInterner>();
+        file.index_atoms(BytesInterner).unwrap();
+        assert_eq!(file.atom_index().unwrap()[1], b"caf\xe9");
+        let expected = file.atom_index().unwrap().to_vec();
+        file.try_index_atoms(BytesInterner).unwrap();
+        assert_eq!(file.atom_index().unwrap(), expected);
+
+        let mut file = BeamFile::from_reader(Cursor::new(&data)).unwrap();
+        let err = file.index_atoms(NaiveInterner).unwrap_err();
+        assert!(matches!(err.root(), BeamFileError::InvalidAtom(_)));
+    }
+
+    #[test]
+    fn lossy_atoms() {
+        let data = beam(&[(b"Atom", b"\x00\x00\x00\x02\x04test\x04caf\xe9")]);
+
+        let mut file = BeamFileOptions::new()
+            .invalid_atoms(InvalidAtoms::Replace)
+            .from_reader(Cursor::new(&data))
+            .unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        assert_eq!(file.atom_index().unwrap()[1], "caf\u{fffd}");
+        assert_eq!(
+            file.warnings(),
+            [Warning::LossyAtom {
+                index: 1,
+                offset: 29
+            }]
+        );
+
+        let mut file = BeamFileOptions::new()
+            .invalid_atoms(InvalidAtoms::HexEscape)
+            .from_reader(Cursor::new(&data))
+            .unwrap();
+        file.try_index_atoms(NaiveInterner).unwrap();
+        assert_eq!(file.atom_index().unwrap()[1], "caf\\xe9");
+        assert_eq!(file.warnings().len(), 1);
+    }
+
+    #[test]
+    fn atoms_raw() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let raw = file.atoms_raw().unwrap();
+
+        assert_eq!(raw.len(), 4);
+        let names: Vec<&str> = raw.iter().map(|name| name.unwrap()).collect();
+        assert_eq!(names, ["test", "module_info", "erlang", "get_module_info"]);
+    }
+
+    #[test]
+    fn peek_name() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+
+        assert_eq!(file.peek_name().unwrap(), Some("test".to_string()));
+        assert_eq!(file.name(), None);
+    }
+
+    #[test]
+    fn borrowed_atoms() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let raw = file.atoms_raw().unwrap();
+        let atoms = raw.intern_borrowed(&StrInterner).unwrap();
+        let chunk: ExpTChunk<&str> = file.read_with_atoms(&atoms).unwrap();
+
+        assert_eq!(chunk.exports[0].function, "module_info");
+        assert_eq!(file.atom_index(), None);
+    }
+
+    #[test]
+    fn read_without_atoms() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let result = file.read::<ImpTChunk<String>>();
+
+        assert!(matches!(result, Err(BeamFileError::AtomsNotIndexed)));
+
+        // The misuse is reported ahead of the missing chunk
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x01m")]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert!(matches!(
+            file.read::<ImpTChunk<String>>(),
+            Err(BeamFileError::AtomsNotIndexed)
+        ));
+        assert!(matches!(
+            file.read_many::<(ImpTChunk<_>, ExpTChunk<_>)>(),
+            Err(BeamFileError::AtomsNotIndexed)
+        ));
+        #[cfg(feature = "arena")]
+        assert!(matches!(
+            file.read_in::<ArenaImpTChunk<String>>(&bumpalo::Bump::new()),
+            Err(BeamFileError::AtomsNotIndexed)
+        ));
+    }
+
+    #[test]
+    fn read_in_context() {
+        /// Exports along with the import table, which the `ExpT` chunk can't decode alone
+        struct Linked {
+            exports: Vec<Export<String>>,
+            imports: Option<ImpTChunk<String>>,
+            has_code: bool,
+        }
+
+        impl ContextChunk for Linked {
+            const ID: Id = Id::EXP_T;
+            type Atom = String;
+
+            fn decode_in_context<R: Read>(
+                reader: R,
+                context: &mut DecodeContext<'_, String>,
+            ) -> Result<Self> {
+                Ok(Linked {
+                    exports: ExpTChunk::decode(reader, context.atoms())?.exports,
+                    imports: context.read_opt()?,
+                    has_code: context.has_chunk(Id::CODE),
+                })
+            }
+        }
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(matches!(
+            file.read_in_context::<Linked>(),
+            Err(BeamFileError::AtomsNotIndexed)
+        ));
+        file.index_atoms(NaiveInterner).unwrap();
+        let linked = file.read_in_context::<Linked>().unwrap();
+        assert_eq!(linked.exports.len(), 2);
+        assert_eq!(linked.imports.unwrap().imports[0].module, "erlang");
+        assert!(linked.has_code);
+
+        let atoms = b"\x00\x00\x00\x01\x04test";
+        let imports = [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 9, 0, 0, 0, 0];
+        let data = beam(&[(b"AtU8", atoms), (b"ExpT", b"\x00\x00\x00\x00")]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let linked = file.read_in_context::<Linked>().unwrap();
+        assert!(linked.imports.is_none());
+        assert!(!linked.has_code);
+
+        let data = beam(&[
+            (b"AtU8", atoms),
+            (b"ExpT", b"\x00\x00\x00\x00"),
+            (b"ImpT", &imports),
+        ]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let err = file.read_in_context::<Linked>().err().unwrap();
+        assert_eq!(err.context().unwrap().chunk, Some(Id::IMP_T));
+        assert!(matches!(
+            err.into_root(),
+            BeamFileError::InvalidAtomIndex { index: 9, .. }
+        ));
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let linked = file.read_in_context::<code::LinkedCode<String>>().unwrap();
+        assert_eq!(linked.code.functions().len(), 2);
+        assert_eq!(linked.imports[1].function, "get_module_info");
+        assert!(linked.literals.is_empty());
+
+        // Literal table claiming 9 bytes of terms with no compressed data
+        let mut data = bif_module(&BIF_IMPORTS, BIF_CODE);
+        data.extend_from_slice(b"LitT\x00\x00\x00\x04\x00\x00\x00\x09");
+        let payload_size = data.len() as u32 - 8;
+        data[4..8].copy_from_slice(&payload_size.to_be_bytes());
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let err = file
+            .read_in_context::<code::LinkedCode<String>>()
+            .err()
+            .unwrap();
+        assert_eq!(err.context().unwrap().chunk, Some(Id::LIT_T));
+    }
+
+    #[test]
+    fn invalid_atom_index() {
+        let atoms = b"\x00\x00\x00\x01\x04test";
+        for index in [0u8, 5] {
+            let exports = [0, 0, 0, 1, 0, 0, 0, index, 0, 0, 0, 0, 0, 0, 0, 1];
+            let data = beam(&[(b"AtU8", atoms), (b"ExpT", &exports)]);
+            let mut file = BeamFile::from_reader(Cursor::new(data)).unwrap();
+            file.index_atoms(NaiveInterner).unwrap();
+
+            match file
+                .read::<ExpTChunk<String>>()
+                .map_err(BeamFileError::into_root)
+            {
+                Err(BeamFileError::InvalidAtomIndex { chunk, index: i }) => {
+                    assert_eq!(chunk, Id(*b"ExpT"));
+                    assert_eq!(i, index as u32);
+                }
+                other => panic!("unexpected result {:?}", other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn hostile_counts() {
+        let atoms = b"\xff\xff\xff\xff\x04test";
+        let exports = b"\xff\xff\xff\xff";
+        let data = beam(&[(b"AtU8", atoms), (b"ExpT", exports)]);
+        let mut file = BeamFile::from_reader(Cursor::new(data)).unwrap();
+
+        assert_eq!(file.atoms_raw().unwrap().capacity_hint(), 5);
+        let err = file.index_atoms(NaiveInterner).unwrap_err();
+        assert!(matches!(err.root(), BeamFileError::Io(_)));
+
+        let err = file
+            .read_with_atoms::<ExpTChunk<&str>>(&["test"])
+            .err()
+            .unwrap();
+        assert!(matches!(err.root(), BeamFileError::Io(_)));
+    }
+
+    #[test]
+    fn chunk_out_of_bounds() {
+        let mut data = beam(&[(b"AtU8", b"\x00\x00\x00\x00"), (b"StrT", b"")]);
+        data[28..32].copy_from_slice(&100u32.to_be_bytes());
+
+        match BeamFile::from_reader(Cursor::new(data)) {
+            Err(BeamFileError::ChunkOutOfBounds {
+                chunk,
+                position,
+                len,
+            }) => {
+                assert_eq!(chunk, Id(*b"StrT"));
+                assert_eq!(position, 32);
+                assert_eq!(len, 100);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+
+        let leftovers = [(&b"sig"[..], *b"sig\x00"), (b"sign\x00\x00\x00", *b"sign")];
+        for (leftover, expected) in leftovers {
+            let data = leftover_payload(leftover);
+            let err = BeamFile::from_slice(&data).err().unwrap();
+            assert!(
+                matches!(err, BeamFileError::ChunkOutOfBounds { chunk, position: 20, len }
+                    if chunk == Id(expected) && len == leftover.len() as u64),
+                "unexpected error {:?}",
+                err
+            );
+            let err = BeamSlice::new(&data).err().unwrap();
+            assert!(matches!(
+                err,
+                BeamFileError::ChunkOutOfBounds { position: 20, .. }
+            ));
+        }
+    }
+
+    /// A file with `leftover` bytes in the payload after an empty `StrT` chunk
+    fn leftover_payload(leftover: &[u8]) -> Vec<u8> {
+        let mut data = beam(&[(b"StrT", b"")]);
+        data.extend_from_slice(leftover);
+        let payload_size = data.len() as u32 - 8;
+        data[4..8].copy_from_slice(&payload_size.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn payload_past_end_of_file() {
+        let mut data = beam(&[(b"AtU8", b"\x00\x00\x00\x00")]);
+        data.truncate(data.len() - 2);
+
+        match BeamFile::from_reader(Cursor::new(data)) {
+            Err(BeamFileError::InvalidPayloadSize {
+                declared,
+                available,
+            }) => {
+                assert_eq!(declared, 16);
+                assert_eq!(available, 14);
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn truncated_file() {
+        let mut data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x01\x04test"),
+            (b"StrT", b"abcdefgh"),
+        ]);
+        data.truncate(data.len() - 3);
+
+        let err = BeamFile::from_reader(Cursor::new(&data)).unwrap_err();
+        assert!(matches!(err, BeamFileError::InvalidPayloadSize { .. }));
+
+        let mut file = BeamFileOptions::new()
+            .recover_truncated(true)
+            .from_reader(Cursor::new(&data))
+            .unwrap();
+        assert!(file.is_truncated());
+        assert_eq!(
+            file.warnings(),
+            [Warning::Truncated {
+                declared: 40,
+                available: 37
+            }]
+        );
+        assert!(file.read_raw(Id(*b"AtU8")).is_ok());
+        assert!(matches!(
+            file.read_raw(Id(*b"StrT")),
+            Err(BeamFileError::MissingChunk(_))
+        ));
+
+        let file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(!file.is_truncated());
+    }
+
+    #[test]
+    fn limits() {
+        let options = |limits: &Limits| {
+            let mut options = BeamFileOptions::new();
+            options.limits(*limits);
+            options
+        };
+
+        let err = options(Limits::new().max_chunk_size(64))
+            .open("fixtures/test.beam")
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            BeamFileError::LimitExceeded {
+                limit: "Chunk size",
+                max: 64,
+                ..
+            }
+        ));
+
+        let err = options(Limits::new().max_total_size(256))
+            .open("fixtures/test.beam")
+            .unwrap_err();
+        assert!(matches!(
+            err.root(),
+            BeamFileError::LimitExceeded {
+                limit: "Total chunk size",
+                ..
+            }
+        ));
+        assert_eq!(err.kind(), ErrorKind::Limits);
+
+        let mut file = options(Limits::new().max_atoms(3))
+            .open("fixtures/test.beam")
+            .unwrap();
+        let err = file.index_atoms(NaiveInterner).unwrap_err();
+        assert!(matches!(
+            err.root(),
+            BeamFileError::LimitExceeded {
+                limit: "Atom count",
+                value: 4,
+                max: 3
+            }
+        ));
+
+        let mut file = options(Limits::new().max_atoms(4))
+            .open("fixtures/test.beam")
+            .unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+    }
+
+    #[test]
+    fn lenient_options() {
+        let data = beam(&[(b"StrT", b"")]);
+
+        let mut trailing = data.clone();
+        trailing.extend_from_slice(b"garbage");
+        let mut file = BeamFile::from_reader(Cursor::new(&trailing)).unwrap();
+        assert_eq!(
+            file.container(),
+            Container {
+                start: 0,
+                declared_payload_size: data.len() as u64 - 8,
+                payload_end: data.len() as u64,
+                file_size: trailing.len() as u64,
+            }
+        );
+        assert_eq!(file.container().trailing_len(), 7);
+        assert_eq!(file.read_trailing().unwrap(), b"garbage");
+        assert_eq!(BeamSlice::new(&trailing).unwrap().trailing(), b"garbage");
+        assert!(BeamSlice::new(&data).unwrap().trailing().is_empty());
+        let result = BeamFileOptions::new()
+            .allow_trailing_data(false)
+            .from_reader(Cursor::new(&trailing));
+        assert!(matches!(result, Err(BeamFileError::TrailingData(7))));
+        for garbage in [&b"garbage"[..], &[0xff; 16]] {
+            let mut trailing = data.clone();
+            trailing.extend_from_slice(garbage);
+            let mut file = BeamFileOptions::lenient()
+                .from_reader(Cursor::new(&trailing))
+                .unwrap();
+            assert_eq!(file.read_trailing().unwrap(), garbage);
+            assert_eq!(file.read_raw(Id(*b"StrT")).unwrap(), b"");
+        }
+
+        let mut wrong_size = data.clone();
+        wrong_size[4..8].copy_from_slice(&100u32.to_be_bytes());
+        assert!(BeamFile::from_reader(Cursor::new(&wrong_size)).is_err());
+        let mut file = BeamFileOptions::new()
+            .ignore_payload_size(true)
+            .from_reader(Cursor::new(&wrong_size))
+            .unwrap();
+        assert_eq!(file.read_raw(Id(*b"StrT")).unwrap(), b"");
+
+        let mut unpadded = beam(&[(b"StrT", b"a")]);
+        unpadded.truncate(unpadded.len() - 3);
+        assert!(BeamFile::from_reader(Cursor::new(&unpadded)).is_err());
+        let mut file = BeamFileOptions::new()
+            .allow_unpadded_final_chunk(true)
+            .from_reader(Cursor::new(&unpadded))
+            .unwrap();
+        assert_eq!(file.read_raw(Id(*b"StrT")).unwrap(), b"a");
+    }
+
+    #[test]
+    fn warnings() {
+        let mut data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x01\x04test"),
+            (b"Xtra", b"\x01"),
+            (b"AtU8", b"\x00\x00\x00\x00"),
+        ]);
+        // Padding after the `Xtra` chunk
+        data[41] = 0xff;
+        data.extend_from_slice(b"junk");
+
+        let file = BeamFile::from_reader(Cursor::new(&data)).unwrap();
+        assert_eq!(
+            file.warnings(),
+            [
+                Warning::TrailingData(4),
+                Warning::UnknownChunk {
+                    chunk: Id(*b"Xtra"),
+                    position: 40
+                },
+                Warning::NonZeroPadding {
+                    chunk: Id(*b"Xtra"),
+                    position: 41
+                },
+                Warning::DuplicateChunk(Id(*b"AtU8")),
+            ]
+        );
+
+        let file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert_eq!(file.warnings(), []);
+
+        let data = beam(&[(b"Meta", b"\x83\x6a"), (b"Type", b"")]);
+        let file = BeamFile::from_slice(&data).unwrap();
+        assert_eq!(file.warnings(), []);
+    }
+
+    #[test]
+    fn duplicate_chunks() {
+        let data = beam(&[(b"StrT", b"one"), (b"StrT", b"two")]);
+
+        let mut file = BeamFile::from_reader(Cursor::new(&data)).unwrap();
+        assert_eq!(file.read_raw(Id(*b"StrT")).unwrap(), b"two");
+
+        let mut file = BeamFileOptions::new()
+            .duplicate_chunks(DuplicateChunks::FirstWins)
+            .from_reader(Cursor::new(&data))
+            .unwrap();
+        assert_eq!(file.read_raw(Id(*b"StrT")).unwrap(), b"one");
+        let duplicates: Vec<_> = file
+            .iter_duplicates_raw()
+            .map(|(id, data)| (id, data.unwrap()))
+            .collect();
+        assert_eq!(duplicates, [(Id(*b"StrT"), b"two".to_vec())]);
+
+        let result = BeamFileOptions::new()
+            .duplicate_chunks(DuplicateChunks::Error)
+            .from_reader(Cursor::new(&data));
+        assert!(matches!(result, Err(BeamFileError::DuplicateChunk(id)) if id == Id(*b"StrT")));
+    }
+
+    #[test]
+    fn code_header() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+
+        assert_eq!(
+            file.code_header().unwrap(),
+            CodeHeader {
+                instruction_set: 0,
+                opcode_max: 169,
+                label_count: 5,
+                function_count: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn verify() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(file.verify().unwrap().is_ok());
+
+        let atoms = b"\x00\x00\x00\x02\x04test";
+        let code = [
+            0, 0, 0, 16, 0, 0, 0, 0, 0, 0, 0, 169, 0, 0, 0, 3, 0, 0, 0, 1,
+        ];
+        let exports = [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 7];
+        let imports = [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 9, 0, 0, 0, 0];
+        let data = beam(&[
+            (b"AtU8", atoms),
+            (b"Code", &code),
+            (b"ExpT", &exports),
+            (b"ImpT", &imports),
+        ]);
+        let mut file = BeamFile::from_reader(Cursor::new(data)).unwrap();
+
+        assert_eq!(
+            file.verify().unwrap().problems,
+            [
+                VerifyProblem::MissingChunk(Id(*b"StrT")),
+                VerifyProblem::AtomCountMismatch {
+                    declared: 2,
+                    actual: 1
+                },
+                VerifyProblem::InvalidAtomIndex {
+                    chunk: Id(*b"ImpT"),
+                    index: 9
+                },
+                VerifyProblem::LabelOutOfRange {
+                    chunk: Id(*b"ExpT"),
+                    label: 7,
+                    label_count: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn error_context() {
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x02\x04test\x02\xff\xff")]);
+        let mut file = BeamFile::from_reader(Cursor::new(data)).unwrap();
+        let err = file.index_atoms(NaiveInterner).unwrap_err();
+
+        let context = err.context().unwrap();
+        assert_eq!(context.path, None);
+        assert_eq!(context.chunk, Some(Id(*b"AtU8")));
+        assert_eq!(context.offset, Some(29));
+        assert_eq!(err.to_string(), "chunk b\"AtU8\", offset 29: Invalid atom");
+        assert_eq!(err.kind(), ErrorKind::Atoms);
+
+        let err = BeamFile::from_file("fixtures/missing.beam").unwrap_err();
+        assert_eq!(
+            err.context().unwrap().path.as_deref(),
+            Some(Path::new("fixtures/missing.beam"))
+        );
+        assert!(matches!(err.root(), BeamFileError::Io(_)));
+        assert_eq!(err.kind(), ErrorKind::Io);
+    }
+
+    #[cfg(feature = "fuzzing")]
+    #[test]
+    fn arbitrary_beam() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let seed: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&seed);
+        while !u.is_empty() {
+            let ArbitraryBeam(data) = ArbitraryBeam::arbitrary(&mut u).unwrap();
+            let mut file = BeamFile::from_reader(Cursor::new(data)).unwrap();
+            assert!(file.verify().unwrap().is_ok());
+            file.index_atoms(NaiveInterner).unwrap();
+            file.read::<ExpTChunk<_>>().unwrap();
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let chunk: ExpTChunk<String> = file.read().unwrap();
+
+        let json = serde_json::to_value(&chunk).unwrap();
+        assert_eq!(
+            json["exports"][0],
+            serde_json::json!({"function": "module_info", "arity": 1, "label": 4})
+        );
+        let decoded: ExpTChunk<String> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.exports, chunk.exports);
+
+        let id = serde_json::to_string(&Id(*b"AtU8")).unwrap();
+        assert_eq!(id, "\"AtU8\"");
+        assert_eq!(serde_json::from_str::<Id>(&id).unwrap(), Id(*b"AtU8"));
+        assert!(serde_json::from_str::<Id>("\"Atom8\"").is_err());
+
+        let metrics = file.function_metrics().unwrap();
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json[0]["name"], "module_info");
+        let decoded: Vec<FunctionMetrics> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, metrics);
+
+        let fingerprints = file.function_fingerprints().unwrap();
+        let json = serde_json::to_value(&fingerprints).unwrap();
+        assert_eq!(json[1]["arity"], 1);
+        let decoded: Vec<FunctionFingerprint> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, fingerprints);
+
+        let hashes = file.chunk_hashes().unwrap();
+        let json = serde_json::to_value(&hashes).unwrap();
+        assert_eq!(json["chunks"][0][1], hashes.chunks[0].1.to_string());
+        let decoded: ChunkHashes = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, hashes);
+        assert!(serde_json::from_str::<ChunkHash>("\"00ff\"").is_err());
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn module_summary() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let summary = ModuleSummary::new(&mut file).unwrap();
+        assert_eq!(summary.name(), Some("test"));
+        assert_eq!(summary.chunks[0], (Id(*b"AtU8"), 20, 44));
+        assert_eq!(summary.exports.len(), 2);
+        assert_eq!(summary.imports[0].function, "get_module_info");
+        assert_eq!(summary.attributes[0].0, "vsn");
+
+        let data = summary.to_bytes();
+        assert_eq!(&data[..4], b"FBMS");
+        assert_eq!(ModuleSummary::from_bytes(&data).unwrap(), summary);
+
+        let mut other_version = data.clone();
+        other_version[5] += 1;
+        let err = ModuleSummary::from_bytes(&other_version).unwrap_err();
+        assert!(matches!(
+            err,
+            BeamFileError::InvalidCache("unsupported version")
+        ));
+        assert!(ModuleSummary::from_bytes(&data[..data.len() - 1]).is_err());
+        assert!(ModuleSummary::from_bytes(b"FOR1").is_err());
+    }
+
+    #[test]
+    fn attributes() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let attributes = file.attributes().unwrap().attributes;
+        assert_eq!(attributes[0].0, "vsn");
+
+        let info = file.compile_info().unwrap().info;
+        let keys: Vec<&str> = info.iter().map(|(key, _)| key.as_str()).collect();
+        assert!(keys.contains(&"version"));
+    }
+
+    #[test]
+    fn decode_terms() {
+        let decode = |data: &[u8]| Term::decode(data).unwrap();
+
+        assert_eq!(decode(b"\x83\x61\x2a"), Term::Integer(42));
+        assert_eq!(decode(b"\x83\x62\xff\xff\xff\xfe"), Term::Integer(-2));
+        assert_eq!(
+            decode(b"\x83\x6e\x05\x01\x00\x00\x00\x00\x01"),
+            Term::Integer(-(1 << 32))
+        );
+        assert_eq!(
+            decode(b"\x83\x6e\x09\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01"),
+            Term::BigInt {
+                negative: false,
+                digits: vec![0, 0, 0, 0, 0, 0, 0, 0, 1]
+            }
+        );
+        assert_eq!(
+            decode(b"\x83\x68\x02\x77\x02ok\x6b\x00\x02hi"),
+            Term::Tuple(vec![Term::Atom("ok".into()), Term::String(b"hi".to_vec())])
+        );
+        assert_eq!(
+            decode(b"\x83\x6c\x00\x00\x00\x01\x64\x00\x01\xe9\x6a"),
+            Term::List(vec![Term::Atom("\u{e9}".into())], Box::new(Term::Nil))
+        );
+        let list = decode(b"\x83\x6c\x00\x00\x00\x01\x64\x00\x01\xe9\x6a");
+        assert_eq!(list.as_list(), Some(&[Term::Atom("\u{e9}".into())][..]));
+        let string = Term::String(b"hi".to_vec());
+        assert_eq!(string.as_list(), None);
+        assert_eq!(
+            string.into_list(),
+            Some(vec![Term::Integer(104), Term::Integer(105)])
+        );
+        assert_eq!(
+            decode(b"\x83\x74\x00\x00\x00\x01\x6a\x6d\x00\x00\x00\x01\x07"),
+            Term::Map(vec![(Term::Nil, Term::Binary(vec![7]))])
+        );
+
+        let err = Term::decode(&b"\x83\x58"[..]).unwrap_err();
+        assert!(matches!(err, BeamFileError::InvalidTerm(_)));
+
+        let mut nested = vec![0x83];
+        nested.extend(std::iter::repeat_n([0x68, 0x01], 100_000).flatten());
+        nested.push(0x6a);
+        let err = Term::decode(&nested[..]).unwrap_err();
+        assert!(matches!(
+            err,
+            BeamFileError::InvalidTerm("nesting too deep")
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn to_json() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let json = file.to_json().unwrap();
+
+        assert_eq!(json["name"], "test");
+        assert_eq!(json["chunks"].as_array().unwrap().len(), 10);
+        assert_eq!(json["chunks"][0]["id"], "AtU8");
+        assert_eq!(json["atoms"].as_array().unwrap().len(), 4);
+        assert_eq!(json["exports"][0]["function"], "module_info");
+        assert_eq!(json["attributes"][0][0], "vsn");
+        assert!(json["raw"]["StrT"].is_string());
+        assert!(json["raw"].get("ExpT").is_none());
+        assert_eq!(json["warnings"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn code_chunk() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let code: CodeChunk<String> = file.read().unwrap();
+
+        assert_eq!(code.header.label_count, 5);
+        assert_eq!(code.instructions.len(), 14);
+        assert_eq!(code.instructions[2].name(), "func_info");
+        assert_eq!(
+            code.instructions[2].args,
+            [
+                Operand::Atom("test".to_string()),
+                Operand::Atom("module_info".to_string()),
+                Operand::Unsigned(0)
+            ]
+        );
+
+        let functions = code.functions();
+        assert_eq!(functions.len(), 2);
+        assert_eq!(
+            (functions[1].name.as_str(), functions[1].arity),
+            ("module_info", 1)
+        );
+        assert_eq!(functions[1].label, Some(4));
+        assert_eq!(functions[1].instructions.len(), 7);
+        assert_eq!(functions[1].code(&code).len(), 19);
+    }
+
+    #[test]
+    fn code_operands() {
+        let mut data =
+            b"\x00\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\xa9\x00\x00\x00\x01\x00\x00\x00\x00"
+                .to_vec();
+        data.extend_from_slice(b"\x40\x19\xff\xff\x03");
+        data.extend_from_slice(b"\x40\x69\xe8\x13");
+        data.extend_from_slice(b"\x40\x47\x00\x04");
+        data.extend_from_slice(b"\xa4\x03\x17\x20\x51\x02");
+        data.extend_from_slice(b"\x40\xf9\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x03");
+        data.push(0x03);
+
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"Code", &data)]))).unwrap();
+        let code: CodeChunk<String> = file.read_with_atoms(&[]).unwrap();
+        let args: Vec<_> = code.instructions.iter().map(|i| i.args.clone()).collect();
+
+        assert_eq!(args[0], [Operand::Integer(-1), Operand::X(0)]);
+        assert_eq!(args[1], [Operand::Integer(1000), Operand::X(1)]);
+        assert_eq!(args[2], [Operand::Literal(0), Operand::Y(0)]);
+        assert_eq!(
+            args[3],
+            [
+                Operand::X(0),
+                Operand::List(vec![Operand::Integer(5), Operand::Nil])
+            ]
+        );
+        assert_eq!(
+            args[4][0],
+            Operand::BigInteger(vec![1, 0, 0, 0, 0, 0, 0, 0, 0])
+        );
+        assert_eq!(code.instructions[5].name(), "int_code_end");
+        assert_eq!(code.instructions[4].offset, 19);
+
+        data.insert(20, 0xff);
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"Code", &data)]))).unwrap();
+        let err = file.read_with_atoms::<CodeChunk<String>>(&[]).unwrap_err();
+        assert!(matches!(
+            err.root(),
+            BeamFileError::MalformedChunk {
+                reason: "unknown opcode",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn disassemble() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let disassembly = file
+            .disassemble(DisasmOptions::new().resolve_lines(true))
+            .unwrap();
+
+        assert_eq!(disassembly.module, "test");
+        let text = disassembly.functions[0].to_string();
+        assert_eq!(
+            text,
+            "{function, module_info, 0, 2}.\n  {label,1}.\n    {line,[]}.\n    \
+             {func_info,{atom,test},{atom,module_info},0}.\n  {label,2}.\n    \
+             {move,{atom,test},{x,0}}.\n    \
+             {call_ext_only,1,{extfunc,erlang,get_module_info,1}}."
+        );
+
+        let data = bif_module(&BIF_IMPORTS, BIF_CODE);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        let disassembly = file.disassemble(&DisasmOptions::new()).unwrap();
+        let text = disassembly.functions[0].to_string();
+        assert!(text.contains("{bif1,{f,0},{extfunc,erlang,hd,1},{x,0},{x,0}}."));
+        assert!(text.contains("{bif2,{f,0},{extfunc,erlang,element,2},{x,0},{x,1},{x,0}}."));
+        assert!(text.contains("{gc_bif2,{f,0},0,{extfunc,erlang,'+',2},{x,0},{x,1},{x,0}}."));
+    }
+
+    #[test]
+    fn literals() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        let mut table = b"\x00\x00\x00\x02".to_vec();
+        table.extend_from_slice(b"\x00\x00\x00\x09\x83\x6d\x00\x00\x00\x03abc");
+        table.extend_from_slice(b"\x00\x00\x00\x09\x83\x68\x02\x61\x01\x77\x02ok");
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&table).unwrap();
+        let mut data = (table.len() as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(&encoder.finish().unwrap());
+
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"LitT", &data)]))).unwrap();
+        let chunk = file.literals().unwrap();
+        assert_eq!(chunk.literals[0], Term::Binary(b"abc".to_vec()));
+        assert_eq!(chunk.literals[1].to_string(), "{1,ok}");
+        assert_eq!(chunk.sizes, [9, 9]);
+
+        let mut limits = Limits::new();
+        limits.max_decompressed_size(16);
+        let mut file = BeamFileOptions::new()
+            .limits(limits)
+            .from_reader(Cursor::new(beam(&[(b"LitT", &data)])))
+            .unwrap();
+        let err = file.literals().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Limits);
+    }
+
+    #[test]
+    fn literal_report() {
+        let mut table = b"\x00\x00\x00\x04".to_vec();
+        table.extend_from_slice(b"\x00\x00\x00\x02\x83\x6a");
+        table.extend_from_slice(b"\x00\x00\x00\x09\x83\x6d\x00\x00\x00\x03abc");
+        table.extend_from_slice(b"\x00\x00\x00\x03\x83\x61\x07");
+        table.extend_from_slice(b"\x00\x00\x00\x09\x83\x6d\x00\x00\x00\x03abc");
+        let mut data = vec![0; 4];
+        data.extend_from_slice(&table);
+
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"LitT", &data)]))).unwrap();
+        let report = file.literals().unwrap().report(2);
+        assert_eq!(report.count, 4);
+        assert_eq!(report.total_size, 23);
+        assert_eq!(
+            report.largest,
+            [
+                LiteralSize { index: 1, size: 9 },
+                LiteralSize { index: 3, size: 9 },
+            ]
+        );
+        assert_eq!(
+            report.duplicates,
+            [DuplicateLiteral {
+                indices: vec![1, 3],
+                size: 9,
+            }]
+        );
+        assert_eq!(
+            report.to_string(),
+            "4 literals, 23 bytes
+largest:
+  #1: 9 bytes
+  #3: 9 bytes
+duplicates:
+  #1, #3: 9 bytes each, 9 wasted
+"
+        );
+
+        let table = LitTChunk {
+            literals: (0..20_000).map(|i| Term::Integer(i % 10_000)).collect(),
+            sizes: vec![5; 20_000],
+        };
+        let report = table.report(0);
+        assert_eq!(report.duplicates.len(), 10_000);
+        assert_eq!(report.duplicates[0].indices, [0, 10_000]);
+        assert_eq!(report.duplicates[9_999].indices, [9_999, 19_999]);
+    }
+
+    #[test]
+    fn shared_literals() {
+        let module = |name: &[u8], literals: &[&[u8]]| {
+            let mut table = (literals.len() as u32).to_be_bytes().to_vec();
+            for literal in literals {
+                table.extend_from_slice(&(literal.len() as u32).to_be_bytes());
+                table.extend_from_slice(literal);
+            }
+            let mut data = vec![0; 4];
+            data.extend_from_slice(&table);
+            let mut atoms = b"\x00\x00\x00\x01".to_vec();
+            atoms.push(name.len() as u8);
+            atoms.extend_from_slice(name);
+            beam(&[(b"AtU8", &atoms), (b"LitT", &data)])
+        };
+        let large = b"\x83\x6d\x00\x00\x00\x08abcdefgh";
+        let small = b"\x83\x61\x07";
+
+        let mut set = BeamSet::new();
+        for data in [
+            module(b"a", &[large, small, large]),
+            module(b"b", &[small, large]),
+            module(b"c", &[small]),
+            module(b"d", &[]),
+        ] {
+            set.add(&mut BeamFile::from_slice(&data).unwrap()).unwrap();
+        }
+
+        let report = set.shared_literals(10);
+        assert_eq!(report.literals.len(), 1);
+        assert_eq!(report.literals[0].size, 14);
+        assert_eq!(report.literals[0].modules, ["a", "b"]);
+        assert_eq!(report.wasted(), 14);
+        assert_eq!(
+            report.to_string(),
+            "14 bytes in 2 modules (a, b): <<\"abcdefgh\">>\ntotal: 14 bytes wasted\n"
+        );
+
+        let report = set.shared_literals(0);
+        assert_eq!(report.literals.len(), 2);
+        assert_eq!(report.literals[1].modules, ["a", "b", "c"]);
+        assert_eq!(report.wasted(), 20);
+    }
+
+    #[test]
+    fn lines() {
+        let mut data = Vec::new();
+        for field in &[0u32, 0, 2, 3, 1] {
+            data.extend_from_slice(&field.to_be_bytes());
+        }
+        data.extend_from_slice(b"\x51\x12\x71\x02\x91\x00\x05a.hrl");
+
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"Line", &data)]))).unwrap();
+        let lines = file.lines().unwrap();
+        assert_eq!(lines.location(0), None);
+        assert_eq!(lines.location(1), Some(&Location { file: 0, line: 5 }));
+        assert_eq!(lines.location(2), Some(&Location { file: 1, line: 7 }));
+        assert_eq!(lines.location(3), Some(&Location { file: 0, line: 9 }));
+        assert_eq!(lines.file(1), Some("a.hrl"));
+        assert_eq!(lines.file(0), None);
+    }
+
+    #[test]
+    fn display_terms() {
+        let term = Term::Tuple(vec![
+            Term::Atom("ok".into()),
+            Term::Atom("Quoted atom".into()),
+            Term::Atom("end".into()),
+            Term::String(b"hi\n".to_vec()),
+            Term::List(vec![Term::Integer(1)], Box::new(Term::Integer(2))),
+            Term::Float(1.0),
+            Term::Float(1e100),
+            Term::Binary(vec![0, 1]),
+            Term::BitBinary {
+                data: vec![1, 0xa0],
+                bits: 3,
+            },
+            Term::Map(vec![(Term::Atom("a".into()), Term::Nil)]),
+            Term::BigInt {
+                negative: true,
+                digits: vec![0, 0, 0, 0, 0, 0, 0, 0, 1],
+            },
+        ]);
+        assert_eq!(
+            term.to_string(),
+            "{ok,'Quoted atom','end',\"hi\\n\",[1|2],1.0,1.0e100,<<0,1>>,<<1,5:3>>,\
+             #{a => []},-18446744073709551616}"
+        );
+    }
+
+    #[test]
+    fn strip() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let stripped = file.strip(&[Id(*b"Attr")]).unwrap();
+
+        let mut stripped_file = BeamFile::from_reader(Cursor::new(&stripped)).unwrap();
+        let mut ids: Vec<_> = stripped_file.iter_raw().map(|(id, _)| id).collect();
+        ids.sort();
+        let expected = [
+            b"AtU8", b"Attr", b"Code", b"ExpT", b"ImpT", b"Line", b"StrT",
+        ];
+        assert_eq!(ids, expected.iter().map(|id| Id(**id)).collect::<Vec<_>>());
+        for id in ids {
+            assert_eq!(
+                stripped_file.read_raw(id).unwrap(),
+                file.read_raw(id).unwrap()
+            );
+        }
+        assert!(stripped_file.warnings().is_empty());
+
+        let data = beam(&[
+            (b"Code", b"code"),
+            (b"Meta", b"\x83\x6a"),
+            (b"Type", b"type"),
+            (b"Dbgi", b"dbgi"),
+        ]);
+        let stripped = BeamFile::from_slice(&data).unwrap().strip(&[]).unwrap();
+        assert_eq!(
+            stripped,
+            beam(&[
+                (b"Code", b"code"),
+                (b"Meta", b"\x83\x6a"),
+                (b"Type", b"type")
+            ])
+        );
+
+        let mut output = Vec::new();
+        write_beam(&mut output, vec![(Id(*b"Code"), &b"abcde"[..])]).unwrap();
+        assert_eq!(output, beam(&[(b"Code", b"abcde")]));
+    }
+
+    #[test]
+    fn rewrite() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let original = file.iter_raw().map(|(id, _)| id).collect::<Vec<_>>();
+
+        let mut output = Vec::new();
+        let written = file
+            .rewrite()
+            .remove(Id::DBGI)
+            .replace(Id::ATTR, b"attr".to_vec())
+            .replace(Id(*b"Xtra"), b"x".to_vec())
+            .write_to(&mut output)
+            .unwrap();
+        assert_eq!(written, output.len() as u64);
+
+        let mut rewritten = BeamFile::from_slice(&output).unwrap();
+        let ids: Vec<_> = rewritten.iter_raw().map(|(id, _)| id).collect();
+        let mut expected: Vec<_> = original.into_iter().filter(|&id| id != Id::DBGI).collect();
+        expected.push(Id(*b"Xtra"));
+        assert_eq!(ids, expected);
+        assert_eq!(rewritten.read_raw(Id::ATTR).unwrap(), b"attr");
+        assert_eq!(rewritten.read_raw(Id(*b"Xtra")).unwrap(), b"x");
+        assert_eq!(
+            rewritten.read_raw(Id::CODE).unwrap(),
+            file.read_raw(Id::CODE).unwrap()
+        );
+        assert!(matches!(
+            rewritten.warnings(),
+            [Warning::UnknownChunk { chunk, .. }] if *chunk == Id(*b"Xtra")
+        ));
+
+        let mut streamed = Vec::new();
+        let written = file.strip_to(&[], &mut streamed).unwrap();
+        assert_eq!(written, streamed.len() as u64);
+        assert_eq!(streamed, file.strip(&[]).unwrap());
+    }
+
+    #[test]
+    fn xref() {
+        let caller = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x04\x01a\x01b\x01f\x01g"),
+            (
+                b"ImpT",
+                b"\x00\x00\x00\x02\x00\x00\x00\x02\x00\x00\x00\x03\x00\x00\x00\x00\
+                  \x00\x00\x00\x02\x00\x00\x00\x04\x00\x00\x00\x01",
+            ),
+        ]);
+        let callee = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x02\x01b\x01f"),
+            (
+                b"ExpT",
+                b"\x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02",
+            ),
+            (
+                b"Attr",
+                b"\x83\x6c\x00\x00\x00\x01\x68\x02\x77\x0adeprecated\
+                  \x6c\x00\x00\x00\x01\x68\x03\x77\x01f\x61\x00\x6b\x00\x07use h/0\x6a\x6a",
+            ),
+        ]);
+
+        let mut set = BeamSet::new();
+        let other = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x01b")]);
+        for data in [caller, callee, other] {
+            set.add(&mut BeamFile::from_reader(Cursor::new(data)).unwrap())
+                .unwrap();
+        }
+        assert_eq!(set.modules().collect::<Vec<_>>(), ["a", "b"]);
+        assert_eq!(
+            set.duplicate_modules()[0].to_string(),
+            "module b is defined more than once, in <unknown>, <unknown>"
+        );
+
+        let unnamed = beam(&[(b"AtU8", b"\x00\x00\x00\x00")]);
+        let mut file = BeamFile::from_reader(Cursor::new(unnamed)).unwrap();
+        assert!(matches!(
+            set.add(&mut file).unwrap_err().into_root(),
+            BeamFileError::MalformedChunk {
+                chunk: Id::AT_U8,
+                reason: "no module name"
+            }
+        ));
+
+        let err = set.add_dir("fixtures/missing").unwrap_err();
+        let path = err.context().and_then(|context| context.path.as_deref());
+        assert_eq!(path, Some(Path::new("fixtures/missing")));
+
+        let undefined = set.undefined_calls();
+        assert_eq!(
+            undefined,
+            [XrefFinding::UndefinedCall {
+                caller: "a".to_string(),
+                module: "b".to_string(),
+                function: "g".to_string(),
+                arity: 1,
+            }]
+        );
+        let deprecated = set.deprecated_calls();
+        assert_eq!(deprecated.len(), 1);
+        assert_eq!(
+            deprecated[0].to_string(),
+            "a calls deprecated function b:f/0: use h/0"
+        );
+    }
+
+    #[test]
+    fn unused_imports() {
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert!(file.unused_imports().unwrap().is_empty());
+
+        // erlang:module_info/0, which the code doesn't call
+        let mut imports = data[144..172].to_vec();
+        imports[3] = 3;
+        imports.extend_from_slice(b"\x00\x00\x00\x03\x00\x00\x00\x02\x00\x00\x00\x00");
+        let data = beam(&[
+            (b"AtU8", &data[20..64]),
+            (b"Code", &data[72..128]),
+            (b"ImpT", &imports),
+        ]);
+        let unused = BeamFile::from_slice(&data)
+            .unwrap()
+            .unused_imports()
+            .unwrap();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(
+            (
+                &unused[0].module[..],
+                &unused[0].function[..],
+                unused[0].arity
+            ),
+            ("erlang", "module_info", 0)
+        );
+
+        let unused = BeamFile::from_slice(&bif_module(&BIF_IMPORTS, BIF_CODE))
+            .unwrap()
+            .unused_imports()
+            .unwrap();
+        let unused: Vec<_> = unused.iter().map(|import| &import.function[..]).collect();
+        assert_eq!(unused, ["unused"]);
+    }
+
+    #[test]
+    fn function_fingerprints() {
+        let module = |code: &[u8]| {
+            let mut chunk = b"\x00\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\xb7\
+                              \x00\x00\x00\x03\x00\x00\x00\x01"
+                .to_vec();
+            chunk.extend_from_slice(code);
+            let data = beam(&[(b"AtU8", b"\x00\x00\x00\x02\x01a\x01f"), (b"Code", &chunk)]);
+            let mut file = BeamFile::from_slice(&data).unwrap();
+            file.function_fingerprints().unwrap()
+        };
+        // label 1, func_info a f 0, label 2, return
+        let original = module(b"\x01\x10\x02\x12\x22\x00\x01\x20\x13\x03");
+        assert_eq!(original.len(), 1);
+        assert_eq!((&original[0].name[..], original[0].arity), ("f", 0));
+
+        let renumbered = module(b"\x01\x50\x02\x12\x22\x00\x01\x60\x13\x03");
+        assert_eq!(renumbered, original);
+
+        // deallocate 0 before the return
+        let changed = module(b"\x01\x10\x02\x12\x22\x00\x01\x20\x12\x00\x13\x03");
+        assert_ne!(changed[0].fingerprint, original[0].fingerprint);
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let fingerprints = BeamFile::from_slice(&data)
+            .unwrap()
+            .function_fingerprints()
+            .unwrap();
+        assert_eq!(fingerprints.len(), 2);
+        assert_ne!(fingerprints[0].fingerprint, fingerprints[1].fingerprint);
+
+        let fingerprint = |imports: &[(u32, u32)], code: &[u8]| {
+            let data = bif_module(imports, code);
+            let mut file = BeamFile::from_slice(&data).unwrap();
+            file.function_fingerprints().unwrap()[0].fingerprint
+        };
+        let original = fingerprint(&BIF_IMPORTS, BIF_CODE);
+        // The same BIFs through a reversed import table
+        let reversed: Vec<_> = BIF_IMPORTS.iter().rev().copied().collect();
+        let code = BIF_CODE.to_vec();
+        let mut swapped = code.clone();
+        for (position, index) in [(10, 2), (15, 1), (22, 0)] {
+            swapped[position] = index << 4;
+        }
+        assert_eq!(fingerprint(&reversed, &swapped), original);
+        // element/2 instead of +/2 in gc_bif2
+        let mut changed = code.clone();
+        changed[22] = 0x20;
+        assert_ne!(fingerprint(&BIF_IMPORTS, &changed), original);
+        // One more live register
+        let mut changed = code;
+        changed[21] = 0x10;
+        assert_ne!(fingerprint(&BIF_IMPORTS, &changed), original);
+    }
+
+    #[test]
+    fn chunk_hashes() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let hashes = file.chunk_hashes().unwrap();
+        assert_eq!(hashes.chunks.len(), file.chunks().count());
+        assert_eq!(hashes, file.chunk_hashes().unwrap());
+        assert_ne!(hashes.get(Id::CODE), hashes.get(Id::ATTR));
+        assert_eq!(hashes.get(Id::DOCS), None);
+        assert_eq!(hashes.combined(&[]).to_string().len(), 32);
+
+        // Attr is replaced with a copy of itself at the end, and Dbgi changed
+        let attr = file.read_raw(Id::ATTR).unwrap();
+        let mut output = Vec::new();
+        file.rewrite()
+            .remove(Id::ATTR)
+            .replace(Id::ATTR, attr)
+            .replace(Id::DBGI, b"changed".to_vec())
+            .write_to(&mut output)
+            .unwrap();
+        let changed = BeamFile::from_slice(&output)
+            .unwrap()
+            .chunk_hashes()
+            .unwrap();
+        assert_ne!(changed.chunks, hashes.chunks);
+        assert_eq!(changed.changed(&hashes), [Id::DBGI]);
+        assert_ne!(changed.combined(&[]), hashes.combined(&[]));
+        assert_eq!(changed.combined(&[Id::DBGI]), hashes.combined(&[Id::DBGI]));
+
+        let stripped = file.strip(&[]).unwrap();
+        let stripped = BeamFile::from_slice(&stripped)
+            .unwrap()
+            .chunk_hashes()
+            .unwrap();
+        assert_eq!(
+            stripped.changed(&hashes),
+            [Id::ATTR, Id::C_INF, Id::DBGI, Id::LOC_T]
+        );
+    }
+
+    #[test]
+    fn function_metrics() {
+        let mut chunk = b"\x00\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\xb7\
+                          \x00\x00\x00\x04\x00\x00\x00\x01"
+            .to_vec();
+        // label 1, func_info a f 0, label 2, call_only 0 f/0, label 3, return
+        chunk.extend_from_slice(b"\x01\x10\x02\x12\x22\x00\x01\x20\x06\x00\x25\x01\x30\x13\x03");
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x02\x01a\x01f"), (b"Code", &chunk)]);
+        let metrics = BeamFile::from_slice(&data)
+            .unwrap()
+            .function_metrics()
+            .unwrap();
+        assert_eq!(
+            metrics,
+            [FunctionMetrics {
+                name: "f".to_string(),
+                arity: 0,
+                instructions: 3,
+                bytes: 14,
+                blocks: 2,
+                calls: 1,
+            }]
+        );
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let metrics = BeamFile::from_slice(&data)
+            .unwrap()
+            .function_metrics()
+            .unwrap();
+        let names: Vec<_> = metrics
+            .iter()
+            .map(|function| (&function.name[..], function.arity))
+            .collect();
+        assert_eq!(names, [("module_info", 0), ("module_info", 1)]);
+        assert!(metrics.iter().all(|function| function.calls == 1));
+    }
+
+    #[test]
+    fn source_status() {
+        use etf::*;
+        use std::time::{Duration, SystemTime};
+
+        let dir = std::env::temp_dir().join(format!("fast_beam_source_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("a.erl");
+        std::fs::write(&source, "-module(a).\n").unwrap();
+
+        let module = |source: &Path, hash: &str| {
+            let source = binary(source.to_str().unwrap());
+            let info = [&[131][..], &list(&[tuple(&[atom("source"), source])])].concat();
+            let hash = list(&[binary(hash)]);
+            let attributes = [&[131][..], &list(&[tuple(&[atom("source_hash"), hash])])].concat();
+            beam(&[
+                (b"AtU8", b"\x00\x00\x00\x01\x01a"),
+                (b"Attr", &attributes),
+                (b"CInf", &info),
+            ])
+        };
+        let status = |data: &[u8], options: &SourceCheck| {
+            BeamFile::from_slice(data)
+                .unwrap()
+                .source_status(options)
+                .unwrap()
+        };
+        let len = |data: &[u8]| data.len().to_string().into_bytes();
+
+        let data = module(&source, "12");
+        assert_eq!(status(&data, &SourceCheck::new()), SourceStatus::Unknown);
+        let later = SystemTime::now() + Duration::from_secs(60);
+        assert_eq!(
+            status(&data, SourceCheck::new().compiled_at(later)),
+            SourceStatus::UpToDate(source.clone())
+        );
+        let earlier = SystemTime::UNIX_EPOCH;
+        assert_eq!(
+            status(&data, SourceCheck::new().compiled_at(earlier)),
+            SourceStatus::Stale(source.clone())
+        );
+        assert_eq!(
+            status(
+                &data,
+                SourceCheck::new().compiled_at(earlier).source_hash(len)
+            ),
+            SourceStatus::UpToDate(source.clone())
+        );
+        assert_eq!(
+            status(&module(&source, "13"), SourceCheck::new().source_hash(len)),
+            SourceStatus::HashMismatch(source.clone())
+        );
+        let missing = dir.join("b.erl");
+        assert_eq!(
+            status(&module(&missing, "12"), &SourceCheck::new()),
+            SourceStatus::Missing(missing)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn function_spans() {
+        // label 1, line 1, func_info a f 0, label 2, line 2, line 3, return
+        let code = b"\x00\x00\x00\x10\x00\x00\x00\x00\x00\x00\x00\xb7\x00\x00\x00\x03\
+                     \x00\x00\x00\x01\x01\x10\x99\x10\x02\x12\x22\x00\x01\x20\x99\x20\x99\x30\
+                     \x13\x03";
+        // Lines 10 and 12 of the module, then line 5 of a.hrl
+        let lines = b"\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x03\
+                      \x00\x00\x00\x01\xa1\xc1\x12\x51\x00\x05a.hrl";
+        let data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x02\x01a\x01f"),
+            (b"Code", code),
+            (b"Line", lines),
+        ]);
+        let spans = BeamFile::from_slice(&data)
+            .unwrap()
+            .function_spans()
+            .unwrap();
+        assert_eq!(
+            spans,
+            [FunctionSpan {
+                name: "f".to_string(),
+                arity: 0,
+                file: None,
+                start: 10,
+                end: 12,
+            }]
+        );
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert!(file.function_spans().unwrap().is_empty());
+    }
+
+    #[test]
+    fn doc_coverage() {
+        use etf::*;
+
+        let entry = |name: &str, doc: Vec<u8>| {
+            tuple(&[
+                tuple(&[atom("function"), atom(name), int(0)]),
+                int(0),
+                list(&[]),
+                doc,
+                map(&[]),
+            ])
+        };
+        let docs = |module_doc: Vec<u8>| {
+            compressed(&tuple(&[
+                atom("docs_v1"),
+                int(0),
+                atom("erlang"),
+                binary("text/markdown"),
+                module_doc,
+                map(&[]),
+                list(&[
+                    entry("f", map(&[(binary("en"), binary("F"))])),
+                    entry("g", atom("hidden")),
+                ]),
+            ]))
+        };
+        // f/0, g/0, h/1 and module_info/0
+        let exports = b"\x00\x00\x00\x04\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02\
+                        \x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x04\
+                        \x00\x00\x00\x04\x00\x00\x00\x01\x00\x00\x00\x06\
+                        \x00\x00\x00\x05\x00\x00\x00\x00\x00\x00\x00\x08";
+        let module = |name: &str, docs: &[u8]| {
+            let mut atoms = b"\x00\x00\x00\x05".to_vec();
+            for atom in [name, "f", "g", "h", "module_info"] {
+                atoms.push(atom.len() as u8);
+                atoms.extend_from_slice(atom.as_bytes());
+            }
+            match docs.is_empty() {
+                true => beam(&[(b"AtU8", &atoms), (b"ExpT", exports)]),
+                false => beam(&[(b"AtU8", &atoms), (b"ExpT", exports), (b"Docs", docs)]),
+            }
+        };
+
+        let data = module("a", &docs(map(&[])));
+        let coverage = BeamFile::from_slice(&data).unwrap().doc_coverage().unwrap();
+        assert_eq!(coverage.documented, 1);
+        assert_eq!(coverage.undocumented, [("h".to_string(), 1)]);
+        assert_eq!(coverage.hidden, [("g".to_string(), 0)]);
+        assert_eq!(
+            coverage.to_string(),
+            "a: 1/3 documented (33.3%)\n  undocumented: h/1\n  hidden: g/0\n"
+        );
+
+        let mut set = BeamSet::new();
+        for data in [
+            module("a", &docs(map(&[]))),
+            module("b", &docs(atom("hidden"))),
+            module("c", &[]),
+        ] {
+            set.add(&mut BeamFile::from_slice(&data).unwrap()).unwrap();
+        }
+        let report = set.doc_coverage();
+        assert_eq!(report.modules[1].hidden.len(), 3);
+        assert_eq!(report.modules[2].undocumented.len(), 3);
+        assert_eq!((report.documented(), report.total()), (1, 9));
+        assert!(report
+            .to_string()
+            .ends_with("\ntotal: 1/9 documented (11.1%)\n"));
+    }
+
+    #[test]
+    fn docs() {
+        use etf::*;
+
+        let en = |text: &str| map(&[(binary("en"), binary(text))]);
+        let html = list(&[
+            tuple(&[
+                atom("p"),
+                list(&[]),
+                list(&[
+                    binary("Adds "),
+                    tuple(&[atom("code"), list(&[]), list(&[binary("x")])]),
+                ]),
+            ]),
+            tuple(&[
+                atom("ul"),
+                list(&[]),
+                list(&[tuple(&[atom("li"), list(&[]), list(&[binary("one")])])]),
+            ]),
+        ]);
+        let docs = tuple(&[
+            atom("docs_v1"),
+            int(0),
+            atom("erlang"),
+            binary("application/erlang+html"),
+            en("Module docs."),
+            map(&[]),
+            list(&[
+                tuple(&[
+                    tuple(&[atom("function"), atom("add"), int(1)]),
+                    int(0),
+                    list(&[binary("add(X)")]),
+                    map(&[(binary("en"), html)]),
+                    map(&[]),
+                ]),
+                tuple(&[
+                    tuple(&[atom("type"), atom("t"), int(0)]),
+                    int(0),
+                    list(&[]),
+                    atom("hidden"),
+                    map(&[]),
+                ]),
+            ]),
+        ]);
+        let data = compressed(&docs);
+
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"Docs", &data)]))).unwrap();
+        let chunk = file.docs().unwrap();
+        assert_eq!(chunk.language, "erlang");
+        assert_eq!(chunk.module_doc.text("en").as_deref(), Some("Module docs."));
+        assert_eq!(chunk.entries[1].doc, Doc::Hidden);
+
+        let add: Vec<_> = chunk.function("add", None).collect();
+        assert_eq!(add.len(), 1);
+        assert_eq!(add[0].signature, ["add(X)"]);
+        assert_eq!(add[0].doc.text("en").as_deref(), Some("Adds `x`\n\n- one"));
+        assert_eq!(chunk.function("add", Some(2)).count(), 0);
+
+        let mut limits = Limits::new();
+        limits.max_decompressed_size(16);
+        let mut file = BeamFileOptions::new()
+            .limits(limits)
+            .from_reader(Cursor::new(beam(&[(b"Docs", &data)])))
+            .unwrap();
+        assert_eq!(file.docs().unwrap_err().kind(), ErrorKind::Limits);
+    }
+
+    #[test]
+    fn diff() {
+        use etf::*;
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let mut same = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(file
+            .diff(&mut same, &DiffOptions::new())
+            .unwrap()
+            .is_empty());
+
+        let stripped = file.strip(&[Id(*b"Attr")]).unwrap();
+        let mut stripped = BeamFile::from_reader(Cursor::new(stripped)).unwrap();
+        let diff = file
+            .diff(
+                &mut stripped,
+                DiffOptions::new().ignore_nondeterministic(true),
+            )
+            .unwrap();
+        let mut removed: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|change| match change {
+                Change::ChunkRemoved(id) => *id,
+                change => panic!("unexpected change {}", change),
+            })
+            .collect();
+        removed.sort();
+        assert_eq!(removed, [Id(*b"Dbgi"), Id(*b"LocT")]);
+
+        let module = |exports: &[u8], attributes: &[Vec<u8>]| {
+            let mut attr = vec![131];
+            attr.extend(list(attributes));
+            let data = beam(&[
+                (b"AtU8", b"\x00\x00\x00\x03\x01m\x01f\x01g"),
+                (b"ExpT", exports),
+                (b"Attr", &attr),
+            ]);
+            BeamFile::from_reader(Cursor::new(data)).unwrap()
+        };
+        let mut old = module(
+            b"\x00\x00\x00\x01\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02",
+            &[tuple(&[atom("a"), int(1)])],
+        );
+        let mut new = module(
+            b"\x00\x00\x00\x01\x00\x00\x00\x03\x00\x00\x00\x01\x00\x00\x00\x02",
+            &[tuple(&[atom("a"), int(2)]), tuple(&[atom("b"), int(1)])],
+        );
+        let diff = old.diff(&mut new, &DiffOptions::new()).unwrap();
+        let changes: Vec<_> = diff.changes.iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "~ chunk Attr, 14 -> 21 bytes",
+                "~ chunk ExpT, 16 -> 16 bytes",
+                "- export f/0",
+                "+ export g/1",
+                "~ attribute a: 1 -> 2",
+                "+ attribute b: 1",
+            ]
+        );
+
+        let behaviour = |name: &str| tuple(&[atom("behaviour"), list(&[atom(name)])]);
+        let exports = b"\x00\x00\x00\x00";
+        let mut old = module(exports, &[behaviour("a"), behaviour("b")]);
+        let mut new = module(exports, &[behaviour("a"), behaviour("c")]);
+        let diff = old.diff(&mut new, &DiffOptions::new()).unwrap();
+        let changes: Vec<_> = diff.changes.iter().map(Change::to_string).collect();
+        assert_eq!(
+            changes,
+            [
+                "~ chunk Attr, 51 -> 51 bytes",
+                "~ attribute behaviour: [b] -> [c]",
+            ]
+        );
+        let mut new = module(exports, &[behaviour("a"), behaviour("b"), behaviour("c")]);
+        let diff = old.diff(&mut new, &DiffOptions::new()).unwrap();
+        let changes: Vec<_> = diff.changes.iter().map(Change::to_string).collect();
+        assert_eq!(changes[1..], ["+ attribute behaviour: [c]"]);
+    }
+
+    /// Builds a zip archive, with the files alternately stored and deflated
+    fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        use std::io::Write;
+
+        let u16 = |value: usize| (value as u16).to_le_bytes();
+        let u32 = |value: usize| (value as u32).to_le_bytes();
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for (i, (name, data)) in files.iter().enumerate() {
+            let (method, compressed) = match i % 2 {
+                0 => (0, data.to_vec()),
+                _ => {
+                    let mut encoder =
+                        flate2::write::DeflateEncoder::new(Vec::new(), Default::default());
+                    encoder.write_all(data).unwrap();
+                    (8, encoder.finish().unwrap())
+                }
+            };
+            let fields = [
+                &u16(20)[..],
+                &u16(0),
+                &u16(method),
+                &u32(0),
+                &u32(0),
+                &u32(compressed.len()),
+                &u32(data.len()),
+                &u16(name.len()),
+                &u16(0),
+            ]
+            .concat();
+            directory.extend(
+                [
+                    &u32(0x0201_4b50)[..],
+                    &u16(20),
+                    &fields,
+                    &u16(0),
+                    &u16(0),
+                    &u16(0),
+                    &u32(0),
+                    &u32(archive.len()),
+                    name.as_bytes(),
+                ]
+                .concat(),
+            );
+            archive.extend([&u32(0x0403_4b50)[..], &fields, name.as_bytes(), &compressed].concat());
+        }
+        let end = [
+            &u32(0x0605_4b50)[..],
+            &u16(0),
+            &u16(0),
+            &u16(files.len()),
+            &u16(files.len()),
+            &u32(directory.len()),
+            &u32(archive.len()),
+            &u16(0),
+        ]
+        .concat();
+        archive.extend(directory);
+        archive.extend(end);
+        archive
+    }
+
+    #[test]
+    fn code_path() {
+        let beam = std::fs::read("fixtures/test.beam").unwrap();
+        let archive = std::env::temp_dir().join(format!("fast_beam_{}.ez", std::process::id()));
+        std::fs::write(
+            &archive,
+            zip(&[
+                ("app-1.0/ebin/other.beam", b"other"),
+                ("app-1.0/ebin/test.beam", &beam),
+            ]),
+        )
+        .unwrap();
+
+        let path = CodePath::new(["missing", "fixtures"]);
+        let found = path.resolve("test").unwrap().unwrap();
+        assert_eq!(
+            found,
+            ModulePath::File(Path::new("fixtures").join("test.beam"))
+        );
+        assert_eq!(path.resolve("other").unwrap(), None);
+
+        let path = CodePath::new([archive.join("app-1.0/ebin"), PathBuf::from("fixtures")]);
+        let found = path.resolve("test").unwrap().unwrap();
+        assert_eq!(
+            found,
+            ModulePath::Archive {
+                archive: archive.clone(),
+                entry: "app-1.0/ebin/test.beam".to_string(),
+            }
+        );
+        assert_eq!(found.read().unwrap(), beam);
+        let other = path.resolve("other").unwrap().unwrap();
+        assert_eq!(other.read().unwrap(), b"other");
+        assert_eq!(CodePath::new([&archive]).resolve("test").unwrap(), None);
+
+        std::fs::write(&archive, b"not an archive").unwrap();
+        let err = path.resolve("test").unwrap_err();
+        std::fs::remove_file(&archive).unwrap();
+        assert_eq!(err.kind(), ErrorKind::Io);
+    }
+
+    #[test]
+    fn abstract_code() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let code = file.abstract_code().unwrap().unwrap();
+        assert_eq!(code.forms.len(), 3);
+        let module: Vec<_> = code.attributes("module").collect();
+        assert_eq!(module, [&Term::Atom("test".to_string())]);
+
+        use etf::*;
+        let dbgi = |backend: &str, forms: Vec<u8>| {
+            let mut data = vec![131];
+            data.extend(tuple(&[
+                atom("debug_info_v1"),
+                atom(backend),
+                tuple(&[forms, list(&[])]),
+            ]));
+            data
+        };
+        let data = dbgi("erl_abstract_code", atom("none"));
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x00"), (b"Dbgi", &data)]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert_eq!(file.abstract_code().unwrap(), None);
+
+        let data = dbgi("elixir_erl", list(&[]));
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x00"), (b"Dbgi", &data)]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert_eq!(file.abstract_code().unwrap(), None);
+
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x00"), (b"Dbgi", b"\x83\x61\x01")]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert_eq!(
+            file.abstract_code().unwrap_err().kind(),
+            ErrorKind::ChunkFormat
+        );
+
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x00")]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert_eq!(file.abstract_code().unwrap(), None);
+
+        let mut stripped = Vec::new();
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.strip_to(&[], &mut stripped).unwrap();
+        let mut file = BeamFile::from_slice(&stripped).unwrap();
+        assert_eq!(file.abstract_code().unwrap(), None);
+        assert_eq!(file.typespecs().unwrap(), None);
+        assert_eq!(file.callbacks().unwrap(), None);
+        assert_eq!(file.records().unwrap(), None);
+    }
+
+    #[test]
+    fn pretty_print() {
+        use etf::*;
+
+        let node = |tag: &str, args: &[Vec<u8>]| {
+            let mut elements = vec![atom(tag), int(1)];
+            elements.extend_from_slice(args);
+            tuple(&elements)
+        };
+        let a = |name: &str| node("atom", &[atom(name)]);
+        let v = |name: &str| node("var", &[atom(name)]);
+        let i = |value: u8| node("integer", &[int(value)]);
+        let op = |op: &str, left: Vec<u8>, right: Vec<u8>| node("op", &[atom(op), left, right]);
+        let clause = |patterns: &[Vec<u8>], guards: &[Vec<u8>], body: &[Vec<u8>]| {
+            node("clause", &[list(patterns), list(guards), list(body)])
+        };
+        let attribute = |name: &str, value: Vec<u8>| node("attribute", &[atom(name), value]);
+
+        let field = |name: &str| node("record_field", &[a(name)]);
+        let default = node("record_field", &[a("a"), i(1)]);
+        let integer = node("type", &[atom("integer"), list(&[])]);
+        let record = list(&[
+            tuple(&[atom("typed_record_field"), default, integer]),
+            field("b"),
+        ]);
+        let segment =
+            |value: Vec<u8>, size: Vec<u8>| node("bin_element", &[value, size, atom("default")]);
+        let binary = node(
+            "bin",
+            &[list(&[
+                segment(v("X"), i(8)),
+                segment(node("string", &[vec![0x6b, 0, 1, b's']]), atom("default")),
+            ])],
+        );
+        let case = node(
+            "case",
+            &[
+                v("Y"),
+                list(&[
+                    clause(
+                        &[node("tuple", &[list(&[a("ok"), v("Z")])])],
+                        &[],
+                        &[node("cons", &[v("Z"), v("Y")])],
+                    ),
+                    clause(
+                        &[v("_")],
+                        &[],
+                        &[node(
+                            "map",
+                            &[list(&[node("map_field_assoc", &[a("a"), binary])])],
+                        )],
+                    ),
+                ]),
+            ],
+        );
+        let product = op("*", v("X"), op("+", v("X"), i(1)));
+        let function = node(
+            "function",
+            &[
+                atom("f"),
+                int(1),
+                list(&[
+                    clause(
+                        &[v("X")],
+                        &[list(&[op(">", v("X"), i(0))])],
+                        &[node("match", &[v("Y"), product]), case],
+                    ),
+                    clause(
+                        &[v("_")],
+                        &[],
+                        &[node(
+                            "fun",
+                            &[tuple(&[atom("function"), atom("g"), int(0)])],
+                        )],
+                    ),
+                ]),
+            ],
+        );
+        let forms = list(&[
+            attribute("module", atom("m")),
+            attribute("export", list(&[tuple(&[atom("f"), int(1)])])),
+            attribute("record", tuple(&[atom("r"), record])),
+            function,
+            node("eof", &[]),
+        ]);
+        let mut dbgi = vec![131];
+        dbgi.extend(tuple(&[
+            atom("debug_info_v1"),
+            atom("erl_abstract_code"),
+            tuple(&[forms, list(&[])]),
+        ]));
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x01m"), (b"Dbgi", &dbgi)]);
+        let code = BeamFile::from_slice(&data)
+            .unwrap()
+            .abstract_code()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            code.to_string(),
+            "-module(m).
+-export([f/1]).
+-record(r, {a = 1 :: integer(), b}).
+
+f(X) when X > 0 ->
+    Y = X * (X + 1),
+    case Y of
+        {ok, Z} ->
+            [Z | Y];
+        _ ->
+            #{a => <<X:8, \"s\">>}
+    end;
+f(_) ->
+    fun g/0.
+"
+        );
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let code = BeamFile::from_slice(&data)
+            .unwrap()
+            .abstract_code()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            code.to_string(),
+            "-file(\"test.erl\", 1).\n-module(test).\n"
+        );
+    }
+
+    #[test]
+    fn typespecs() {
+        use etf::*;
+
+        let ty =
+            |name: &str, args: &[Vec<u8>]| tuple(&[atom("type"), int(1), atom(name), list(args)]);
+        let var = |name: &str| tuple(&[atom("var"), int(1), atom(name)]);
+        let attribute =
+            |kind: &str, value: Vec<u8>| tuple(&[atom("attribute"), int(1), atom(kind), value]);
+        let fun = |args: &[Vec<u8>], result: Vec<u8>| ty("fun", &[ty("product", args), result]);
+        let forms = list(&[
+            attribute(
+                "type",
+                tuple(&[
+                    atom("pair"),
+                    ty("tuple", &[var("A"), var("A")]),
+                    list(&[var("A")]),
+                ]),
+            ),
+            attribute(
+                "opaque",
+                tuple(&[atom("id"), ty("integer", &[]), list(&[])]),
+            ),
+            attribute(
+                "spec",
+                tuple(&[
+                    tuple(&[atom("f"), int(1)]),
+                    list(&[fun(&[ty("integer", &[])], ty("atom", &[]))]),
+                ]),
+            ),
+            attribute("type", atom("malformed")),
+        ]);
+        let mut dbgi = vec![131];
+        dbgi.extend(tuple(&[
+            atom("debug_info_v1"),
+            atom("erl_abstract_code"),
+            tuple(&[forms, list(&[])]),
+        ]));
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x01m"), (b"Dbgi", &dbgi)]);
+        let specs = BeamFile::from_slice(&data)
+            .unwrap()
+            .typespecs()
+            .unwrap()
+            .unwrap();
+
+        let key = |name: &str, arity| (name.to_string(), arity);
+        assert_eq!(specs.types.len(), 2);
+        let pair = &specs.types[&key("pair", 1)];
+        assert_eq!(pair.params, ["A"]);
+        assert_eq!(pair.to_string(), "-type pair(A) :: {A, A}.");
+        let id = &specs.types[&key("id", 0)];
+        assert!(id.opaque);
+        assert_eq!(id.to_string(), "-opaque id() :: integer().");
+        assert_eq!(
+            specs.specs[&key("f", 1)].to_string(),
+            "-spec f(integer()) -> atom()."
+        );
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let specs = BeamFile::from_slice(&data).unwrap().typespecs().unwrap();
+        assert_eq!(specs, Some(Typespecs::default()));
+    }
+
+    #[test]
+    fn records() {
+        use etf::*;
+
+        let node = |tag: &str, args: &[Vec<u8>]| {
+            let mut elements = vec![atom(tag), int(1)];
+            elements.extend_from_slice(args);
+            tuple(&elements)
+        };
+        let a = |name: &str| node("atom", &[atom(name)]);
+        let default = node("record_field", &[a("items"), node("nil", &[])]);
+        let ty = node("type", &[atom("list"), list(&[])]);
+        let fields = list(&[
+            node("record_field", &[a("id")]),
+            tuple(&[atom("typed_record_field"), default, ty]),
+        ]);
+        let forms = list(&[
+            node("attribute", &[atom("record"), tuple(&[atom("r"), fields])]),
+            node("attribute", &[atom("record"), atom("malformed")]),
+        ]);
+        let mut dbgi = vec![131];
+        dbgi.extend(tuple(&[
+            atom("debug_info_v1"),
+            atom("erl_abstract_code"),
+            tuple(&[forms, list(&[])]),
+        ]));
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x01m"), (b"Dbgi", &dbgi)]);
+        let records = BeamFile::from_slice(&data)
+            .unwrap()
+            .records()
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(records.len(), 1);
+        let record = &records["r"];
+        let names: Vec<_> = record.fields.iter().map(|field| &field.name[..]).collect();
+        assert_eq!(names, ["id", "items"]);
+        assert_eq!(record.fields[0].default_source(), None);
+        assert_eq!(record.fields[1].default_source().as_deref(), Some("[]"));
+        assert!(record.fields[1].ty.is_some());
+        assert_eq!(
+            record.to_string(),
+            "-record(r, {id, items = [] :: list()})."
+        );
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let records = BeamFile::from_slice(&data).unwrap().records().unwrap();
+        assert_eq!(records, Some(Default::default()));
+    }
+
+    #[test]
+    fn behaviour_stub() {
+        use etf::*;
+
+        let ty =
+            |name: &str, args: &[Vec<u8>]| tuple(&[atom("type"), int(1), atom(name), list(args)]);
+        let spec = |kind: &str, name: &str, arity: u8, clauses: &[Vec<u8>]| {
+            tuple(&[
+                atom("attribute"),
+                int(1),
+                atom(kind),
+                tuple(&[tuple(&[atom(name), int(arity)]), list(clauses)]),
+            ])
+        };
+        let fun = |args: &[Vec<u8>], result: Vec<u8>| ty("fun", &[ty("product", args), result]);
+        let user_type = tuple(&[atom("user_type"), int(1), atom("t"), list(&[])]);
+        let ok = tuple(&[atom("atom"), int(1), atom("ok")]);
+        let forms = list(&[
+            spec("spec", "f", 1, &[fun(&[ty("integer", &[])], user_type)]),
+            spec(
+                "callback",
+                "init",
+                1,
+                &[fun(
+                    &[ty("list", &[ty("atom", &[])])],
+                    ty(
+                        "union",
+                        &[ok, tuple(&[atom("type"), int(1), atom("map"), atom("any")])],
+                    ),
+                )],
+            ),
+        ]);
+        let mut dbgi = vec![131];
+        dbgi.extend(tuple(&[
+            atom("debug_info_v1"),
+            atom("erl_abstract_code"),
+            tuple(&[forms, list(&[])]),
+        ]));
+        let exports = [
+            &[0, 0, 0, 3][..],
+            &[0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 2],
+            &[0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 4],
+            &[0, 0, 0, 4, 0, 0, 0, 2, 0, 0, 0, 6],
+        ]
+        .concat();
+        let data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x04\x01m\x01f\x0bmodule_info\x01g"),
+            (b"ExpT", &exports),
+            (b"Dbgi", &dbgi),
+        ]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        let stub = file.behaviour_stub().unwrap();
+        assert_eq!(stub.module, "m_behaviour");
+        assert_eq!(
+            stub.callbacks,
+            [
+                "f(integer()) -> m:t()",
+                "g(term(), term()) -> term()",
+                "init([atom()]) -> ok | map()",
+            ]
+        );
+        assert!(stub
+            .to_string()
+            .starts_with("-module(m_behaviour).\n\n-callback f(integer()) -> m:t().\n"));
+
+        let mut exports = exports;
+        exports[8..12].copy_from_slice(&[0xff; 4]);
+        let data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x04\x01m\x01f\x0bmodule_info\x01g"),
+            (b"ExpT", &exports),
+        ]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert!(matches!(
+            file.behaviour_stub(),
+            Err(BeamFileError::MalformedChunk {
+                chunk: Id::EXP_T,
+                reason: "invalid arity"
+            })
+        ));
+
+        for arity in [-1, 256, i64::MAX] {
+            let spec = Term::Tuple(vec![
+                Term::Tuple(vec![Term::Atom("f".into()), Term::Integer(arity)]),
+                Term::Nil,
+            ]);
+            assert_eq!(abstract_code::spec_clauses(&spec), None);
+        }
+    }
+
+    #[test]
+    fn callbacks() {
+        use etf::*;
+
+        let ty =
+            |name: &str, args: &[Vec<u8>]| tuple(&[atom("type"), int(1), atom(name), list(args)]);
+        let fun = |args: &[Vec<u8>], result: Vec<u8>| ty("fun", &[ty("product", args), result]);
+        let attribute =
+            |kind: &str, value: Vec<u8>| tuple(&[atom("attribute"), int(1), atom(kind), value]);
+        let callback = |name: &str, arity: u8, clause: Vec<u8>| {
+            attribute(
+                "callback",
+                tuple(&[tuple(&[atom(name), int(arity)]), list(&[clause])]),
+            )
+        };
+        let forms = list(&[
+            callback("init", 1, fun(&[ty("term", &[])], ty("atom", &[]))),
+            callback("stop", 0, fun(&[], ty("ok", &[]))),
+            attribute(
+                "optional_callbacks",
+                list(&[
+                    tuple(&[atom("stop"), int(0)]),
+                    tuple(&[atom("missing"), int(2)]),
+                ]),
+            ),
+        ]);
+        let mut dbgi = vec![131];
+        dbgi.extend(tuple(&[
+            atom("debug_info_v1"),
+            atom("erl_abstract_code"),
+            tuple(&[forms, list(&[])]),
+        ]));
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x01m"), (b"Dbgi", &dbgi)]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        let callbacks = file.callbacks().unwrap().unwrap();
+
+        let key = |name: &str, arity| (name.to_string(), arity);
+        assert_eq!(callbacks.len(), 2);
+        let init = &callbacks[&key("init", 1)];
+        assert!(!init.optional);
+        assert_eq!(init.to_string(), "-callback init(term()) -> atom().");
+        assert!(callbacks[&key("stop", 0)].optional);
+
+        let stub = file.behaviour_stub().unwrap();
+        assert_eq!(stub.optional_callbacks, [key("stop", 0)]);
+        assert!(stub
+            .to_string()
+            .starts_with("-module(m_behaviour).\n\n-optional_callbacks([stop/0]).\n"));
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let callbacks = BeamFile::from_slice(&data).unwrap().callbacks().unwrap();
+        assert_eq!(callbacks, Some(Default::default()));
+    }
+
+    #[test]
+    fn hex_dump() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let dump = file.hex_dump(Id::IMP_T).unwrap().to_string();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines[0], "ImpT at offset 144, 28 bytes");
+        assert!(lines[1].starts_with("00000090  00 00 00 02   "));
+        assert!(lines[1].ends_with("   import count = 2"));
+        assert!(lines[3].starts_with("000000a0  00 00 00 03 00 00 00 04 00 00 00 02   "));
+        assert!(lines[3].ends_with("   import 1: module = atom 3, function = atom 4, arity = 2"));
+        assert_eq!(lines.len(), 4);
+
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x02\x04test\x20")]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        let dump = file.hex_dump(Id::AT_U8).unwrap();
+        let labels: Vec<_> = dump
+            .annotations
+            .iter()
+            .map(|a| (a.offset, a.len, &a.label[..]))
+            .collect();
+        assert_eq!(labels, [(0, 4, "atom count = 2"), (4, 5, "atom 1 = test")]);
+        assert!(dump.to_string().ends_with("\n0000001d  20\n"));
+
+        let dump = file.hex_dump(Id::new(*b"Meta")).unwrap_err();
+        assert!(matches!(dump, BeamFileError::MissingChunk(_)));
+    }
+
+    #[test]
+    fn semantically_eq() {
+        use etf::*;
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let mut same = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(file.semantically_eq(&mut same, &EqOptions::new()).unwrap());
+
+        let module = |atoms: &[u8; 4], exports: &[u8], attributes: &[Vec<u8>]| {
+            let mut attr = vec![131];
+            attr.extend(list(attributes));
+            let data = beam(&[
+                (atoms, b"\x00\x00\x00\x03\x01m\x01f\x01g"),
+                (b"ExpT", exports),
+                (b"Attr", &attr),
+            ]);
+            BeamFile::from_reader(Cursor::new(data)).unwrap()
+        };
+        let f0 = b"\x00\x00\x00\x02\x00\x00\x00\x00\x00\x00\x00\x02";
+        let g1 = b"\x00\x00\x00\x03\x00\x00\x00\x01\x00\x00\x00\x04";
+        let attribute = [tuple(&[atom("a"), int(1)])];
+        let mut old = module(
+            b"AtU8",
+            &[&[0, 0, 0, 2], &f0[..], &g1[..]].concat(),
+            &attribute,
+        );
+
+        let mut reordered = module(
+            b"Atom",
+            &[&[0, 0, 0, 2], &g1[..], &f0[..]].concat(),
+            &attribute,
+        );
+        assert!(old
+            .semantically_eq(&mut reordered, &EqOptions::new())
+            .unwrap());
+
+        let mut changed = module(b"AtU8", &[&[0, 0, 0, 1], &f0[..]].concat(), &[]);
+        assert!(!old
+            .semantically_eq(&mut changed, &EqOptions::new())
+            .unwrap());
+        assert!(!old
+            .semantically_eq(&mut changed, EqOptions::new().ignore_chunk(Id::ATTR))
+            .unwrap());
+        assert!(old
+            .semantically_eq(
+                &mut changed,
+                EqOptions::new()
+                    .ignore_chunk(Id::ATTR)
+                    .ignore_chunk(Id::EXP_T)
+            )
+            .unwrap());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_reader() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+
+        runtime.block_on(async {
+            let mut file = AsyncBeamFile::from_reader(Cursor::new(data.clone()))
+                .await
+                .unwrap();
+            let mut sync_file = BeamFile::from_reader(Cursor::new(data)).unwrap();
+            assert_eq!(
+                file.read_raw(Id(*b"Code")).await.unwrap(),
+                sync_file.read_raw(Id(*b"Code")).unwrap()
+            );
+
+            file.index_atoms(NaiveInterner).await.unwrap();
+            assert_eq!(file.file().name().map(String::as_str), Some("test"));
+            let exports: ExpTChunk<String> = file.read().await.unwrap();
+            assert_eq!(exports.exports.len(), 2);
+
+            assert!(file.file_mut().attributes().is_err());
+            file.fetch(&[Id(*b"Attr"), Id(*b"LitT")]).await.unwrap();
+            assert_eq!(file.file_mut().attributes().unwrap().attributes[0].0, "vsn");
+
+            let truncated = beam(&[(b"Code", b"abcd")])[..14].to_vec();
+            let err = AsyncBeamFile::from_reader(Cursor::new(truncated))
+                .await
+                .err()
+                .unwrap();
+            assert!(matches!(err, BeamFileError::InvalidPayloadSize { .. }));
+
+            let err = AsyncBeamFile::from_reader(Cursor::new(leftover_payload(b"sig")))
+                .await
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err,
+                BeamFileError::ChunkOutOfBounds { position: 20, .. }
+            ));
+        });
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn futures_reader() {
+        use std::{
+            pin::Pin,
+            task::{Context, Poll},
+        };
+
+        /// A futures-io reader that's always ready
+        struct Ready(Cursor<Vec<u8>>);
+
+        impl futures_io::AsyncRead for Ready {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<io::Result<usize>> {
+                Poll::Ready(self.0.read(buf))
+            }
+        }
+
+        impl futures_io::AsyncSeek for Ready {
+            fn poll_seek(
+                mut self: Pin<&mut Self>,
+                _: &mut Context<'_>,
+                pos: SeekFrom,
+            ) -> Poll<io::Result<u64>> {
+                Poll::Ready(self.0.seek(pos))
+            }
+        }
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+
+        runtime.block_on(async {
+            let reader = FuturesIo(Ready(Cursor::new(data)));
+            let mut file = BeamFileOptions::new()
+                .from_async_reader(reader)
+                .await
+                .unwrap();
+            file.index_atoms(NaiveInterner).await.unwrap();
+            let imports: ImpTChunk<String> = file.read().await.unwrap();
+            assert_eq!(imports.imports[0].function, "get_module_info");
+            assert_eq!(file.read_raw(Id(*b"StrT")).await.unwrap(), b"");
+        });
+    }
+
+    #[test]
+    fn chunks() {
+        let file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let chunks: Vec<_> = file.chunks().collect();
+        let ids: Vec<_> = chunks.iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(
+            ids,
+            [
+                b"AtU8", b"Code", b"StrT", b"ImpT", b"ExpT", b"LocT", b"Attr", b"CInf", b"Dbgi",
+                b"Line"
+            ]
+            .map(|id| Id(*id))
+        );
+        let mut file = file;
+        let raw: Vec<_> = file
+            .iter_raw()
+            .map(|(id, data)| (id, data.unwrap()))
+            .collect();
+        assert!(raw.iter().map(|(id, _)| *id).eq(ids.iter().copied()));
+        assert_eq!(raw[1].1.len(), 56);
+
+        let owned = std::thread::spawn(move || file.into_chunks().collect::<Vec<_>>())
+            .join()
+            .unwrap();
+        assert_eq!(owned.len(), 10);
+        assert_eq!(owned[4].0, Id(*b"ExpT"));
+        assert_eq!(*owned[4].1.as_ref().unwrap(), raw[4].1);
+        assert_eq!(chunks[0], (Id(*b"AtU8"), 20, 44));
+        assert_eq!(chunks[1], (Id(*b"Code"), 72, 56));
+        let (_, offset, len) = chunks[9];
+        assert_eq!(offset + len, 612);
+    }
+
+    #[test]
+    fn id() {
+        assert_eq!(Id::AT_U8, Id(*b"AtU8"));
+        assert_eq!("ExpT".parse::<Id>(), Ok(Id::EXP_T));
+        assert_eq!("Exp".parse::<Id>(), Err(ParseIdError));
+        assert_eq!("Exp\u{e9}".parse::<Id>(), Err(ParseIdError));
+        assert_eq!(Id::C_INF.to_string(), "CInf");
+        assert_eq!(format!("{:<6}|", Id::LINE), "Line  |");
+        assert_eq!(Id::new([b'a', 0, 0xff, b' ']).to_string(), "a\\x00\\xff ");
+    }
+
+    #[test]
+    fn from_bytes() {
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert_eq!(file.peek_name().unwrap().as_deref(), Some("test"));
+        let mut file = BeamFile::from_bytes(data).unwrap();
+        assert_eq!(file.read_raw(Id(*b"StrT")).unwrap(), b"");
+
+        let err = BeamFile::from_slice(b"FOR1").unwrap_err();
+        assert!(matches!(err, BeamFileError::Io(_)));
+
+        fn load<'a, T: TryFrom<&'a Path, Error = BeamFileError>>(path: &'a Path) -> Result<T> {
+            T::try_from(path)
+        }
+        let mut file: BeamFile<File> = load(Path::new("fixtures/test.beam")).unwrap();
+        let data = file.read_raw(Id(*b"AtU8")).unwrap();
+        let err = BeamFile::try_from(&data[..]).unwrap_err();
+        assert!(matches!(
+            err.into_root(),
+            BeamFileError::UnexpectedMagicNumber(_)
+        ));
+    }
+
+    #[test]
+    fn open_all() {
+        let paths = [
+            "fixtures/test.beam",
+            "fixtures/missing.beam",
+            "fixtures/test.beam",
+        ];
+        let options = BeamFileOptions::new();
+        for executor in [
+            &Sequential as &dyn Executor,
+            &ScopedThreads::with_threads(2),
+        ] {
+            let mut results = options.open_all(&paths, executor);
+            assert_eq!(results.len(), 3);
+            assert_eq!(
+                results[0].as_mut().unwrap().peek_name().unwrap().unwrap(),
+                "test"
+            );
+            assert_eq!(results[1].as_ref().unwrap_err().kind(), ErrorKind::Io);
+            assert!(results[2].is_ok());
+        }
+
+        /// Runs only the first task
+        struct Lazy;
+
+        impl Executor for Lazy {
+            fn execute<'a>(&self, tasks: Vec<Task<'a>>) {
+                if let Some(task) = tasks.into_iter().next() {
+                    task();
+                }
+            }
+        }
+
+        let results = options.open_all(&paths, &Lazy);
+        assert!(results[0].is_ok());
+        let err = results[2].as_ref().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        let path = err.context().and_then(|context| context.path.as_deref());
+        assert_eq!(path, Some(Path::new("fixtures/test.beam")));
+    }
+
+    #[test]
+    fn metrics() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        #[derive(Default)]
+        struct Counters {
+            files: AtomicU64,
+            bytes: AtomicU64,
+            decoded: AtomicU64,
+            failed: AtomicU64,
+        }
+
+        impl Metrics for Counters {
+            fn file_parsed(&self) {
+                self.files.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn bytes_read(&self, bytes: u64) {
+                self.bytes.fetch_add(bytes, Ordering::Relaxed);
+            }
+
+            fn chunk_decoded(&self, _chunk: Id) {
+                self.decoded.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn decode_failed(&self, _chunk: Id) {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        // Other tests run concurrently and report to the same receiver,
+        // so only lower bounds can be checked.
+        let counters: &'static Counters = Box::leak(Box::default());
+        assert!(set_metrics(counters));
+        assert!(!set_metrics(counters));
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        file.read::<ExpTChunk<_>>().unwrap();
+        let mut file = BeamFile::from_reader(Cursor::new(beam(&[(b"Attr", b"xx")]))).unwrap();
+        file.attributes().unwrap_err();
+
+        assert!(counters.files.load(Ordering::Relaxed) >= 2);
+        assert!(counters.bytes.load(Ordering::Relaxed) >= 12 + 44 + 28);
+        assert!(counters.decoded.load(Ordering::Relaxed) >= 1);
+        assert!(counters.failed.load(Ordering::Relaxed) >= 1);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing() {
+        use std::sync::{Arc, Mutex};
+        use tracing::{span, Event, Metadata, Subscriber};
+
+        /// Records the names of spans and the messages of events
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        struct Message<'a>(&'a mut String);
+
+        impl tracing::field::Visit for Message<'_> {
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    *self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+                let name = span.metadata().name().to_string();
+                self.0.lock().unwrap().push(name);
+                span::Id::from_u64(1)
+            }
+
+            fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+            fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut message = String::new();
+                event.record(&mut Message(&mut message));
+                self.0.lock().unwrap().push(message);
+            }
+
+            fn enter(&self, _: &span::Id) {}
+
+            fn exit(&self, _: &span::Id) {}
+        }
+
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+            file.index_atoms(NaiveInterner).unwrap();
+        });
+        let recorded = recorder.0.lock().unwrap();
+        assert_eq!(recorded[..2], ["open", "index"]);
+        assert_eq!(
+            recorded
+                .iter()
+                .filter(|name| *name == "found chunk")
+                .count(),
+            10
+        );
+        assert!(recorded.contains(&"indexed".to_string()));
+        assert!(recorded.contains(&"read chunk".to_string()));
+        assert_eq!(recorded.last().unwrap(), "indexed atoms");
+    }
+
+    #[test]
+    fn chunk_source() {
+        struct Recording {
+            data: Vec<u8>,
+            reads: Vec<(u64, usize)>,
+        }
+
+        impl ChunkSource for Recording {
+            fn size(&mut self) -> io::Result<u64> {
+                Ok(self.data.len() as u64)
+            }
+
+            fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+                self.reads.push((offset, len));
+                self.data.read_at(offset, len)
+            }
+        }
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let source = Recording {
+            data: data.clone(),
+            reads: Vec::new(),
+        };
+        let mut file = BeamFile::from_source(source).unwrap();
+        let headers = file.reader.get_ref().reads.len();
+        assert_eq!(file.read_raw(Id(*b"ExpT")).unwrap().len(), 28);
+        let reads = &file.reader.get_ref().reads[headers..];
+        assert_eq!(reads.len(), 1);
+        assert_eq!(reads[0].1, 28);
+
+        let mut file = BeamFile::from_source(data.as_slice()).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        assert_eq!(file.name().map(String::as_str), Some("test"));
+        let err = BeamFile::from_source(&data[..100]).unwrap_err();
+        assert!(matches!(err, BeamFileError::InvalidPayloadSize { .. }));
+    }
+
+    #[test]
+    fn beam_slice() {
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let slice = BeamSlice::new(&data).unwrap();
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+
+        let ids: Vec<_> = slice.chunks().map(|(id, _)| id).collect();
+        assert_eq!(ids[0], Id(*b"AtU8"));
+        assert_eq!(ids.len(), 10);
+        for (id, chunk) in slice.chunks() {
+            assert_eq!(chunk, &file.read_raw(id).unwrap()[..]);
+        }
+        assert_eq!(slice.chunk(Id(*b"LitT")), None);
+
+        let err = BeamSlice::new(&data[..10]).unwrap_err();
+        assert!(matches!(err, BeamFileError::UnexpectedEof(0)));
+        let slice = BeamFileOptions::new()
+            .recover_truncated(true)
+            .parse_slice(&data[..100])
+            .unwrap();
+        assert!(slice.is_truncated());
+        assert_eq!(slice.chunks().count(), 1);
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn ffi() {
+        use std::ffi::CStr;
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        unsafe {
+            let file = ffi::fast_beam_open(data.as_ptr(), data.len());
+            assert!(!file.is_null());
+            assert_eq!(
+                CStr::from_ptr(ffi::fast_beam_name(file)).to_str(),
+                Ok("test")
+            );
+            assert_eq!(ffi::fast_beam_atom_count(file), 4);
+            assert!(ffi::fast_beam_atom(file, 4).is_null());
+
+            let mut len = 0;
+            let chunks = ffi::fast_beam_chunks(file, &mut len);
+            let chunks = std::slice::from_raw_parts(chunks, len);
+            assert_eq!(chunks.len(), 10);
+            assert_eq!(&chunks[0].id, b"AtU8");
+            assert_eq!(chunks[0].offset, 20);
+
+            let exports = ffi::fast_beam_exports(file, &mut len);
+            let exports = std::slice::from_raw_parts(exports, len);
+            assert_eq!(
+                CStr::from_ptr(exports[0].function).to_str(),
+                Ok("module_info")
+            );
+            assert_eq!((exports[0].arity, exports[0].label), (1, 4));
+
+            let imports = ffi::fast_beam_imports(file, &mut len);
+            let imports = std::slice::from_raw_parts(imports, len);
+            assert_eq!(CStr::from_ptr(imports[1].module).to_str(), Ok("erlang"));
+            assert_eq!(imports[1].arity, 2);
+            ffi::fast_beam_free(file);
+
+            assert!(ffi::fast_beam_open(data.as_ptr(), 10).is_null());
+            assert!(!ffi::fast_beam_last_error().is_null());
+        }
     }
 
     #[test]
     fn impt_chunk() {
-        let mut file = BeamFile::<_, NaiveInterner>::from_file("fixtures/test.beam").unwrap();
-        file.index_atoms(NaiveInterner::default()).unwrap();
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
         let chunk: ImpTChunk<String> = file.read().unwrap();
 
         assert_eq!(chunk.imports.len(), 2);
@@ -275,10 +4370,360 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "arena")]
+    #[test]
+    fn arena_chunks() {
+        let bump = bumpalo::Bump::new();
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+
+        let imports: ArenaImpTChunk<String> = file.read_in(&bump).unwrap();
+        let exports: ArenaExpTChunk<String> = file.read_in(&bump).unwrap();
+
+        assert_eq!(imports.imports.len(), 2);
+        assert_eq!(imports.imports[1].arity, 2);
+        assert_eq!(exports.exports.len(), 2);
+        assert_eq!(exports.exports[0].label, 4);
+
+        let locals: ArenaLocTChunk<String> = file.read_in(&bump).unwrap();
+        assert!(locals.locals.is_empty());
+        let code: ArenaCodeChunk<String> = file.read_in(&bump).unwrap();
+        let expected: CodeChunk<String> = file.read().unwrap();
+        assert_eq!(code.header, expected.header);
+        assert_eq!(&code.instructions[..], &expected.instructions[..]);
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let code: ArenaCodeChunk<String> = file.read_in(&bump).unwrap();
+        assert_eq!(&code.instructions[..], &expected.instructions[..]);
+        let position = file.index[&Id::LOC_T].position as usize;
+        let mut data = data;
+        data[position..position + 4].copy_from_slice(&[0, 0, 0, 1]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        assert!(file.read_in::<ArenaLocTChunk<String>>(&bump).is_err());
+    }
+
+    #[test]
+    fn read_many() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(matches!(
+            file.read_many::<(ImpTChunk<_>, ExpTChunk<_>)>(),
+            Err(BeamFileError::AtomsNotIndexed)
+        ));
+        file.index_atoms(NaiveInterner).unwrap();
+
+        let (exports, imports, locals): (ExpTChunk<String>, ImpTChunk<String>, LocTChunk<String>) =
+            file.read_many().unwrap();
+        assert_eq!(
+            exports.exports,
+            file.read::<ExpTChunk<_>>().unwrap().exports
+        );
+        assert_eq!(
+            imports.imports,
+            file.read::<ImpTChunk<_>>().unwrap().imports
+        );
+        assert!(locals.locals.is_empty());
+
+        let (first, second): (ExpTChunk<String>, ExpTChunk<String>) = file.read_many().unwrap();
+        assert_eq!(first.exports, second.exports);
+
+        let data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x00"),
+            (b"ExpT", b"\x00\x00\x00\x00"),
+        ]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        assert!(matches!(
+            file.read_many::<(ExpTChunk<_>, ImpTChunk<_>)>(),
+            Err(BeamFileError::MissingChunk(Id::IMP_T))
+        ));
+    }
+
+    #[test]
+    fn summary() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let summary = file.summary().unwrap();
+        assert_eq!(summary.module, "test");
+        assert_eq!(
+            (summary.exports, summary.imports, summary.locals),
+            (2, 2, 0)
+        );
+        assert_eq!(summary.chunks.len(), 10);
+
+        let text = summary.to_string();
+        assert!(text.starts_with("module:  test\nexports: 2\nimports: 2\nlocals:  0\n"));
+        assert!(text.contains("\nAtU8           20         44\n"));
+        assert!(text.contains("\ncompile info:\n  version: "));
+
+        let data = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x04test")]);
+        let summary = BeamFile::from_slice(&data).unwrap().summary().unwrap();
+        assert_eq!(summary.exports, 0);
+        assert!(!summary.to_string().contains("compile info"));
+    }
+
+    #[test]
+    fn forms() {
+        let first = std::fs::read("fixtures/test.beam").unwrap();
+        let second = beam(&[(b"AtU8", b"\x00\x00\x00\x01\x05other")]);
+        let mut stream = [&first[..], &second[..]].concat();
+
+        let mut forms = Forms::new(&stream[..]);
+        let mut file = forms.next().unwrap().unwrap();
+        assert_eq!(file.read_raw(Id::AT_U8).unwrap().len(), 44);
+        assert_eq!(forms.offset(), first.len() as u64);
+        let mut file = forms.next().unwrap().unwrap();
+        assert_eq!(file.owned_atoms().unwrap(), ["other"]);
+        assert!(forms.next().is_none());
+
+        stream.extend_from_slice(b"FOR1\x00\x00\x01\x00BEAM");
+        let results: Vec<_> = BeamFileOptions::new().forms(&stream[..]).collect();
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            results[2],
+            Err(BeamFileError::InvalidPayloadSize {
+                declared: 256,
+                available: 4
+            })
+        ));
+
+        stream.truncate(first.len() + second.len() + 3);
+        let mut forms = Forms::new(&stream[..]);
+        assert!(matches!(
+            forms.nth(2),
+            Some(Err(BeamFileError::UnexpectedEof(_)))
+        ));
+        assert!(forms.next().is_none());
+        // The declared size isn't trusted for preallocating the form
+        let mut forms = Forms::new(&b"FOR1\xff\xff\xff\xf0BEAM"[..]);
+        assert!(matches!(
+            forms.next(),
+            Some(Err(BeamFileError::InvalidPayloadSize {
+                declared: 0xffff_fff0,
+                available: 4
+            }))
+        ));
+    }
+
+    #[test]
+    fn freeze() {
+        fn assert_owned<T: Send + 'static>(_: &T) {}
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let expected = file.read_raw(Id::CODE).unwrap();
+        let frozen: OwnedBeamFile = file.freeze().unwrap();
+        assert_owned(&frozen);
+        assert_eq!(frozen.path(), Some(Path::new("fixtures/test.beam")));
+        assert_eq!(frozen.atom_index().unwrap()[0], "test");
+
+        let mut clone = frozen.clone();
+        assert_eq!(clone.read_raw(Id::CODE).unwrap(), expected);
+        assert_eq!(clone.read::<ExpTChunk<String>>().unwrap().exports.len(), 2);
+        assert_eq!(
+            clone.into_inner().into_inner()[..],
+            std::fs::read("fixtures/test.beam").unwrap()[..]
+        );
+
+        let file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let total = file.chunks().map(|(_, _, size)| size).sum();
+        let file = BeamFileOptions::new()
+            .limits(*Limits::new().max_total_size(total))
+            .open("fixtures/test.beam")
+            .unwrap();
+        let err = file.freeze().unwrap_err();
+        assert!(matches!(
+            err.root(),
+            BeamFileError::LimitExceeded {
+                limit: "File size",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn from_shared() {
+        let data: Arc<[u8]> = std::fs::read("fixtures/test.beam").unwrap().into();
+        let mut file = BeamFile::from_shared(data.clone()).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let mut file = file.clone();
+                std::thread::spawn(move || file.read::<ExpTChunk<String>>().unwrap().exports)
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap().len(), 2);
+        }
+        assert!(Arc::ptr_eq(&file.index, &file.clone().index));
+        assert_eq!(Arc::strong_count(&data), 2);
+    }
+
+    #[test]
+    fn into_parts() {
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let file = BeamFile::from_bytes(data.clone()).unwrap();
+        assert_eq!(file.into_inner().into_inner(), data);
+
+        let mut file = BeamFile::from_bytes(data.clone()).unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
+        let chunks: Vec<_> = file.chunks().collect();
+        let parts = file.into_parts();
+        assert_eq!(parts.reader.into_inner(), data);
+        assert_eq!(parts.chunks, chunks);
+        assert_eq!(parts.atoms.unwrap()[0], "test");
+    }
+
+    #[test]
+    fn prefetch() {
+        use std::{cell::Cell, rc::Rc};
+
+        /// Counts the seeks, which every read of a chunk starts with
+        struct Seeks(Cursor<Vec<u8>>, Rc<Cell<usize>>);
+
+        impl Read for Seeks {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                self.0.read(buf)
+            }
+        }
+
+        impl Seek for Seeks {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                self.1.set(self.1.get() + 1);
+                self.0.seek(pos)
+            }
+        }
+
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let seeks = Rc::new(Cell::new(0));
+        let mut file =
+            BeamFile::from_reader(Seeks(Cursor::new(data.clone()), seeks.clone())).unwrap();
+        seeks.set(0);
+
+        file.prefetch(&[Id::AT_U8, Id::IMP_T, Id::EXP_T, Id::ATTR, Id::DOCS])
+            .unwrap();
+        assert_eq!(seeks.get(), 1);
+        let atoms = file.owned_atoms().unwrap();
+        let imports = file.read_with_atoms::<ImpTChunk<String>>(&atoms).unwrap();
+        file.attributes().unwrap();
+        assert_eq!(file.read_raw(Id::EXP_T).unwrap(), &data[180..208]);
+        assert_eq!(seeks.get(), 1);
+        assert_eq!(imports.imports.len(), 2);
+
+        file.read_raw(Id::CODE).unwrap();
+        assert_eq!(seeks.get(), 2);
+        file.clear_prefetched();
+        file.read_raw(Id::EXP_T).unwrap();
+        assert_eq!(seeks.get(), 3);
+
+        /// Panics on any use once armed
+        struct Armed(Cursor<Vec<u8>>, Rc<Cell<bool>>);
+
+        impl Read for Armed {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                assert!(!self.1.get(), "read after prefetch");
+                self.0.read(buf)
+            }
+        }
+
+        impl Seek for Armed {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                assert!(!self.1.get(), "seek after prefetch");
+                self.0.seek(pos)
+            }
+        }
+
+        let armed = Rc::new(Cell::new(false));
+        let mut file =
+            BeamFile::from_reader(Armed(Cursor::new(data.clone()), armed.clone())).unwrap();
+        file.prefetch(&KNOWN_CHUNKS).unwrap();
+        armed.set(true);
+
+        assert_eq!(file.peek_name().unwrap().unwrap(), "test");
+        file.index_atoms(NaiveInterner).unwrap();
+        let (exports, imports) = file
+            .read_many::<(ExpTChunk<String>, ImpTChunk<String>)>()
+            .unwrap();
+        assert_eq!((exports.exports.len(), imports.imports.len()), (2, 2));
+        let linked = file.read_in_context::<code::LinkedCode<String>>().unwrap();
+        assert_eq!(linked.imports.len(), 2);
+        assert_eq!(file.iter_raw().count(), 10);
+        file.chunk_hashes().unwrap();
+        let mut same = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(file
+            .diff(&mut same, &DiffOptions::new())
+            .unwrap()
+            .is_empty());
+        let chunks: Vec<_> = file
+            .into_chunks()
+            .map(|(id, data)| (id, data.unwrap()))
+            .collect();
+        assert_eq!(chunks[0], (Id::AT_U8, data[20..64].to_vec()));
+    }
+
+    #[test]
+    fn read_raw_cow() {
+        let data = std::fs::read("fixtures/test.beam").unwrap();
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        let expected = file.read_raw(Id::EXP_T).unwrap();
+        assert!(
+            matches!(file.read_raw_cow(Id::EXP_T), Ok(Cow::Borrowed(data)) if data == expected)
+        );
+
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(matches!(file.read_raw_cow(Id::EXP_T), Ok(Cow::Owned(data)) if data == expected));
+        assert!(matches!(
+            file.read_raw_cow(Id::LIT_T),
+            Err(BeamFileError::MissingChunk(Id::LIT_T))
+        ));
+    }
+
+    #[test]
+    fn has_chunk() {
+        let file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        assert!(file.has_chunk(Id::DBGI));
+        assert!(!file.has_chunk(Id::DOCS));
+        assert_eq!(file.chunk_size(Id::LOC_T), Some(4));
+        assert_eq!(file.chunk_size(Id::STR_T), Some(0));
+        assert_eq!(file.chunk_size(Id::LIT_T), None);
+    }
+
+    #[test]
+    fn read_with() {
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        let exports: ExpTChunk<String> = file.read_with(NaiveInterner).unwrap();
+        assert_eq!(exports.exports.len(), 2);
+        assert_eq!(file.name().map(String::as_str), Some("test"));
+
+        let imports: ImpTChunk<String> = file.read_with(NaiveInterner).unwrap();
+        assert_eq!(imports.imports[0].module, "erlang");
+    }
+
+    #[test]
+    fn read_opt() {
+        let data = beam(&[
+            (b"AtU8", b"\x00\x00\x00\x00"),
+            (b"ExpT", b"\x00\x00\x00\x00"),
+        ]);
+        let mut file = BeamFile::from_slice(&data).unwrap();
+        assert!(matches!(
+            file.read_opt::<ExpTChunk<_>>(),
+            Err(BeamFileError::AtomsNotIndexed)
+        ));
+        file.index_atoms(NaiveInterner).unwrap();
+
+        assert!(
+            matches!(file.read_opt::<ExpTChunk<_>>(), Ok(Some(chunk)) if chunk.exports.is_empty())
+        );
+        assert!(matches!(file.read_opt::<ImpTChunk<_>>(), Ok(None)));
+    }
+
     #[test]
     fn expt_chunk() {
-        let mut file = BeamFile::<_, NaiveInterner>::from_file("fixtures/test.beam").unwrap();
-        file.index_atoms(NaiveInterner::default()).unwrap();
+        let mut file = BeamFile::from_file("fixtures/test.beam").unwrap();
+        file.index_atoms(NaiveInterner).unwrap();
         let chunk: ExpTChunk<String> = file.read().unwrap();
 
         assert_eq!(chunk.exports.len(), 2);
@@ -0,0 +1,167 @@
+use std::io::{Read, Seek};
+
+use byteorder::{BigEndian, ReadBytesExt};
+
+use crate::{
+    chunk::cautious_capacity,
+    code::{op, Decoder, Value, TAG_A, TAG_I},
+    BeamFile, BeamFileError, CodeChunk, Id, InternerMut, Operand, Result,
+};
+
+/// A source location referenced by `line` instructions
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Location {
+    /// Index of the file, see `LineChunk::file`
+    pub file: u32,
+    pub line: u32,
+}
+
+/// Source lines of a function, see `BeamFile::function_spans`
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionSpan {
+    pub name: String,
+    pub arity: u32,
+    /// File the function is defined in, `None` for the module's own source file
+    pub file: Option<String>,
+    /// Line of the function head
+    pub start: u32,
+    /// Last line of the function in `file`
+    pub end: u32,
+}
+
+/// The line table from the `Line` chunk
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct LineChunk {
+    pub version: u32,
+    pub flags: u32,
+    /// Number of `line` instructions in the code
+    pub instruction_count: u32,
+    /// Locations indexed by the argument of `line` instructions
+    ///
+    /// Index 0 stands for an unknown location.
+    pub locations: Vec<Option<Location>>,
+    /// Names of included files, the module's own source file isn't listed
+    pub filenames: Vec<String>,
+}
+
+impl LineChunk {
+    pub const ID: Id = Id::LINE;
+
+    pub fn decode<R: Read>(mut reader: R) -> Result<Self> {
+        let version = reader.read_u32::<BigEndian>()?;
+        let flags = reader.read_u32::<BigEndian>()?;
+        let instruction_count = reader.read_u32::<BigEndian>()?;
+        let item_count = reader.read_u32::<BigEndian>()? as usize;
+        let filename_count = reader.read_u32::<BigEndian>()? as usize;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        let mut decoder = Decoder::<()> {
+            chunk: Self::ID,
+            data: &data,
+            position: 0,
+            atom_index: &[],
+        };
+        let mut locations = Vec::with_capacity(cautious_capacity::<Location>(item_count + 1));
+        locations.push(None);
+        let mut file = 0;
+        while locations.len() <= item_count {
+            match decoder.tagged()? {
+                // Atom-tagged values switch the current file
+                (TAG_A, Value::Small(index), _) => file = index as u32,
+                (TAG_I, Value::Small(line), _) => locations.push(Some(Location {
+                    file,
+                    line: line as u32,
+                })),
+                _ => {
+                    return Err(BeamFileError::MalformedChunk {
+                        chunk: Self::ID,
+                        reason: "invalid line item",
+                    })
+                }
+            }
+        }
+
+        let mut names = &data[decoder.position..];
+        let mut filenames = Vec::with_capacity(cautious_capacity::<String>(filename_count));
+        for _ in 0..filename_count {
+            let len = names.read_u16::<BigEndian>()? as usize;
+            if names.len() < len {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+            }
+            let (name, rest) = names.split_at(len);
+            filenames.push(String::from_utf8_lossy(name).into_owned());
+            names = rest;
+        }
+
+        Ok(LineChunk {
+            version,
+            flags,
+            instruction_count,
+            locations,
+            filenames,
+        })
+    }
+
+    /// Location referenced by a `line` instruction argument
+    pub fn location(&self, index: u32) -> Option<&Location> {
+        self.locations.get(index as usize).and_then(Option::as_ref)
+    }
+
+    /// Name of a file referenced by a location
+    ///
+    /// File 0 is the module's own source file, which is returned as `None`,
+    /// as its name is only known from the compile info.
+    pub fn file(&self, index: u32) -> Option<&str> {
+        (index as usize)
+            .checked_sub(1)
+            .and_then(|i| self.filenames.get(i))
+            .map(String::as_str)
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Source lines of each function, in the order they're defined
+    ///
+    /// The span starts at the location of the function head and ends at the
+    /// last line the function's `line` instructions refer to in the same
+    /// file, so code inlined from included files doesn't extend it.
+    /// Functions without line information, such as the generated
+    /// `module_info`, are left out.
+    pub fn function_spans(&mut self) -> Result<Vec<FunctionSpan>> {
+        let atoms = self.owned_atoms()?;
+        let code = self.read_with_atoms::<CodeChunk<String>>(&atoms)?;
+        let lines = self.lines()?;
+
+        let spans = code
+            .functions()
+            .into_iter()
+            .filter_map(|function| {
+                let mut locations = function
+                    .instructions
+                    .iter()
+                    .filter(|instruction| instruction.opcode == op::LINE)
+                    .filter_map(|instruction| match instruction.args.first() {
+                        Some(Operand::Unsigned(index)) => lines.location(*index as u32),
+                        _ => None,
+                    });
+                let head = locations.next()?;
+                let end = locations
+                    .filter(|location| location.file == head.file)
+                    .fold(head.line, |end, location| end.max(location.line));
+                Some(FunctionSpan {
+                    name: function.name.clone(),
+                    arity: function.arity,
+                    file: lines.file(head.file).map(str::to_string),
+                    start: head.line,
+                    end,
+                })
+            })
+            .collect();
+        Ok(spans)
+    }
+}
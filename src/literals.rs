@@ -0,0 +1,278 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use byteorder::{BigEndian, ReadBytesExt};
+use flate2::read::ZlibDecoder;
+use fxhash::{FxHashMap, FxHashSet};
+
+use crate::{
+    chunk::cautious_capacity, fingerprint::term_fingerprint, BeamFileError, Fingerprint, Id,
+    Limits, Result, Term,
+};
+
+/// Number of characters of a literal kept for display
+const PREVIEW_LEN: usize = 60;
+
+/// The literal table from the `LitT` chunk
+///
+/// Indexed by the literal arguments of `Code` chunk instructions.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LitTChunk {
+    pub literals: Vec<Term>,
+    /// Encoded size of each literal, in bytes
+    pub sizes: Vec<u32>,
+}
+
+impl LitTChunk {
+    pub const ID: Id = Id::LIT_T;
+
+    /// Decodes the table, decompressing at most `max_size` bytes
+    pub fn decode<R: Read>(mut reader: R, max_size: u64) -> Result<Self> {
+        let size = reader.read_u32::<BigEndian>()? as u64;
+        Limits::check("Decompressed size", size, max_size)?;
+
+        // Tables are stored uncompressed when the declared size is 0
+        let mut data = Vec::with_capacity(cautious_capacity::<u8>(size as usize));
+        if size == 0 {
+            reader.read_to_end(&mut data)?;
+        } else {
+            let mut decoder = ZlibDecoder::new(reader);
+            (&mut decoder).take(size).read_to_end(&mut data)?;
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                compressed = decoder.total_in(),
+                decompressed = size,
+                "decompressed"
+            );
+            if data.len() as u64 != size {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+
+        let mut data = &data[..];
+        let count = data.read_u32::<BigEndian>()? as usize;
+        let mut literals = Vec::with_capacity(cautious_capacity::<Term>(count));
+        let mut sizes = Vec::with_capacity(cautious_capacity::<u32>(count));
+        for _ in 0..count {
+            let len = data.read_u32::<BigEndian>()?;
+            if data.len() < len as usize {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            let (literal, rest) = data.split_at(len as usize);
+            literals.push(Term::decode(literal)?);
+            sizes.push(len);
+            data = rest;
+        }
+
+        if !data.is_empty() {
+            return Err(BeamFileError::MalformedChunk {
+                chunk: Self::ID,
+                reason: "data after the last literal",
+            });
+        }
+
+        Ok(LitTChunk { literals, sizes })
+    }
+}
+
+/// Size and position of a literal, see `LiteralReport`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LiteralSize {
+    /// Index into the literal table
+    pub index: usize,
+    /// Encoded size, in bytes
+    pub size: u32,
+}
+
+/// A literal stored more than once in the same table
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DuplicateLiteral {
+    /// Indices of every copy, in order
+    pub indices: Vec<usize>,
+    /// Encoded size of one copy, in bytes
+    pub size: u32,
+}
+
+impl DuplicateLiteral {
+    /// Bytes that would be saved by storing the literal once
+    pub fn wasted(&self) -> u64 {
+        self.size as u64 * (self.indices.len() as u64 - 1)
+    }
+}
+
+/// Summary of a literal table, see `LitTChunk::report`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LiteralReport {
+    pub count: usize,
+    /// Encoded size of all literals, in bytes
+    pub total_size: u64,
+    /// The largest literals, largest first
+    pub largest: Vec<LiteralSize>,
+    /// Literals stored more than once, the most wasteful first
+    pub duplicates: Vec<DuplicateLiteral>,
+}
+
+impl LitTChunk {
+    /// Reports the `top` largest literals and any duplicates
+    ///
+    /// The compiler normally merges equal literals, so duplicates point to
+    /// tools rewriting the table.
+    pub fn report(&self, top: usize) -> LiteralReport {
+        let mut largest: Vec<_> = self
+            .sizes
+            .iter()
+            .enumerate()
+            .map(|(index, &size)| LiteralSize { index, size })
+            .collect();
+        largest.sort_by(|a, b| b.size.cmp(&a.size).then(a.index.cmp(&b.index)));
+        largest.truncate(top);
+
+        // Equal literals have the same size and text, so only literals sharing
+        // both are compared
+        let mut buckets: FxHashMap<_, Vec<Vec<usize>>> = FxHashMap::default();
+        for (index, (literal, &size)) in self.literals.iter().zip(&self.sizes).enumerate() {
+            let key = (size, term_fingerprint(&literal.to_string()));
+            let copies = buckets.entry(key).or_default();
+            match copies
+                .iter_mut()
+                .find(|indices| self.literals[indices[0]] == *literal)
+            {
+                Some(indices) => indices.push(index),
+                None => copies.push(vec![index]),
+            }
+        }
+        let mut duplicates: Vec<_> = buckets
+            .into_iter()
+            .flat_map(|((size, _), copies)| {
+                copies
+                    .into_iter()
+                    .filter(|indices| indices.len() > 1)
+                    .map(move |indices| DuplicateLiteral { indices, size })
+            })
+            .collect();
+        duplicates
+            .sort_by_key(|duplicate| (std::cmp::Reverse(duplicate.wasted()), duplicate.indices[0]));
+
+        LiteralReport {
+            count: self.literals.len(),
+            total_size: self.sizes.iter().map(|&size| size as u64).sum(),
+            largest,
+            duplicates,
+        }
+    }
+}
+
+impl fmt::Display for LiteralReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} literals, {} bytes", self.count, self.total_size)?;
+        if !self.largest.is_empty() {
+            writeln!(f, "largest:")?;
+        }
+        for literal in &self.largest {
+            writeln!(f, "  #{}: {} bytes", literal.index, literal.size)?;
+        }
+        if !self.duplicates.is_empty() {
+            writeln!(f, "duplicates:")?;
+        }
+        for duplicate in &self.duplicates {
+            let indices: Vec<_> = duplicate
+                .indices
+                .iter()
+                .map(|index| format!("#{}", index))
+                .collect();
+            writeln!(
+                f,
+                "  {}: {} bytes each, {} wasted",
+                indices.join(", "),
+                duplicate.size,
+                duplicate.wasted()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A literal of one module, as kept by a `BeamSet`
+#[derive(Clone, Debug)]
+pub(crate) struct LiteralDigest {
+    pub(crate) fingerprint: Fingerprint,
+    pub(crate) size: u32,
+    pub(crate) preview: String,
+}
+
+impl LitTChunk {
+    /// Fingerprints of the distinct literals of the table
+    pub(crate) fn digests(&self) -> Vec<LiteralDigest> {
+        let mut digests = Vec::new();
+        let mut seen = FxHashSet::default();
+        for (literal, &size) in self.literals.iter().zip(&self.sizes) {
+            let text = literal.to_string();
+            let fingerprint = term_fingerprint(&text);
+            if !seen.insert(fingerprint) {
+                continue;
+            }
+            let mut preview: String = text.chars().take(PREVIEW_LEN).collect();
+            if preview.len() < text.len() {
+                preview.push_str("...");
+            }
+            digests.push(LiteralDigest {
+                fingerprint,
+                size,
+                preview,
+            });
+        }
+        digests
+    }
+}
+
+/// A literal embedded in more than one module of a `BeamSet`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SharedLiteral {
+    /// Fingerprint of the literal, equal literals have equal fingerprints
+    pub fingerprint: Fingerprint,
+    /// Encoded size of one copy, in bytes
+    pub size: u32,
+    /// Modules embedding the literal, in the order of their names
+    pub modules: Vec<String>,
+    /// The literal in Erlang syntax, shortened if it's long
+    pub preview: String,
+}
+
+impl SharedLiteral {
+    /// Bytes that would be saved by keeping the literal in a single module
+    pub fn wasted(&self) -> u64 {
+        self.size as u64 * (self.modules.len() as u64 - 1)
+    }
+}
+
+/// Literals embedded in several modules, see `BeamSet::shared_literals`
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SharedLiteralReport {
+    /// The most wasteful first
+    pub literals: Vec<SharedLiteral>,
+}
+
+impl SharedLiteralReport {
+    pub fn wasted(&self) -> u64 {
+        self.literals.iter().map(SharedLiteral::wasted).sum()
+    }
+}
+
+impl fmt::Display for SharedLiteralReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for literal in &self.literals {
+            writeln!(
+                f,
+                "{} bytes in {} modules ({}): {}",
+                literal.size,
+                literal.modules.len(),
+                literal.modules.join(", "),
+                literal.preview
+            )?;
+        }
+        writeln!(f, "total: {} bytes wasted", self.wasted())
+    }
+}
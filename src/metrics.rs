@@ -0,0 +1,38 @@
+use std::sync::OnceLock;
+
+use crate::Id;
+
+/// Receives counters from all files parsed in the process
+///
+/// Install an implementation with `set_metrics`, for example one updating
+/// Prometheus counters. All methods do nothing by default.
+pub trait Metrics: Send + Sync {
+    /// A file was indexed
+    fn file_parsed(&self) {}
+
+    /// Bytes were read from a file, for headers or chunk contents
+    fn bytes_read(&self, _bytes: u64) {}
+
+    /// A chunk was decoded successfully
+    fn chunk_decoded(&self, _chunk: Id) {}
+
+    /// Decoding a chunk failed
+    fn decode_failed(&self, _chunk: Id) {}
+}
+
+struct NoMetrics;
+
+impl Metrics for NoMetrics {}
+
+static METRICS: OnceLock<&'static dyn Metrics> = OnceLock::new();
+
+/// Installs the metrics receiver for the process
+///
+/// Returns `false` if a receiver was already installed, which is kept.
+pub fn set_metrics(metrics: &'static dyn Metrics) -> bool {
+    METRICS.set(metrics).is_ok()
+}
+
+pub(crate) fn metrics() -> &'static dyn Metrics {
+    METRICS.get().copied().unwrap_or(&NoMetrics)
+}
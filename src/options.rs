@@ -0,0 +1,259 @@
+#[cfg(feature = "std")]
+use std::{
+    fmt::Write,
+    fs::File,
+    io::{Read, Seek},
+    path::Path,
+};
+
+#[cfg(any(feature = "tokio", feature = "futures-io"))]
+use crate::{AsyncBeamFile, AsyncSource};
+#[cfg(feature = "std")]
+use crate::{BeamFile, ChunkSource, Forms, SourceReader};
+use crate::{BeamFileError, BeamSlice, Result};
+
+/// How to treat a chunk id appearing more than once in a file
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DuplicateChunks {
+    /// Fail with `BeamFileError::DuplicateChunk`
+    Error,
+    /// Keep the first occurrence
+    FirstWins,
+    /// Keep the last occurrence
+    #[default]
+    LastWins,
+}
+
+/// How `BeamFile::index_atoms` treats atom names that aren't valid UTF-8
+///
+/// Applies only when the interner doesn't accept raw bytes through
+/// `Interner::intern_bytes`. Lossily decoded atoms are reported as
+/// `Warning::LossyAtom`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum InvalidAtoms {
+    /// Fail with `BeamFileError::InvalidAtom`
+    #[default]
+    Error,
+    /// Replace invalid sequences with `U+FFFD REPLACEMENT CHARACTER`
+    Replace,
+    /// Replace invalid bytes with `\xNN` escapes
+    HexEscape,
+}
+
+impl InvalidAtoms {
+    /// Decodes a name that isn't valid UTF-8, returns `None` with `Error`
+    #[cfg(feature = "std")]
+    pub(crate) fn decode(self, name: &[u8]) -> Option<String> {
+        match self {
+            InvalidAtoms::Error => None,
+            InvalidAtoms::Replace => Some(String::from_utf8_lossy(name).into_owned()),
+            InvalidAtoms::HexEscape => {
+                let mut decoded = String::with_capacity(name.len() * 4);
+                for chunk in name.utf8_chunks() {
+                    decoded.push_str(chunk.valid());
+                    for byte in chunk.invalid() {
+                        let _ = write!(decoded, "\\x{:02x}", byte);
+                    }
+                }
+                Some(decoded)
+            }
+        }
+    }
+}
+
+/// Bounds on the resources used for indexing and decoding a file
+///
+/// Exceeding any of them fails with `BeamFileError::LimitExceeded`.
+/// Everything is unlimited by default.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Limits {
+    pub(crate) max_atoms: usize,
+    pub(crate) max_chunk_size: u64,
+    pub(crate) max_total_size: u64,
+    pub(crate) max_decompressed_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_atoms: usize::MAX,
+            max_chunk_size: u64::MAX,
+            max_total_size: u64::MAX,
+            max_decompressed_size: u64::MAX,
+        }
+    }
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maximum number of atoms declared in the atom chunk
+    pub fn max_atoms(&mut self, max: usize) -> &mut Self {
+        self.max_atoms = max;
+        self
+    }
+
+    /// Maximum size of a single chunk in bytes
+    pub fn max_chunk_size(&mut self, max: u64) -> &mut Self {
+        self.max_chunk_size = max;
+        self
+    }
+
+    /// Maximum combined size of all indexed chunks in bytes
+    ///
+    /// Chunks are read into memory one at a time, so this bounds the memory
+    /// used by reading every chunk of the file.
+    pub fn max_total_size(&mut self, max: u64) -> &mut Self {
+        self.max_total_size = max;
+        self
+    }
+
+    /// Maximum size of a decompressed chunk, such as `LitT`, in bytes
+    pub fn max_decompressed_size(&mut self, max: u64) -> &mut Self {
+        self.max_decompressed_size = max;
+        self
+    }
+
+    pub(crate) fn check(limit: &'static str, value: u64, max: u64) -> Result<()> {
+        if value > max {
+            Err(BeamFileError::LimitExceeded { limit, value, max })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Options controlling how a BEAM file is indexed
+#[derive(Clone, Debug)]
+pub struct BeamFileOptions {
+    pub(crate) duplicate_chunks: DuplicateChunks,
+    pub(crate) allow_trailing_data: bool,
+    pub(crate) ignore_payload_size: bool,
+    pub(crate) allow_unpadded_final_chunk: bool,
+    pub(crate) invalid_atoms: InvalidAtoms,
+    pub(crate) recover_truncated: bool,
+    pub(crate) limits: Limits,
+}
+
+impl Default for BeamFileOptions {
+    fn default() -> Self {
+        Self {
+            duplicate_chunks: DuplicateChunks::default(),
+            allow_trailing_data: true,
+            ignore_payload_size: false,
+            allow_unpadded_final_chunk: false,
+            invalid_atoms: InvalidAtoms::default(),
+            recover_truncated: false,
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl BeamFileOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options accepting the common deviations of post-processed files
+    ///
+    /// Enables the `allow_*` options, and replaces invalid atom names instead
+    /// of failing. The declared payload size is still respected, since
+    /// ignoring it would make any trailing data parse as chunks.
+    pub fn lenient() -> Self {
+        Self {
+            duplicate_chunks: DuplicateChunks::default(),
+            allow_trailing_data: true,
+            ignore_payload_size: false,
+            allow_unpadded_final_chunk: true,
+            invalid_atoms: InvalidAtoms::Replace,
+            recover_truncated: false,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Whether data after the end of the payload is accepted, `true` by default
+    pub fn allow_trailing_data(&mut self, allow: bool) -> &mut Self {
+        self.allow_trailing_data = allow;
+        self
+    }
+
+    /// Whether to disregard the declared payload size, `false` by default
+    ///
+    /// When enabled, chunks are read up to the end of the file, so any
+    /// trailing data is treated as chunks as well.
+    pub fn ignore_payload_size(&mut self, ignore: bool) -> &mut Self {
+        self.ignore_payload_size = ignore;
+        self
+    }
+
+    /// Whether the padding of the final chunk may be missing, `false` by default
+    pub fn allow_unpadded_final_chunk(&mut self, allow: bool) -> &mut Self {
+        self.allow_unpadded_final_chunk = allow;
+        self
+    }
+
+    /// Sets the policy for duplicate chunk ids
+    ///
+    /// Occurrences not kept in the index are still available through
+    /// `BeamFile::iter_duplicates_raw`.
+    pub fn duplicate_chunks(&mut self, policy: DuplicateChunks) -> &mut Self {
+        self.duplicate_chunks = policy;
+        self
+    }
+
+    /// Whether to index the chunks present in a truncated file, `false` by default
+    ///
+    /// Instead of failing with `InvalidPayloadSize`, chunks up to the last one
+    /// fully present are indexed and the file is marked with
+    /// `BeamFile::is_truncated`.
+    pub fn recover_truncated(&mut self, recover: bool) -> &mut Self {
+        self.recover_truncated = recover;
+        self
+    }
+
+    /// Sets the policy for atom names that aren't valid UTF-8
+    pub fn invalid_atoms(&mut self, policy: InvalidAtoms) -> &mut Self {
+        self.invalid_atoms = policy;
+        self
+    }
+
+    /// Sets the resource limits enforced for the file
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.limits = limits;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<BeamFile<File>> {
+        BeamFile::open(path.as_ref(), self)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read + Seek>(&self, reader: R) -> Result<BeamFile<R>> {
+        BeamFile::parse(reader, self)
+    }
+
+    /// Indexes each of the forms stored back to back in `reader`
+    #[cfg(feature = "std")]
+    pub fn forms<R: Read>(&self, reader: R) -> Forms<R> {
+        Forms::with_options(reader, self)
+    }
+
+    /// Indexes a file from a `ChunkSource`, reading only the headers
+    #[cfg(feature = "std")]
+    pub fn from_source<S: ChunkSource>(&self, source: S) -> Result<BeamFile<SourceReader<S>>> {
+        BeamFile::parse(SourceReader::new(source)?, self)
+    }
+
+    /// Indexes a file held in memory, without copying it
+    pub fn parse_slice<'a>(&self, data: &'a [u8]) -> Result<BeamSlice<'a>> {
+        BeamSlice::parse(data, self)
+    }
+
+    #[cfg(any(feature = "tokio", feature = "futures-io"))]
+    pub async fn from_async_reader<R: AsyncSource>(&self, reader: R) -> Result<AsyncBeamFile<R>> {
+        AsyncBeamFile::parse(reader, self).await
+    }
+}
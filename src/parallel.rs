@@ -0,0 +1,102 @@
+use std::{fs::File, io, path::Path, sync::Mutex, thread};
+
+use crate::{BeamFile, BeamFileError, BeamFileOptions, Result};
+
+/// A unit of work handed to an `Executor`
+pub type Task<'a> = Box<dyn FnOnce() + Send + 'a>;
+
+/// Runs the tasks of bulk operations like `BeamFileOptions::open_all`
+///
+/// Implement it to run the work on an existing thread pool, for example
+/// with `rayon::scope`.
+pub trait Executor {
+    /// Runs all tasks, in any order, returning once all of them completed
+    fn execute<'a>(&self, tasks: Vec<Task<'a>>);
+}
+
+/// Runs tasks one after another on the calling thread
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sequential;
+
+impl Executor for Sequential {
+    fn execute<'a>(&self, tasks: Vec<Task<'a>>) {
+        for task in tasks {
+            task();
+        }
+    }
+}
+
+/// Runs tasks on scoped threads spawned for each call
+#[derive(Clone, Copy, Debug)]
+pub struct ScopedThreads {
+    threads: usize,
+}
+
+impl ScopedThreads {
+    /// Uses as many threads as the available parallelism
+    pub fn new() -> Self {
+        Self::with_threads(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+
+    /// Uses at most `threads` threads, at least one
+    pub fn with_threads(threads: usize) -> Self {
+        ScopedThreads {
+            threads: threads.max(1),
+        }
+    }
+}
+
+impl Default for ScopedThreads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Executor for ScopedThreads {
+    fn execute<'a>(&self, tasks: Vec<Task<'a>>) {
+        let threads = self.threads.min(tasks.len());
+        let queue = Mutex::new(tasks.into_iter());
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let task = queue.lock().unwrap().next();
+                    match task {
+                        Some(task) => task(),
+                        None => break,
+                    }
+                });
+            }
+        });
+    }
+}
+
+impl BeamFileOptions {
+    /// Opens and indexes many files, with the work run by `executor`
+    ///
+    /// Results are in the order of `paths`. Files whose task the executor
+    /// didn't run fail with an io error.
+    pub fn open_all<P, E>(&self, paths: &[P], executor: &E) -> Vec<Result<BeamFile<File>>>
+    where
+        P: AsRef<Path> + Sync,
+        E: Executor + ?Sized,
+    {
+        let mut results: Vec<Option<Result<BeamFile<File>>>> = paths.iter().map(|_| None).collect();
+        let tasks = paths
+            .iter()
+            .zip(&mut results)
+            .map(|(path, result)| Box::new(move || *result = Some(self.open(path))) as Task<'_>)
+            .collect();
+        executor.execute(tasks);
+
+        results
+            .into_iter()
+            .zip(paths)
+            .map(|(result, path)| {
+                result.unwrap_or_else(|| {
+                    let err = io::Error::other("the executor didn't run the task");
+                    Err(BeamFileError::from(err).in_file(Some(path.as_ref())))
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,697 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Write},
+};
+
+use crate::{
+    abstract_code::{spec_clauses, tagged, Spec},
+    term::AtomName,
+    typespec::{self, record_def, type_decl},
+    AbstractCode, Term,
+};
+
+const INDENT: usize = 4;
+
+/// Precedence of expressions that aren't operators, which never need parentheses
+const PRIMARY: u32 = 1000;
+/// Precedence of the operands of unary operators
+const UNARY: u32 = 700;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+/// Precedence and associativity of binary operators, from `erl_parse`
+fn binary_operator(op: &str) -> (u32, Associativity) {
+    match op {
+        "!" | "=" | "?=" => (150, Associativity::Right),
+        "orelse" => (160, Associativity::Right),
+        "andalso" => (200, Associativity::Right),
+        "==" | "/=" | "=<" | "<" | ">=" | ">" | "=:=" | "=/=" => (300, Associativity::None),
+        "++" | "--" => (400, Associativity::Right),
+        "+" | "-" | "bor" | "bxor" | "bsl" | "bsr" | "or" | "xor" => (500, Associativity::Left),
+        _ => (600, Associativity::Left),
+    }
+}
+
+/// Displays the forms as Erlang source, in the spirit of `erl_pp`
+///
+/// The source compiles back to an equivalent module, but the layout is
+/// regular rather than the original, and comments and macros are lost.
+/// Forms that aren't valid abstract code are written as comments.
+impl fmt::Display for AbstractCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer { out: String::new() };
+        let mut previous_function = false;
+        for form in &self.forms {
+            let function = match tagged(form) {
+                Some(("eof", _)) => continue,
+                Some((tag, _)) => tag == "function",
+                None => false,
+            };
+            if (function || previous_function) && !printer.out.is_empty() {
+                printer.out.push('\n');
+            }
+            printer.form(form);
+            previous_function = function;
+        }
+        f.write_str(&printer.out)
+    }
+}
+
+struct Printer {
+    out: String,
+}
+
+impl Printer {
+    fn newline(&mut self, indent: usize) {
+        self.out.push('\n');
+        self.out.extend(std::iter::repeat_n(' ', indent));
+    }
+
+    fn form(&mut self, form: &Term) {
+        match tagged(form) {
+            Some(("attribute", [_, Term::Atom(name), value])) => self.attribute(name, value),
+            Some(("function", [_, Term::Atom(name), _, clauses])) => {
+                let clauses = clauses.as_list().unwrap_or(&[]);
+                for (i, clause) in clauses.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(";\n");
+                    }
+                    self.out.push_str(&AtomName(name).to_string());
+                    self.clause(clause, 0);
+                }
+                self.out.push_str(".\n");
+            }
+            _ => {
+                let _ = writeln!(self.out, "%% {}", form);
+            }
+        }
+    }
+
+    fn attribute(&mut self, name: &str, value: &Term) {
+        let spec = |kind: &str| {
+            spec_clauses(value).map(|((name, _), clauses)| {
+                let spec = Spec {
+                    name: &name,
+                    clauses: &clauses,
+                    module: None,
+                };
+                format!("-{} {}.", kind, spec)
+            })
+        };
+        let text = match name {
+            "export" | "export_type" | "optional_callbacks" => {
+                function_list(value).map(|functions| format!("-{}([{}]).", name, functions))
+            }
+            "import" => match value {
+                Term::Tuple(elements) => match &elements[..] {
+                    [Term::Atom(module), functions] => function_list(functions).map(|functions| {
+                        format!("-import({}, [{}]).", AtomName(module), functions)
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            },
+            "file" => match value {
+                Term::Tuple(elements) => match &elements[..] {
+                    [file, Term::Integer(line)] => {
+                        Some(format!("-file({}, {}).", string(file), line))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+            "record" => record_def(value).map(|record| record.to_string()),
+            "type" | "opaque" => type_decl(value, name == "opaque").map(|ty| ty.to_string()),
+            "spec" | "callback" => spec(name),
+            _ => None,
+        };
+        match text {
+            Some(text) => self.out.push_str(&text),
+            None => {
+                let _ = write!(self.out, "-{}({}).", AtomName(name), value);
+            }
+        }
+        self.out.push('\n');
+    }
+
+    /// Writes `(Patterns) when Guards ->` and the body of a clause
+    fn clause(&mut self, clause: &Term, indent: usize) {
+        if let Some(("clause", [_, patterns, guards, body])) = tagged(clause) {
+            self.out.push('(');
+            self.exprs(patterns.as_list().unwrap_or(&[]), indent, ", ");
+            self.out.push(')');
+            self.guards(guards, indent);
+            self.body(body, indent);
+        }
+    }
+
+    /// Writes clauses of `case`, `receive` and the like, one per line
+    fn clauses(&mut self, clauses: &Term, indent: usize, kind: ClauseKind) {
+        let clauses = clauses.as_list().unwrap_or(&[]);
+        for (i, clause) in clauses.iter().enumerate() {
+            if i > 0 {
+                self.out.push(';');
+            }
+            self.newline(indent);
+            if let Some(("clause", [_, patterns, guards, body])) = tagged(clause) {
+                let patterns = patterns.as_list().unwrap_or(&[]);
+                match (kind, patterns) {
+                    (ClauseKind::Case, [pattern]) => self.expr(pattern, indent, 0),
+                    (ClauseKind::Catch, [pattern]) => self.catch_pattern(pattern, indent),
+                    (ClauseKind::Fun(name), _) => {
+                        if let Some(name) = name {
+                            self.out.push_str(name);
+                        }
+                        self.out.push('(');
+                        self.exprs(patterns, indent, ", ");
+                        self.out.push(')');
+                    }
+                    _ => {}
+                }
+                match kind {
+                    ClauseKind::If => {
+                        self.guard_sequence(guards, indent);
+                    }
+                    _ => self.guards(guards, indent),
+                }
+                self.body(body, indent);
+            }
+        }
+    }
+
+    /// Writes `Class:Reason:Stacktrace`, leaving out an ignored stacktrace
+    fn catch_pattern(&mut self, pattern: &Term, indent: usize) {
+        match tagged(pattern) {
+            Some(("tuple", [_, parts])) => match parts.as_list() {
+                Some([class, reason, stacktrace]) => {
+                    self.expr(class, indent, PRIMARY);
+                    self.out.push(':');
+                    self.expr(reason, indent, PRIMARY);
+                    if !matches!(tagged(stacktrace), Some(("var", [_, Term::Atom(name)])) if name == "_")
+                    {
+                        self.out.push(':');
+                        self.expr(stacktrace, indent, PRIMARY);
+                    }
+                }
+                _ => self.expr(pattern, indent, 0),
+            },
+            _ => self.expr(pattern, indent, 0),
+        }
+    }
+
+    fn guards(&mut self, guards: &Term, indent: usize) {
+        if guards.as_list().is_some_and(|guards| !guards.is_empty()) {
+            self.out.push_str(" when ");
+            self.guard_sequence(guards, indent);
+        }
+    }
+
+    /// Writes guards separated by `;`, each a list of tests separated by `,`
+    fn guard_sequence(&mut self, guards: &Term, indent: usize) {
+        for (i, guard) in guards.as_list().unwrap_or(&[]).iter().enumerate() {
+            if i > 0 {
+                self.out.push_str("; ");
+            }
+            self.exprs(guard.as_list().unwrap_or(&[]), indent, ", ");
+        }
+    }
+
+    /// Writes ` ->` and the expressions of a clause body, indented on their own lines
+    fn body(&mut self, body: &Term, indent: usize) {
+        self.out.push_str(" ->");
+        self.block(body, indent + INDENT);
+    }
+
+    /// Writes expressions on their own lines, separated by `,`
+    fn block(&mut self, body: &Term, indent: usize) {
+        for (i, expr) in body.as_list().unwrap_or(&[]).iter().enumerate() {
+            if i > 0 {
+                self.out.push(',');
+            }
+            self.newline(indent);
+            self.expr(expr, indent, 0);
+        }
+    }
+
+    fn exprs(&mut self, exprs: &[Term], indent: usize, separator: &str) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(separator);
+            }
+            self.expr(expr, indent, 0);
+        }
+    }
+
+    /// Writes an expression, in parentheses if it binds looser than `min_precedence`
+    fn expr(&mut self, expr: &Term, indent: usize, min_precedence: u32) {
+        if precedence(expr) < min_precedence {
+            self.out.push('(');
+            self.expr(expr, indent, 0);
+            self.out.push(')');
+            return;
+        }
+        let parts = match tagged(expr) {
+            Some((tag, [_, parts @ ..])) => (tag, parts),
+            _ => {
+                let _ = write!(self.out, "{}", expr);
+                return;
+            }
+        };
+        match parts {
+            ("var", [Term::Atom(name)]) => self.out.push_str(name),
+            ("atom", [Term::Atom(name)]) => {
+                let _ = write!(self.out, "{}", AtomName(name));
+            }
+            ("integer", [value]) | ("float", [value]) => {
+                let _ = write!(self.out, "{}", value);
+            }
+            ("char", [Term::Integer(c)]) => self.char(*c),
+            ("string", [value]) => self.out.push_str(&string(value)),
+            ("nil", []) => self.out.push_str("[]"),
+            ("cons", [_, _]) => self.list(expr, indent),
+            ("tuple", [values]) => {
+                self.out.push('{');
+                self.exprs(values.as_list().unwrap_or(&[]), indent, ", ");
+                self.out.push('}');
+            }
+            ("map", [fields]) => self.map_fields(fields, indent),
+            ("map", [map, fields]) => {
+                self.expr(map, indent, PRIMARY);
+                self.map_fields(fields, indent);
+            }
+            ("map_field_assoc", [key, value]) | ("map_field_exact", [key, value]) => {
+                self.expr(key, indent, 0);
+                self.out.push_str(match parts.0 {
+                    "map_field_assoc" => " => ",
+                    _ => " := ",
+                });
+                self.expr(value, indent, 0);
+            }
+            ("match", [pattern, value]) | ("maybe_match", [pattern, value]) => {
+                let op = match parts.0 {
+                    "match" => "=",
+                    _ => "?=",
+                };
+                self.binary_op(op, pattern, value, indent);
+            }
+            ("op", [Term::Atom(op), left, right]) => self.binary_op(op, left, right, indent),
+            ("op", [Term::Atom(op), operand]) => self.unary_op(op, operand, indent),
+            ("call", [function, args]) => {
+                match tagged(function) {
+                    Some(("remote", [_, module, function])) => {
+                        self.expr(module, indent, PRIMARY);
+                        self.out.push(':');
+                        self.expr(function, indent, PRIMARY);
+                    }
+                    Some(("atom", _)) | Some(("var", _)) => self.expr(function, indent, 0),
+                    _ => {
+                        self.out.push('(');
+                        self.expr(function, indent, 0);
+                        self.out.push(')');
+                    }
+                }
+                self.out.push('(');
+                self.exprs(args.as_list().unwrap_or(&[]), indent, ", ");
+                self.out.push(')');
+            }
+            ("remote", [module, function]) => {
+                self.expr(module, indent, PRIMARY);
+                self.out.push(':');
+                self.expr(function, indent, PRIMARY);
+            }
+            ("case", [value, clauses]) => {
+                self.out.push_str("case ");
+                self.expr(value, indent, 0);
+                self.out.push_str(" of");
+                self.clauses(clauses, indent + INDENT, ClauseKind::Case);
+                self.end(indent);
+            }
+            ("if", [clauses]) => {
+                self.out.push_str("if");
+                self.clauses(clauses, indent + INDENT, ClauseKind::If);
+                self.end(indent);
+            }
+            ("receive", [clauses]) => {
+                self.out.push_str("receive");
+                self.clauses(clauses, indent + INDENT, ClauseKind::Case);
+                self.end(indent);
+            }
+            ("receive", [clauses, timeout, body]) => {
+                self.out.push_str("receive");
+                self.clauses(clauses, indent + INDENT, ClauseKind::Case);
+                self.newline(indent);
+                self.out.push_str("after");
+                self.newline(indent + INDENT);
+                self.expr(timeout, indent + INDENT, 0);
+                self.body(body, indent + INDENT);
+                self.end(indent);
+            }
+            ("try", [body, clauses, catches, after]) => {
+                self.out.push_str("try");
+                self.block(body, indent + INDENT);
+                if clauses.as_list().is_some_and(|clauses| !clauses.is_empty()) {
+                    self.newline(indent);
+                    self.out.push_str("of");
+                    self.clauses(clauses, indent + INDENT, ClauseKind::Case);
+                }
+                if catches.as_list().is_some_and(|catches| !catches.is_empty()) {
+                    self.newline(indent);
+                    self.out.push_str("catch");
+                    self.clauses(catches, indent + INDENT, ClauseKind::Catch);
+                }
+                if after.as_list().is_some_and(|after| !after.is_empty()) {
+                    self.newline(indent);
+                    self.out.push_str("after");
+                    self.block(after, indent + INDENT);
+                }
+                self.end(indent);
+            }
+            ("maybe", [body]) => {
+                self.out.push_str("maybe");
+                self.block(body, indent + INDENT);
+                self.end(indent);
+            }
+            ("maybe", [body, otherwise]) => {
+                self.out.push_str("maybe");
+                self.block(body, indent + INDENT);
+                if let Some(("else", [_, clauses])) = tagged(otherwise) {
+                    self.newline(indent);
+                    self.out.push_str("else");
+                    self.clauses(clauses, indent + INDENT, ClauseKind::Case);
+                }
+                self.end(indent);
+            }
+            ("catch", [value]) => {
+                self.out.push_str("catch ");
+                self.expr(value, indent, 0);
+            }
+            ("block", [body]) => {
+                self.out.push_str("begin");
+                self.block(body, indent + INDENT);
+                self.end(indent);
+            }
+            ("fun", [function]) => self.fun(function, indent),
+            ("named_fun", [Term::Atom(name), clauses]) => {
+                self.out.push_str("fun");
+                self.clauses(clauses, indent + INDENT, ClauseKind::Fun(Some(name)));
+                self.end(indent);
+            }
+            ("lc", [value, qualifiers]) => {
+                self.out.push('[');
+                self.expr(value, indent, 0);
+                self.qualifiers(qualifiers, indent);
+                self.out.push(']');
+            }
+            ("bc", [value, qualifiers]) => {
+                self.out.push_str("<< ");
+                self.expr(value, indent, 0);
+                self.qualifiers(qualifiers, indent);
+                self.out.push_str(" >>");
+            }
+            ("mc", [value, qualifiers]) => {
+                self.out.push_str("#{");
+                self.expr(value, indent, 0);
+                self.qualifiers(qualifiers, indent);
+                self.out.push('}');
+            }
+            ("record", [Term::Atom(name), fields]) => self.record_fields(name, fields, indent),
+            ("record", [record, Term::Atom(name), fields]) => {
+                self.expr(record, indent, PRIMARY);
+                self.record_fields(name, fields, indent);
+            }
+            ("record_field", [record, Term::Atom(name), field]) => {
+                self.expr(record, indent, PRIMARY);
+                let _ = write!(self.out, "#{}.", AtomName(name));
+                self.expr(field, indent, 0);
+            }
+            ("record_index", [Term::Atom(name), field]) => {
+                let _ = write!(self.out, "#{}.", AtomName(name));
+                self.expr(field, indent, 0);
+            }
+            ("bin", [elements]) => self.binary(elements, indent),
+            _ => {
+                let _ = write!(self.out, "{}", expr);
+            }
+        }
+    }
+
+    fn end(&mut self, indent: usize) {
+        self.newline(indent);
+        self.out.push_str("end");
+    }
+
+    fn binary_op(&mut self, op: &str, left: &Term, right: &Term, indent: usize) {
+        let (precedence, associativity) = binary_operator(op);
+        let (left_min, right_min) = match associativity {
+            Associativity::Left => (precedence, precedence + 1),
+            Associativity::Right => (precedence + 1, precedence),
+            Associativity::None => (precedence + 1, precedence + 1),
+        };
+        self.expr(left, indent, left_min);
+        let _ = write!(self.out, " {} ", op);
+        self.expr(right, indent, right_min);
+    }
+
+    fn unary_op(&mut self, op: &str, operand: &Term, indent: usize) {
+        self.out.push_str(op);
+        if op.chars().all(char::is_alphabetic) {
+            self.out.push(' ');
+        }
+        // Avoid `--1` and `- -X` turning into other tokens
+        let signed = match tagged(operand) {
+            Some(("op", [_, _, _])) => true,
+            Some(("integer", [_, Term::Integer(value)])) => *value < 0,
+            Some(("integer", [_, Term::BigInt { negative, .. }])) => *negative,
+            Some(("float", [_, Term::Float(value)])) => value.is_sign_negative(),
+            _ => false,
+        };
+        match signed {
+            true => {
+                self.out.push('(');
+                self.expr(operand, indent, 0);
+                self.out.push(')');
+            }
+            false => self.expr(operand, indent, UNARY),
+        }
+    }
+
+    fn char(&mut self, c: i64) {
+        let escaped = match u8::try_from(c) {
+            Ok(b' ') => "\\s".to_string(),
+            Ok(b'\\') => "\\\\".to_string(),
+            Ok(b'\n') => "\\n".to_string(),
+            Ok(b'\t') => "\\t".to_string(),
+            Ok(c) if c.is_ascii_graphic() => (c as char).to_string(),
+            _ => format!("\\x{{{:X}}}", c),
+        };
+        self.out.push('$');
+        self.out.push_str(&escaped);
+    }
+
+    /// Writes `[H1, H2 | T]` for a chain of cons cells
+    fn list(&mut self, mut list: &Term, indent: usize) {
+        self.out.push('[');
+        let mut first = true;
+        loop {
+            match tagged(list) {
+                Some(("cons", [_, head, tail])) => {
+                    if !first {
+                        self.out.push_str(", ");
+                    }
+                    self.expr(head, indent, 0);
+                    first = false;
+                    list = tail;
+                }
+                Some(("nil", [_])) => break,
+                _ => {
+                    self.out.push_str(" | ");
+                    self.expr(list, indent, 0);
+                    break;
+                }
+            }
+        }
+        self.out.push(']');
+    }
+
+    fn map_fields(&mut self, fields: &Term, indent: usize) {
+        self.out.push_str("#{");
+        self.exprs(fields.as_list().unwrap_or(&[]), indent, ", ");
+        self.out.push('}');
+    }
+
+    fn record_fields(&mut self, name: &str, fields: &Term, indent: usize) {
+        let _ = write!(self.out, "#{}{{", AtomName(name));
+        for (i, field) in fields.as_list().unwrap_or(&[]).iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            if let Some(("record_field", [_, name, value])) = tagged(field) {
+                self.expr(name, indent, 0);
+                self.out.push_str(" = ");
+                self.expr(value, indent, 0);
+            }
+        }
+        self.out.push('}');
+    }
+
+    fn qualifiers(&mut self, qualifiers: &Term, indent: usize) {
+        self.out.push_str(" || ");
+        for (i, qualifier) in qualifiers.as_list().unwrap_or(&[]).iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            let arrow = match tagged(qualifier) {
+                Some(("generate", _)) | Some(("m_generate", _)) => "<-",
+                Some(("generate_strict", _)) | Some(("m_generate_strict", _)) => "<:-",
+                Some(("b_generate", _)) => "<=",
+                Some(("b_generate_strict", _)) => "<:=",
+                _ => {
+                    self.expr(qualifier, indent, 0);
+                    continue;
+                }
+            };
+            if let Some((_, [_, pattern, value])) = tagged(qualifier) {
+                self.expr(pattern, indent, 0);
+                let _ = write!(self.out, " {} ", arrow);
+                self.expr(value, indent, 0);
+            }
+        }
+    }
+
+    fn binary(&mut self, segments: &Term, indent: usize) {
+        self.out.push_str("<<");
+        for (i, segment) in segments.as_list().unwrap_or(&[]).iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            let (value, size, types) = match tagged(segment) {
+                Some(("bin_element", [_, value, size, types])) => (value, size, types),
+                _ => continue,
+            };
+            // Nested binaries would start with `<<<`
+            let nested = i == 0 && matches!(tagged(value), Some(("bin", _)));
+            if nested {
+                self.out.push(' ');
+            }
+            self.expr(value, indent, PRIMARY);
+            if !is_default(size) {
+                self.out.push(':');
+                self.expr(size, indent, PRIMARY);
+            }
+            if let Some(types) = types.as_list() {
+                for (i, ty) in types.iter().enumerate() {
+                    self.out.push(if i == 0 { '/' } else { '-' });
+                    match ty {
+                        Term::Tuple(unit) => match &unit[..] {
+                            [Term::Atom(name), value] => {
+                                let _ = write!(self.out, "{}:{}", name, value);
+                            }
+                            _ => {
+                                let _ = write!(self.out, "{}", ty);
+                            }
+                        },
+                        Term::Atom(name) => self.out.push_str(name),
+                        _ => {
+                            let _ = write!(self.out, "{}", ty);
+                        }
+                    }
+                }
+            }
+        }
+        self.out.push_str(">>");
+    }
+
+    /// Writes `fun f/1`, `fun m:f/1` or an anonymous function
+    fn fun(&mut self, function: &Term, indent: usize) {
+        match function {
+            Term::Tuple(parts) => match &parts[..] {
+                [Term::Atom(tag), Term::Atom(name), arity] if tag == "function" => {
+                    let _ = write!(self.out, "fun {}/{}", AtomName(name), arity);
+                }
+                [Term::Atom(tag), module, name, arity] if tag == "function" => {
+                    self.out.push_str("fun ");
+                    for (i, part) in [module, name, arity].iter().enumerate() {
+                        if i > 0 {
+                            self.out.push(if i == 1 { ':' } else { '/' });
+                        }
+                        match part {
+                            Term::Atom(name) => {
+                                let _ = write!(self.out, "{}", AtomName(name));
+                            }
+                            Term::Integer(arity) => {
+                                let _ = write!(self.out, "{}", arity);
+                            }
+                            part => self.expr(part, indent, PRIMARY),
+                        }
+                    }
+                }
+                [Term::Atom(tag), clauses] if tag == "clauses" => {
+                    self.out.push_str("fun");
+                    self.clauses(clauses, indent + INDENT, ClauseKind::Fun(None));
+                    self.end(indent);
+                }
+                _ => {
+                    let _ = write!(self.out, "{}", function);
+                }
+            },
+            _ => {
+                let _ = write!(self.out, "{}", function);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum ClauseKind<'a> {
+    /// Clauses with a single pattern, of `case`, `receive` and `maybe ... else`
+    Case,
+    /// Clauses of `try ... catch`, with `Class:Reason:Stacktrace` patterns
+    Catch,
+    /// Clauses of `if`, with only guards
+    If,
+    /// Clauses of anonymous functions, with the name of named funs
+    Fun(Option<&'a str>),
+}
+
+/// Renders an expression on its own, for use within attributes
+pub(crate) fn render(expr: &Term) -> String {
+    let mut printer = Printer { out: String::new() };
+    printer.expr(expr, 0, 0);
+    printer.out
+}
+
+fn precedence(expr: &Term) -> u32 {
+    match tagged(expr) {
+        Some(("match", _)) | Some(("maybe_match", _)) => 150,
+        Some(("op", [_, Term::Atom(op), _, _])) => binary_operator(op).0,
+        Some(("op", [_, _, _])) => UNARY,
+        Some(("catch", _)) => 0,
+        _ => PRIMARY,
+    }
+}
+
+/// Writes the value of a `string` node, which is a list of characters
+fn string(value: &Term) -> String {
+    match value {
+        Term::Nil => "\"\"".to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn is_default(term: &Term) -> bool {
+    matches!(term, Term::Atom(name) if name == "default")
+}
+
+/// Writes `[f/1, g/2]` from a list of `{Name, Arity}` tuples
+fn function_list(functions: &Term) -> Option<String> {
+    let functions: Vec<_> = typespec::function_list(functions)?
+        .iter()
+        .map(|(name, arity)| format!("{}/{}", AtomName(name), arity))
+        .collect();
+    Some(functions.join(", "))
+}
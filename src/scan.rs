@@ -0,0 +1,265 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::btree_map::Entry;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::hash_map::Entry;
+
+use crate::{
+    BeamFileError, BeamFileOptions, Container, DuplicateChunks, Id, Index, IndexEntry, Limits,
+    Result, Warning, KNOWN_CHUNKS,
+};
+
+/// Builds the chunk index without doing any I/O itself
+///
+/// The caller reads the ranges returned by `next_read` and passes their
+/// contents to `feed`, which lets blocking and async readers share the parser.
+pub(crate) struct Scanner<'a> {
+    options: &'a BeamFileOptions,
+    start: u64,
+    file_size: u64,
+    payload_end: u64,
+    state: State,
+    pub(crate) index: Index,
+    pub(crate) duplicates: Vec<(Id, IndexEntry)>,
+    pub(crate) warnings: Vec<Warning>,
+    pub(crate) truncated: bool,
+    pub(crate) container: Container,
+    total_size: u64,
+}
+
+enum State {
+    Header,
+    ChunkHeader(u64),
+    Padding {
+        id: Id,
+        data_end: u64,
+        padding_len: u64,
+        available: usize,
+    },
+    Done,
+}
+
+impl<'a> Scanner<'a> {
+    /// Scans a file of `file_size` bytes with the container starting at `start`
+    pub(crate) fn new(start: u64, file_size: u64, options: &'a BeamFileOptions) -> Self {
+        Scanner {
+            options,
+            start,
+            file_size,
+            payload_end: 0,
+            state: State::Header,
+            index: Index::default(),
+            duplicates: Vec::new(),
+            warnings: Vec::new(),
+            truncated: false,
+            container: Container::default(),
+            total_size: 0,
+        }
+    }
+
+    /// The position and length of the next range to read, `None` once done
+    pub(crate) fn next_read(&self) -> Option<(u64, usize)> {
+        match self.state {
+            State::Header => Some((self.start, 12)),
+            State::ChunkHeader(position) => {
+                Some((position, (self.payload_end - position).min(8) as usize))
+            }
+            State::Padding {
+                data_end,
+                available,
+                ..
+            } => Some((data_end, available)),
+            State::Done => None,
+        }
+    }
+
+    /// Processes the contents of the range returned by `next_read`
+    pub(crate) fn feed(&mut self, data: &[u8]) -> Result<()> {
+        match self.state {
+            State::Header => self.header(data),
+            State::ChunkHeader(position) => self.chunk_header(position, data),
+            State::Padding {
+                id,
+                data_end,
+                padding_len,
+                available,
+            } => {
+                if data.iter().any(|&byte| byte != 0) {
+                    self.warnings.push(Warning::NonZeroPadding {
+                        chunk: id,
+                        position: data_end,
+                    });
+                }
+                if available < padding_len as usize {
+                    self.warnings.push(Warning::UnpaddedFinalChunk(id));
+                }
+                self.advance(data_end + padding_len);
+                Ok(())
+            }
+            State::Done => Ok(()),
+        }
+    }
+
+    fn header(&mut self, data: &[u8]) -> Result<()> {
+        let options = self.options;
+        let magic_number = word(&data[0..4]);
+        if magic_number != *b"FOR1" {
+            return Err(BeamFileError::UnexpectedMagicNumber(magic_number.into()));
+        }
+        let payload_size = u32::from_be_bytes(word(&data[4..8])) as u64;
+        let type_id = word(&data[8..12]);
+        if type_id != *b"BEAM" {
+            return Err(BeamFileError::UnexpectedFormType(type_id.into()));
+        }
+
+        let file_size = self.file_size;
+        let declared_end = self.start + 8 + payload_size;
+        let missing_padding = declared_end.saturating_sub(file_size);
+        let mut payload_end = if options.ignore_payload_size
+            || (options.allow_unpadded_final_chunk && missing_padding > 0 && missing_padding < 4)
+        {
+            file_size
+        } else {
+            declared_end
+        };
+        self.truncated = payload_end > file_size;
+        if self.truncated {
+            let available = file_size.saturating_sub(self.start + 8);
+            if !options.recover_truncated {
+                return Err(BeamFileError::InvalidPayloadSize {
+                    declared: payload_size,
+                    available,
+                });
+            }
+            self.warnings.push(Warning::Truncated {
+                declared: payload_size,
+                available,
+            });
+            payload_end = file_size;
+        }
+        if file_size > payload_end {
+            if !options.allow_trailing_data {
+                return Err(BeamFileError::TrailingData(file_size - payload_end));
+            }
+            self.warnings
+                .push(Warning::TrailingData(file_size - payload_end));
+        }
+
+        self.payload_end = payload_end;
+        self.container = Container {
+            start: self.start,
+            declared_payload_size: payload_size,
+            payload_end,
+            file_size,
+        };
+        self.advance(self.start + 12);
+        Ok(())
+    }
+
+    fn chunk_header(&mut self, position: u64, data: &[u8]) -> Result<()> {
+        // Fewer bytes than a chunk header left at the end of the payload
+        if data.len() < 8 {
+            let mut chunk_id = [0; 4];
+            let id_len = data.len().min(4);
+            chunk_id[..id_len].copy_from_slice(&data[..id_len]);
+            return Err(BeamFileError::ChunkOutOfBounds {
+                chunk: chunk_id.into(),
+                position,
+                len: data.len() as u64,
+            });
+        }
+
+        let chunk_id = word(&data[0..4]);
+        let chunk_len = u32::from_be_bytes(word(&data[4..8])) as u64;
+        let payload_end = self.payload_end;
+
+        // Chunks are laid out back to back, so staying within the payload
+        // also guarantees they don't overlap.
+        if position + 8 + chunk_len > payload_end {
+            if self.truncated {
+                self.state = State::Done;
+                return Ok(());
+            }
+            return Err(BeamFileError::ChunkOutOfBounds {
+                chunk: chunk_id.into(),
+                position: position + 8,
+                len: chunk_len,
+            });
+        }
+
+        let id = chunk_id.into();
+        let entry = IndexEntry {
+            position: position + 8,
+            len: chunk_len,
+        };
+
+        let limits = &self.options.limits;
+        self.total_size += chunk_len;
+        Limits::check("Chunk size", chunk_len, limits.max_chunk_size)
+            .and_then(|_| Limits::check("Total chunk size", self.total_size, limits.max_total_size))
+            .map_err(|err| err.in_chunk(id, entry.position))?;
+
+        #[cfg(feature = "tracing")]
+        tracing::trace!(chunk = ?id, offset = entry.position, len = chunk_len, "found chunk");
+        if !KNOWN_CHUNKS.contains(&id) {
+            self.warnings.push(Warning::UnknownChunk {
+                chunk: id,
+                position: entry.position,
+            });
+        }
+
+        match self.index.entry(id) {
+            Entry::Vacant(vacant) => {
+                vacant.insert(entry);
+            }
+            Entry::Occupied(mut occupied) => {
+                match self.options.duplicate_chunks {
+                    DuplicateChunks::Error => return Err(BeamFileError::DuplicateChunk(id)),
+                    DuplicateChunks::FirstWins => self.duplicates.push((id, entry)),
+                    DuplicateChunks::LastWins => {
+                        self.duplicates.push((id, occupied.insert(entry)));
+                    }
+                }
+                self.warnings.push(Warning::DuplicateChunk(id));
+            }
+        }
+
+        let data_end = position + 8 + chunk_len;
+        let padding_len = 4 * chunk_len.div_ceil(4) - chunk_len;
+        let available = padding_len.min(payload_end - data_end) as usize;
+        if available > 0 {
+            self.state = State::Padding {
+                id,
+                data_end,
+                padding_len,
+                available,
+            };
+        } else {
+            if available < padding_len as usize {
+                self.warnings.push(Warning::UnpaddedFinalChunk(id));
+            }
+            self.advance(data_end + padding_len);
+        }
+        Ok(())
+    }
+
+    /// Moves on to the chunk at `position`, if there's one
+    fn advance(&mut self, position: u64) {
+        let incomplete = self.truncated && position + 8 > self.payload_end;
+        self.state = if position < self.payload_end && !incomplete {
+            State::ChunkHeader(position)
+        } else {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                chunks = self.index.len(),
+                bytes = self.total_size,
+                "indexed"
+            );
+            State::Done
+        };
+    }
+}
+
+fn word(bytes: &[u8]) -> [u8; 4] {
+    [bytes[0], bytes[1], bytes[2], bytes[3]]
+}
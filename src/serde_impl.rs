@@ -0,0 +1,109 @@
+use std::{convert::TryFrom, fmt, str};
+
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{ChunkHash, Id};
+
+/// Ids are serialized as strings, unless they aren't valid UTF-8
+///
+/// Binary formats, which can't tell strings from bytes when deserializing,
+/// always get the bytes.
+impl Serialize for Id {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match str::from_utf8(&self.0) {
+            Ok(id) if serializer.is_human_readable() => serializer.serialize_str(id),
+            _ => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(IdVisitor)
+        } else {
+            deserializer.deserialize_bytes(IdVisitor)
+        }
+    }
+}
+
+struct IdVisitor;
+
+impl<'de> Visitor<'de> for IdVisitor {
+    type Value = Id;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a 4-byte chunk id")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Id, E> {
+        self.visit_bytes(value.as_bytes())
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Id, E> {
+        <[u8; 4]>::try_from(value)
+            .map(Id)
+            .map_err(|_| E::invalid_length(value.len(), &self))
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Id, A::Error> {
+        let mut id = [0; 4];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+        }
+        Ok(Id(id))
+    }
+}
+
+/// Chunk hashes are serialized as hex strings to human-readable formats,
+/// which often can't represent 128-bit integers
+impl Serialize for ChunkHash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => serializer.collect_str(self),
+            false => serializer.serialize_u128(self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ChunkHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(ChunkHashVisitor)
+        } else {
+            deserializer.deserialize_u128(ChunkHashVisitor)
+        }
+    }
+}
+
+struct ChunkHashVisitor;
+
+impl<'de> Visitor<'de> for ChunkHashVisitor {
+    type Value = ChunkHash;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a 128-bit chunk hash")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<ChunkHash, E> {
+        match value.len() {
+            32 => u128::from_str_radix(value, 16)
+                .map(ChunkHash)
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self)),
+            len => Err(E::invalid_length(len, &self)),
+        }
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<ChunkHash, E> {
+        Ok(ChunkHash(value.into()))
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<ChunkHash, E> {
+        Ok(ChunkHash(value))
+    }
+}
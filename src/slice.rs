@@ -0,0 +1,83 @@
+use alloc::vec::Vec;
+
+use crate::{
+    in_file_order, scan::Scanner, BeamFileError, BeamFileOptions, Container, Id, Index, Result,
+    Warning,
+};
+
+/// A BEAM file indexed in memory, borrowing the chunk data
+///
+/// Unlike `BeamFile`, it's available without the `std` feature, for
+/// environments with only an allocator. Chunks are returned as raw bytes.
+#[derive(Clone, Debug)]
+pub struct BeamSlice<'a> {
+    data: &'a [u8],
+    index: Index,
+    warnings: Vec<Warning>,
+    truncated: bool,
+    container: Container,
+}
+
+impl<'a> BeamSlice<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        Self::parse(data, &BeamFileOptions::default())
+    }
+
+    pub(crate) fn parse(data: &'a [u8], options: &BeamFileOptions) -> Result<Self> {
+        let mut scanner = Scanner::new(0, data.len() as u64, options);
+        while let Some((position, len)) = scanner.next_read() {
+            let range = data
+                .get(position as usize..)
+                .and_then(|rest| rest.get(..len))
+                .ok_or(BeamFileError::UnexpectedEof(position))?;
+            scanner.feed(range)?;
+        }
+
+        Ok(BeamSlice {
+            data,
+            index: scanner.index,
+            warnings: scanner.warnings,
+            truncated: scanner.truncated,
+            container: scanner.container,
+        })
+    }
+
+    /// The data of a chunk
+    pub fn chunk(&self, id: Id) -> Option<&'a [u8]> {
+        let entry = self.index.get(&id)?;
+        let start = entry.position as usize;
+        Some(&self.data[start..start + entry.len as usize])
+    }
+
+    /// Chunks in the order they appear in the file
+    pub fn chunks(&self) -> impl Iterator<Item = (Id, &'a [u8])> + '_ {
+        let data = self.data;
+        in_file_order(&self.index)
+            .into_iter()
+            .map(move |(id, entry)| {
+                let start = entry.position as usize;
+                (id, &data[start..start + entry.len as usize])
+            })
+    }
+
+    /// Sizes of the container, see `BeamFile::container`
+    pub fn container(&self) -> Container {
+        self.container
+    }
+
+    /// The data after the payload, see `BeamFile::read_trailing`
+    pub fn trailing(&self) -> &'a [u8] {
+        let end = (self.container.payload_end as usize).min(self.data.len());
+        &self.data[end..]
+    }
+
+    /// Whether the file was truncated, see `BeamFile::is_truncated`
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Recoverable anomalies found while indexing
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+}
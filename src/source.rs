@@ -0,0 +1,151 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+};
+
+use crate::{BeamFile, BeamFileOptions, Result};
+
+/// Storage BEAM files can be read from by range
+///
+/// Every read names its range, so sources such as HTTP range requests or
+/// members of an archive only fetch the chunks that are actually read.
+pub trait ChunkSource {
+    /// The size of the whole source, in bytes
+    fn size(&mut self) -> io::Result<u64>;
+
+    /// Reads `len` bytes at `offset`
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>>;
+}
+
+impl ChunkSource for File {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; len];
+        self.seek(SeekFrom::Start(offset))?;
+        self.read_exact(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl ChunkSource for &[u8] {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.get(offset as usize..)
+            .and_then(|rest| rest.get(..len))
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+    }
+}
+
+impl ChunkSource for Vec<u8> {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+
+    fn read_at(&mut self, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+        self.as_slice().read_at(offset, len)
+    }
+}
+
+/// Readers that may hold the whole file in memory
+///
+/// Lets `BeamFile::read_raw_cow` borrow chunks instead of copying them.
+pub trait AsBytes {
+    /// The whole contents of the reader, if they're in memory
+    fn as_bytes(&self) -> Option<&[u8]>;
+}
+
+impl<T: AsRef<[u8]>> AsBytes for Cursor<T> {
+    fn as_bytes(&self) -> Option<&[u8]> {
+        Some(self.get_ref().as_ref())
+    }
+}
+
+impl AsBytes for File {
+    fn as_bytes(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+impl<R> AsBytes for BufReader<R> {
+    fn as_bytes(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+impl<S> AsBytes for SourceReader<S> {
+    fn as_bytes(&self) -> Option<&[u8]> {
+        None
+    }
+}
+
+/// Adapts a `ChunkSource` to `Read + Seek`, making one `read_at` per read
+///
+/// `BeamFile` reads each chunk with a single read, so a read of a chunk
+/// is a single request to the source.
+#[derive(Clone, Debug)]
+pub struct SourceReader<S> {
+    source: S,
+    size: u64,
+    position: u64,
+}
+
+impl<S: ChunkSource> SourceReader<S> {
+    pub fn new(mut source: S) -> io::Result<Self> {
+        let size = source.size()?;
+        Ok(SourceReader {
+            source,
+            size,
+            position: 0,
+        })
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.source
+    }
+
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: ChunkSource> Read for SourceReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self
+            .size
+            .saturating_sub(self.position)
+            .min(buf.len() as u64) as usize;
+        if len == 0 {
+            return Ok(0);
+        }
+        let data = self.source.read_at(self.position, len)?;
+        let len = data.len().min(len);
+        buf[..len].copy_from_slice(&data[..len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<S> Seek for SourceReader<S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.position = match pos {
+            SeekFrom::Start(position) => Some(position),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+        Ok(self.position)
+    }
+}
+
+impl<S: ChunkSource> BeamFile<SourceReader<S>> {
+    pub fn from_source(source: S) -> Result<Self> {
+        BeamFileOptions::default().from_source(source)
+    }
+}
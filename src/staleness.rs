@@ -0,0 +1,119 @@
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use crate::{optional, BeamFile, InternerMut, Result, Term};
+
+/// Hashes the contents of a source file, to compare with a `source_hash` attribute
+pub type SourceHasher = fn(&[u8]) -> Vec<u8>;
+
+/// Options for `BeamFile::source_status`
+#[derive(Clone, Debug, Default)]
+pub struct SourceCheck {
+    pub(crate) compiled_at: Option<SystemTime>,
+    pub(crate) hasher: Option<SourceHasher>,
+}
+
+impl SourceCheck {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// When the module was compiled, the modification time of its file by default
+    ///
+    /// Needed for files not opened from the file system.
+    pub fn compiled_at(&mut self, time: SystemTime) -> &mut Self {
+        self.compiled_at = Some(time);
+        self
+    }
+
+    /// Verifies the `source_hash` attribute of the module, if it has one
+    ///
+    /// The hash is computed over the contents of the source file and takes
+    /// precedence over modification times.
+    pub fn source_hash(&mut self, hasher: SourceHasher) -> &mut Self {
+        self.hasher = Some(hasher);
+        self
+    }
+}
+
+/// Whether a module is up to date with its source
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SourceStatus {
+    /// The source matches the recorded hash, or wasn't modified after compiling
+    UpToDate(PathBuf),
+    /// The source was modified after the module was compiled
+    Stale(PathBuf),
+    /// The source doesn't match the recorded hash
+    HashMismatch(PathBuf),
+    /// The source file doesn't exist
+    Missing(PathBuf),
+    /// No source is recorded in the `CInf` chunk, or the time of compiling isn't known
+    Unknown,
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Compares the source recorded in the `CInf` chunk with the file system
+    pub fn source_status(&mut self, options: &SourceCheck) -> Result<SourceStatus> {
+        let source = optional(self.compile_info())?
+            .map(|chunk| chunk.info)
+            .unwrap_or_default()
+            .into_iter()
+            .find(|(key, _)| key == "source")
+            .and_then(|(_, value)| match value {
+                Term::String(path) | Term::Binary(path) => String::from_utf8(path).ok(),
+                _ => None,
+            });
+        let source = match source {
+            Some(source) => PathBuf::from(source),
+            None => return Ok(SourceStatus::Unknown),
+        };
+        let modified = match fs::metadata(&source) {
+            Ok(metadata) => metadata.modified()?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Ok(SourceStatus::Missing(source))
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        if let Some(hasher) = options.hasher {
+            if let Some(expected) = self.source_hash()? {
+                return Ok(match hasher(&fs::read(&source)?) == expected {
+                    true => SourceStatus::UpToDate(source),
+                    false => SourceStatus::HashMismatch(source),
+                });
+            }
+        }
+
+        let compiled_at = match (options.compiled_at, self.path()) {
+            (Some(time), _) => time,
+            (None, Some(path)) => fs::metadata(path)?.modified()?,
+            (None, None) => return Ok(SourceStatus::Unknown),
+        };
+        Ok(match modified > compiled_at {
+            true => SourceStatus::Stale(source),
+            false => SourceStatus::UpToDate(source),
+        })
+    }
+
+    /// Value of the `-source_hash(<<...>>).` attribute
+    fn source_hash(&mut self) -> Result<Option<Vec<u8>>> {
+        let attributes =
+            optional(self.attributes())?.map_or_else(Vec::new, |chunk| chunk.attributes);
+        let hash = attributes
+            .into_iter()
+            .find(|(key, _)| key == "source_hash")
+            .and_then(|(_, value)| match value {
+                Term::List(mut elements, _) if elements.len() == 1 => match elements.remove(0) {
+                    Term::Binary(hash) | Term::String(hash) => Some(hash),
+                    _ => None,
+                },
+                Term::Binary(hash) | Term::String(hash) => Some(hash),
+                _ => None,
+            });
+        Ok(hash)
+    }
+}
@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{Read, Seek},
+};
+
+use crate::{
+    abstract_code::{spec_clauses, Spec},
+    term::AtomName,
+    BeamFile, BeamFileError, Id, InternerMut, Result,
+};
+
+/// Functions every module exports, which don't make sense as callbacks
+const GENERATED_FUNCTIONS: [(&str, u32); 3] = [
+    ("module_info", 0),
+    ("module_info", 1),
+    ("behaviour_info", 1),
+];
+
+/// A behaviour declaring a callback for each function exported by a module
+///
+/// Displays as the source of the behaviour module.
+#[derive(Clone, PartialEq, Debug)]
+pub struct BehaviourStub {
+    /// Name of the behaviour module, `<module>_behaviour` by default
+    pub module: String,
+    /// The `-callback` declarations, without the attribute syntax
+    pub callbacks: Vec<String>,
+    /// Callbacks the module declares optional itself
+    pub optional_callbacks: Vec<(String, u32)>,
+}
+
+impl fmt::Display for BehaviourStub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "-module({}).", AtomName(&self.module))?;
+        if !self.optional_callbacks.is_empty() {
+            let functions: Vec<_> = self
+                .optional_callbacks
+                .iter()
+                .map(|(name, arity)| format!("{}/{}", AtomName(name), arity))
+                .collect();
+            writeln!(f)?;
+            writeln!(f, "-optional_callbacks([{}]).", functions.join(", "))?;
+        }
+        for callback in &self.callbacks {
+            writeln!(f)?;
+            writeln!(f, "-callback {}.", callback)?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Generates a behaviour with a callback for each exported function
+    ///
+    /// Callbacks use the `-spec` of the function from the abstract code, with
+    /// local types qualified by the module name. Without debug info, or for
+    /// functions without a spec, all types are `term()`. Callbacks the module
+    /// declares itself are kept as well.
+    pub fn behaviour_stub(&mut self) -> Result<BehaviourStub> {
+        let atoms = self.owned_atoms()?;
+        let module = atoms.first().cloned().unwrap_or_default();
+        let exports = self.owned_exports(&atoms)?;
+        let code = self.abstract_code()?;
+
+        let specs = code
+            .iter()
+            .flat_map(|code| code.attributes("spec"))
+            .filter_map(spec_clauses)
+            .collect::<BTreeMap<_, _>>();
+        let declared = code.map(|code| code.callbacks()).unwrap_or_default();
+        let optional_callbacks = declared
+            .values()
+            .filter(|callback| callback.optional)
+            .map(|callback| (callback.name.clone(), callback.arity))
+            .collect();
+        let mut callbacks: BTreeMap<_, _> = declared
+            .into_iter()
+            .map(|(key, callback)| (key, callback.clauses))
+            .collect();
+
+        for export in exports {
+            if export.arity > 255 {
+                return Err(BeamFileError::MalformedChunk {
+                    chunk: Id::EXP_T,
+                    reason: "invalid arity",
+                });
+            }
+            let key = (export.function, export.arity);
+            if GENERATED_FUNCTIONS.contains(&(key.0.as_str(), key.1)) {
+                continue;
+            }
+            callbacks
+                .entry(key.clone())
+                .or_insert_with(|| specs.get(&key).cloned().unwrap_or_default());
+        }
+
+        let callbacks = callbacks
+            .into_iter()
+            .map(|((name, arity), clauses)| match clauses.is_empty() {
+                true => untyped(&name, arity),
+                false => Spec {
+                    name: &name,
+                    clauses: &clauses,
+                    module: Some(&module),
+                }
+                .to_string(),
+            })
+            .collect();
+
+        Ok(BehaviourStub {
+            module: format!("{}_behaviour", module),
+            callbacks,
+            optional_callbacks,
+        })
+    }
+}
+
+/// A callback with all types `term()`
+fn untyped(name: &str, arity: u32) -> String {
+    let args = vec!["term()"; arity as usize];
+    format!("{}({}) -> term()", AtomName(name), args.join(", "))
+}
@@ -0,0 +1,64 @@
+use std::{
+    fmt,
+    io::{Read, Seek},
+};
+
+use crate::{optional, BeamFile, Id, InternerMut, LocTChunk, Result, Term};
+
+/// Overview of a module, displayed as a report in the spirit of `beam_lib:info/1`
+#[derive(Clone, PartialEq, Debug)]
+pub struct Summary {
+    pub module: String,
+    /// Chunks in file order, as `(id, offset, len)`
+    pub chunks: Vec<(Id, u64, u64)>,
+    pub exports: usize,
+    pub imports: usize,
+    pub locals: usize,
+    /// Contents of the `CInf` chunk, empty if it's absent
+    pub compile_info: Vec<(String, Term)>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "module:  {}", self.module)?;
+        writeln!(f, "exports: {}", self.exports)?;
+        writeln!(f, "imports: {}", self.imports)?;
+        writeln!(f, "locals:  {}", self.locals)?;
+
+        writeln!(f)?;
+        writeln!(f, "{:<6} {:>10} {:>10}", "chunk", "offset", "size")?;
+        for (id, offset, size) in &self.chunks {
+            writeln!(f, "{:<6} {:>10} {:>10}", id, offset, size)?;
+        }
+
+        if !self.compile_info.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "compile info:")?;
+            for (key, value) in &self.compile_info {
+                writeln!(f, "  {}: {}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Summarizes the module, chunks other than the atoms are optional
+    pub fn summary(&mut self) -> Result<Summary> {
+        let atoms = self.owned_atoms()?;
+        let exports = self.owned_exports(&atoms)?.len();
+        let imports = self.owned_imports(&atoms)?.len();
+        let locals = optional(self.read_with_atoms::<LocTChunk<String>>(&atoms))?
+            .map_or(0, |chunk| chunk.locals.len());
+        let compile_info = optional(self.compile_info())?.map_or_else(Vec::new, |chunk| chunk.info);
+
+        Ok(Summary {
+            module: atoms.into_iter().next().unwrap_or_default(),
+            chunks: self.chunks().collect(),
+            exports,
+            imports,
+            locals,
+            compile_info,
+        })
+    }
+}
@@ -0,0 +1,591 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Write},
+    io::Read,
+};
+
+use byteorder::{BigEndian, ReadBytesExt};
+use flate2::read::ZlibDecoder;
+
+use crate::{chunk::cautious_capacity, BeamFileError, Id, Limits, Result};
+
+const VERSION: u8 = 131;
+const COMPRESSED: u8 = 80;
+
+/// Bound on the nesting of decoded terms
+///
+/// Dropping and comparing terms still recurses, so hostile input mustn't be
+/// able to build arbitrarily deep ones.
+const MAX_DEPTH: usize = 4096;
+
+/// An Erlang term in the external term format
+///
+/// Only terms that can appear in compiled modules are supported, so pids,
+/// ports, references and local funs fail to decode.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Term {
+    Integer(i64),
+    /// Integer not fitting in `i64`, with the magnitude in little-endian digits
+    BigInt {
+        negative: bool,
+        digits: Vec<u8>,
+    },
+    Float(f64),
+    Atom(String),
+    Tuple(Vec<Term>),
+    /// List with the tail, `Nil` for proper lists
+    List(Vec<Term>, Box<Term>),
+    Nil,
+    /// List of bytes, as produced for strings
+    String(Vec<u8>),
+    Binary(Vec<u8>),
+    /// Binary whose last byte only has `bits` significant bits
+    BitBinary {
+        data: Vec<u8>,
+        bits: u8,
+    },
+    Map(Vec<(Term, Term)>),
+    /// External function reference `fun Module:Function/Arity`
+    Export {
+        module: Box<Term>,
+        function: Box<Term>,
+        arity: Box<Term>,
+    },
+}
+
+impl Term {
+    /// Decodes a term prefixed with the version byte
+    pub fn decode<R: Read>(reader: R) -> Result<Self> {
+        Self::decode_limited(reader, u64::MAX)
+    }
+
+    /// Decodes a term prefixed with the version byte, decompressing at most
+    /// `max_size` bytes if it's compressed
+    pub fn decode_limited<R: Read>(mut reader: R, max_size: u64) -> Result<Self> {
+        if reader.read_u8()? != VERSION {
+            return Err(BeamFileError::InvalidTerm("unsupported version"));
+        }
+
+        let tag = reader.read_u8()?;
+        if tag != COMPRESSED {
+            return Self::decode_body(&mut [tag].chain(reader));
+        }
+
+        let size = reader.read_u32::<BigEndian>()? as u64;
+        Limits::check("Decompressed size", size, max_size)?;
+        let mut data = Vec::with_capacity(cautious_capacity::<u8>(size as usize));
+        let mut decoder = ZlibDecoder::new(reader);
+        (&mut decoder).take(size).read_to_end(&mut data)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            compressed = decoder.total_in(),
+            decompressed = size,
+            "decompressed"
+        );
+        if data.len() as u64 != size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        Self::decode_body(&mut &data[..])
+    }
+
+    /// Decodes a term without the version byte
+    pub fn decode_body<R: Read>(reader: &mut R) -> Result<Self> {
+        // Terms in abstract code can nest deeply, so containers are tracked
+        // on an explicit stack rather than by recursion.
+        let mut stack: Vec<Partial> = Vec::new();
+        loop {
+            let mut term = match decode_step(reader)? {
+                Step::Term(term) => term,
+                Step::Open(partial) => {
+                    if stack.len() >= MAX_DEPTH {
+                        return Err(BeamFileError::InvalidTerm("nesting too deep"));
+                    }
+                    stack.push(partial);
+                    continue;
+                }
+            };
+
+            loop {
+                match stack.last_mut() {
+                    None => return Ok(term),
+                    Some(partial) => {
+                        if !partial.push(term) {
+                            break;
+                        }
+                        term = stack.pop().unwrap().finish();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the name of an atom
+    pub fn as_atom(&self) -> Option<&str> {
+        match self {
+            Term::Atom(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a proper list
+    ///
+    /// Strings aren't stored as lists of terms, so only `into_list` returns
+    /// their elements.
+    pub fn as_list(&self) -> Option<&[Term]> {
+        match self {
+            Term::Nil => Some(&[]),
+            Term::List(elements, tail) if **tail == Term::Nil => Some(elements),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements of a proper list, including strings
+    pub fn into_list(self) -> Option<Vec<Term>> {
+        match self {
+            Term::Nil => Some(Vec::new()),
+            Term::List(elements, tail) if *tail == Term::Nil => Some(elements),
+            Term::String(bytes) => Some(bytes.iter().map(|&b| Term::Integer(b as i64)).collect()),
+            _ => None,
+        }
+    }
+}
+
+/// Result of decoding a single tag
+enum Step {
+    Term(Term),
+    /// Container whose elements follow
+    Open(Partial),
+}
+
+/// Container term with elements still to be decoded
+enum Partial {
+    Tuple(Vec<Term>, usize),
+    /// Elements, their count, and the tail once decoded
+    List(Vec<Term>, usize),
+    Map(Vec<(Term, Term)>, Option<Term>, usize),
+    Export(Vec<Term>),
+}
+
+impl Partial {
+    /// Adds the next element, returns `true` once the container is complete
+    fn push(&mut self, term: Term) -> bool {
+        match self {
+            Partial::Tuple(elements, len) => {
+                elements.push(term);
+                elements.len() == *len
+            }
+            // The tail is pushed after the elements
+            Partial::List(elements, len) => {
+                elements.push(term);
+                elements.len() == *len + 1
+            }
+            Partial::Map(pairs, key, len) => {
+                match key.take() {
+                    Some(key) => pairs.push((key, term)),
+                    None => *key = Some(term),
+                }
+                pairs.len() == *len
+            }
+            Partial::Export(parts) => {
+                parts.push(term);
+                parts.len() == 3
+            }
+        }
+    }
+
+    fn finish(self) -> Term {
+        match self {
+            Partial::Tuple(elements, _) => Term::Tuple(elements),
+            Partial::List(mut elements, _) => {
+                let tail = elements.pop().unwrap();
+                Term::List(elements, Box::new(tail))
+            }
+            Partial::Map(pairs, _, _) => Term::Map(pairs),
+            Partial::Export(mut parts) => {
+                let arity = parts.pop().unwrap();
+                let function = parts.pop().unwrap();
+                let module = parts.pop().unwrap();
+                Term::Export {
+                    module: Box::new(module),
+                    function: Box::new(function),
+                    arity: Box::new(arity),
+                }
+            }
+        }
+    }
+}
+
+fn decode_step<R: Read>(reader: &mut R) -> Result<Step> {
+    let tag = reader.read_u8()?;
+    let term = match tag {
+        97 => Term::Integer(reader.read_u8()? as i64),
+        98 => Term::Integer(reader.read_i32::<BigEndian>()? as i64),
+        70 => Term::Float(reader.read_f64::<BigEndian>()?),
+        99 => {
+            let text = read_bytes(reader, 31)?;
+            let text = String::from_utf8_lossy(&text);
+            let value = text.trim_end_matches('\0').trim().parse();
+            Term::Float(value.map_err(|_| BeamFileError::InvalidTerm("invalid float"))?)
+        }
+        100 => {
+            let len = reader.read_u16::<BigEndian>()? as usize;
+            Term::Atom(read_latin1_atom(reader, len)?)
+        }
+        115 => {
+            let len = reader.read_u8()? as usize;
+            Term::Atom(read_latin1_atom(reader, len)?)
+        }
+        118 => {
+            let len = reader.read_u16::<BigEndian>()? as usize;
+            Term::Atom(read_atom(reader, len)?)
+        }
+        119 => {
+            let len = reader.read_u8()? as usize;
+            Term::Atom(read_atom(reader, len)?)
+        }
+        104 => {
+            let arity = reader.read_u8()? as usize;
+            return Ok(open_tuple(arity));
+        }
+        105 => {
+            let arity = reader.read_u32::<BigEndian>()? as usize;
+            return Ok(open_tuple(arity));
+        }
+        106 => Term::Nil,
+        107 => {
+            let len = reader.read_u16::<BigEndian>()? as usize;
+            Term::String(read_bytes(reader, len)?)
+        }
+        108 => {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            let elements = Vec::with_capacity(cautious_capacity::<Term>(len.saturating_add(1)));
+            return Ok(Step::Open(Partial::List(elements, len)));
+        }
+        109 => {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            Term::Binary(read_bytes(reader, len)?)
+        }
+        77 => {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            let bits = reader.read_u8()?;
+            Term::BitBinary {
+                data: read_bytes(reader, len)?,
+                bits,
+            }
+        }
+        110 => {
+            let len = reader.read_u8()? as usize;
+            decode_big(reader, len)?
+        }
+        111 => {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            decode_big(reader, len)?
+        }
+        116 => {
+            let len = reader.read_u32::<BigEndian>()? as usize;
+            if len == 0 {
+                Term::Map(Vec::new())
+            } else {
+                let pairs = Vec::with_capacity(cautious_capacity::<(Term, Term)>(len));
+                return Ok(Step::Open(Partial::Map(pairs, None, len)));
+            }
+        }
+        113 => return Ok(Step::Open(Partial::Export(Vec::with_capacity(3)))),
+        _ => return Err(BeamFileError::InvalidTerm("unsupported tag")),
+    };
+    Ok(Step::Term(term))
+}
+
+fn open_tuple(arity: usize) -> Step {
+    if arity == 0 {
+        Step::Term(Term::Tuple(Vec::new()))
+    } else {
+        Step::Open(Partial::Tuple(
+            Vec::with_capacity(cautious_capacity::<Term>(arity)),
+            arity,
+        ))
+    }
+}
+
+fn read_bytes<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>> {
+    let mut data = Vec::with_capacity(cautious_capacity::<u8>(len));
+    reader.take(len as u64).read_to_end(&mut data)?;
+    if data.len() != len {
+        return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+    }
+    Ok(data)
+}
+
+fn read_atom<R: Read>(reader: &mut R, len: usize) -> Result<String> {
+    String::from_utf8(read_bytes(reader, len)?)
+        .map_err(|err| BeamFileError::InvalidAtom(err.utf8_error()))
+}
+
+fn read_latin1_atom<R: Read>(reader: &mut R, len: usize) -> Result<String> {
+    Ok(read_bytes(reader, len)?
+        .into_iter()
+        .map(char::from)
+        .collect())
+}
+
+fn decode_big<R: Read>(reader: &mut R, len: usize) -> Result<Term> {
+    let negative = reader.read_u8()? != 0;
+    let mut digits = read_bytes(reader, len)?;
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+
+    if digits.len() <= 8 {
+        let magnitude = digits
+            .iter()
+            .rev()
+            .fold(0u64, |acc, &digit| (acc << 8) | digit as u64);
+        let value = if negative {
+            0i64.checked_sub_unsigned(magnitude)
+        } else {
+            i64::try_from(magnitude).ok()
+        };
+        if let Some(value) = value {
+            return Ok(Term::Integer(value));
+        }
+    }
+    Ok(Term::BigInt { negative, digits })
+}
+
+/// Key-value pairs decoded from a proplist term, in order
+fn decode_proplist<R: Read>(reader: R) -> Result<Vec<(String, Term)>> {
+    let elements = Term::decode(reader)?
+        .into_list()
+        .ok_or(BeamFileError::InvalidTerm("expected a list"))?;
+
+    elements
+        .into_iter()
+        .map(|element| match element {
+            Term::Tuple(mut pair) if pair.len() == 2 => {
+                let value = pair.pop().unwrap();
+                match pair.pop().unwrap() {
+                    Term::Atom(key) => Ok((key, value)),
+                    _ => Err(BeamFileError::InvalidTerm("expected an atom key")),
+                }
+            }
+            _ => Err(BeamFileError::InvalidTerm("expected a 2-tuple")),
+        })
+        .collect()
+}
+
+/// Module attributes, such as `vsn` or `behaviour`
+///
+/// Attributes appearing more than once are kept as separate entries.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttrChunk {
+    pub attributes: Vec<(String, Term)>,
+}
+
+impl AttrChunk {
+    pub const ID: Id = Id::ATTR;
+
+    pub fn decode<R: Read>(reader: R) -> Result<Self> {
+        Ok(AttrChunk {
+            attributes: decode_proplist(reader)?,
+        })
+    }
+}
+
+/// Compilation information, such as the compiler options and version
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CInfChunk {
+    pub info: Vec<(String, Term)>,
+}
+
+impl CInfChunk {
+    pub const ID: Id = Id::C_INF;
+
+    pub fn decode<R: Read>(reader: R) -> Result<Self> {
+        Ok(CInfChunk {
+            info: decode_proplist(reader)?,
+        })
+    }
+}
+
+const RESERVED_WORDS: [&str; 29] = [
+    "after", "and", "andalso", "band", "begin", "bnot", "bor", "bsl", "bsr", "bxor", "case",
+    "catch", "cond", "div", "else", "end", "fun", "if", "let", "maybe", "not", "of", "or",
+    "orelse", "receive", "rem", "try", "when", "xor",
+];
+
+/// Writes an atom, quoting it when required
+pub(crate) fn write_atom(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    let bare = name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '@')
+        && !RESERVED_WORDS.contains(&name);
+    if bare {
+        return f.write_str(name);
+    }
+
+    f.write_char('\'')?;
+    for c in name.chars() {
+        match c {
+            '\'' => f.write_str("\\'")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\x{{{:x}}}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('\'')
+}
+
+/// Displays an atom name, quoting it when required
+pub(crate) struct AtomName<'a>(pub(crate) &'a str);
+
+impl fmt::Display for AtomName<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_atom(f, self.0)
+    }
+}
+
+fn is_printable(bytes: &[u8]) -> bool {
+    bytes
+        .iter()
+        .all(|&b| (0x20..0x7f).contains(&b) || b == b'\n' || b == b'\t')
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    f.write_char('"')?;
+    for &b in bytes {
+        match b {
+            b'"' => f.write_str("\\\"")?,
+            b'\\' => f.write_str("\\\\")?,
+            b'\n' => f.write_str("\\n")?,
+            b'\t' => f.write_str("\\t")?,
+            b => f.write_char(b as char)?,
+        }
+    }
+    f.write_char('"')
+}
+
+fn write_separated<'a>(
+    f: &mut fmt::Formatter<'_>,
+    terms: impl IntoIterator<Item = &'a Term>,
+) -> fmt::Result {
+    for (i, term) in terms.into_iter().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        write!(f, "{}", term)?;
+    }
+    Ok(())
+}
+
+/// Decimal digits of a little-endian magnitude
+fn decimal(digits: &[u8]) -> String {
+    let mut magnitude: Vec<u32> = digits.iter().rev().map(|&d| d as u32).collect();
+    let mut decimal = Vec::new();
+    while magnitude.iter().any(|&d| d != 0) {
+        let mut remainder = 0;
+        for digit in magnitude.iter_mut() {
+            let value = remainder * 256 + *digit;
+            *digit = value / 10;
+            remainder = value % 10;
+        }
+        decimal.push(b'0' + remainder as u8);
+    }
+    if decimal.is_empty() {
+        decimal.push(b'0');
+    }
+    decimal.reverse();
+    String::from_utf8(decimal).unwrap()
+}
+
+/// Formats the term in Erlang syntax
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Integer(value) => write!(f, "{}", value),
+            Term::BigInt { negative, digits } => {
+                write!(f, "{}{}", if *negative { "-" } else { "" }, decimal(digits))
+            }
+            Term::Float(value) => {
+                let text = format!("{:?}", value);
+                match text.find('e') {
+                    Some(e) if !text[..e].contains('.') => {
+                        write!(f, "{}.0{}", &text[..e], &text[e..])
+                    }
+                    _ => f.write_str(&text),
+                }
+            }
+            Term::Atom(name) => write_atom(f, name),
+            Term::Tuple(elements) => {
+                f.write_char('{')?;
+                write_separated(f, elements)?;
+                f.write_char('}')
+            }
+            Term::List(elements, tail) => {
+                f.write_char('[')?;
+                write_separated(f, elements)?;
+                if **tail != Term::Nil {
+                    write!(f, "|{}", tail)?;
+                }
+                f.write_char(']')
+            }
+            Term::Nil => f.write_str("[]"),
+            Term::String(bytes) if is_printable(bytes) => write_string(f, bytes),
+            Term::String(bytes) => {
+                f.write_char('[')?;
+                for (i, b) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", b)?;
+                }
+                f.write_char(']')
+            }
+            Term::Binary(bytes) if !bytes.is_empty() && is_printable(bytes) => {
+                f.write_str("<<")?;
+                write_string(f, bytes)?;
+                f.write_str(">>")
+            }
+            Term::Binary(bytes) => {
+                f.write_str("<<")?;
+                for (i, b) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", b)?;
+                }
+                f.write_str(">>")
+            }
+            Term::BitBinary { data, bits } => {
+                f.write_str("<<")?;
+                if let Some((last, rest)) = data.split_last() {
+                    for b in rest {
+                        write!(f, "{},", b)?;
+                    }
+                    write!(f, "{}:{}", last >> (8 - (*bits).clamp(1, 8)), bits)?;
+                }
+                f.write_str(">>")
+            }
+            Term::Map(pairs) => {
+                f.write_str("#{")?;
+                for (i, (key, value)) in pairs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{} => {}", key, value)?;
+                }
+                f.write_char('}')
+            }
+            Term::Export {
+                module,
+                function,
+                arity,
+            } => write!(f, "fun {}:{}/{}", module, function, arity),
+        }
+    }
+}
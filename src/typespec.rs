@@ -0,0 +1,318 @@
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    fmt,
+    io::{Read, Seek},
+};
+
+use crate::{
+    abstract_code::{spec_clauses, tagged, Spec, Type},
+    pretty::render,
+    term::AtomName,
+    AbstractCode, BeamFile, InternerMut, Result, Term,
+};
+
+/// A `-spec` declaration, displayed in Erlang syntax
+#[derive(Clone, PartialEq, Debug)]
+pub struct FunctionSpec {
+    pub name: String,
+    pub arity: u32,
+    /// Clauses in the abstract format, `{type, Anno, fun | bounded_fun, ...}`
+    pub clauses: Vec<Term>,
+}
+
+impl fmt::Display for FunctionSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spec = Spec {
+            name: &self.name,
+            clauses: &self.clauses,
+            module: None,
+        };
+        write!(f, "-spec {}.", spec)
+    }
+}
+
+/// A `-callback` declaration of a behaviour, displayed in Erlang syntax
+#[derive(Clone, PartialEq, Debug)]
+pub struct CallbackSpec {
+    pub name: String,
+    pub arity: u32,
+    /// Clauses in the abstract format, `{type, Anno, fun | bounded_fun, ...}`
+    pub clauses: Vec<Term>,
+    /// Whether the callback is listed in `-optional_callbacks`
+    pub optional: bool,
+}
+
+impl fmt::Display for CallbackSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let spec = Spec {
+            name: &self.name,
+            clauses: &self.clauses,
+            module: None,
+        };
+        write!(f, "-callback {}.", spec)
+    }
+}
+
+/// A `-type` or `-opaque` declaration, displayed in Erlang syntax
+#[derive(Clone, PartialEq, Debug)]
+pub struct TypeDecl {
+    pub name: String,
+    /// Names of the type variables
+    pub params: Vec<String>,
+    /// The type in the abstract format
+    pub definition: Term,
+    pub opaque: bool,
+}
+
+impl TypeDecl {
+    pub fn arity(&self) -> u32 {
+        self.params.len() as u32
+    }
+}
+
+impl fmt::Display for TypeDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.opaque {
+            true => "opaque",
+            false => "type",
+        };
+        let definition = Type {
+            ty: &self.definition,
+            module: None,
+        };
+        write!(
+            f,
+            "-{} {}({}) :: {}.",
+            kind,
+            AtomName(&self.name),
+            self.params.join(", "),
+            definition
+        )
+    }
+}
+
+/// A `-record` declaration, displayed in Erlang syntax
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordDef {
+    pub name: String,
+    /// Fields in the order of the declaration, which is the order of the tuple elements
+    pub fields: Vec<RecordField>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecordField {
+    pub name: String,
+    /// Default value as an expression in the abstract format
+    pub default: Option<Term>,
+    /// Declared type in the abstract format
+    pub ty: Option<Term>,
+}
+
+impl RecordField {
+    /// The default value as Erlang source, such as `[]` or `maps:new()`
+    pub fn default_source(&self) -> Option<String> {
+        self.default.as_ref().map(render)
+    }
+}
+
+impl fmt::Display for RecordField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", AtomName(&self.name))?;
+        if let Some(default) = self.default_source() {
+            write!(f, " = {}", default)?;
+        }
+        if let Some(ty) = &self.ty {
+            write!(f, " :: {}", Type { ty, module: None })?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RecordDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "-record({}, {{", AtomName(&self.name))?;
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", field)?;
+        }
+        f.write_str("}).")
+    }
+}
+
+/// Specs and types declared by a module, keyed by name and arity
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Typespecs {
+    pub specs: BTreeMap<(String, u32), FunctionSpec>,
+    pub types: BTreeMap<(String, u32), TypeDecl>,
+}
+
+impl AbstractCode {
+    /// Collects the `-spec`, `-type` and `-opaque` declarations
+    ///
+    /// Specs of other modules' functions, `-spec m:f(...)`, are keyed by the
+    /// function alone, and malformed declarations are skipped.
+    pub fn typespecs(&self) -> Typespecs {
+        let specs = self
+            .attributes("spec")
+            .filter_map(spec_clauses)
+            .map(|((name, arity), clauses)| {
+                let spec = FunctionSpec {
+                    name: name.clone(),
+                    arity,
+                    clauses,
+                };
+                ((name, arity), spec)
+            })
+            .collect();
+        let types = ["type", "opaque"]
+            .iter()
+            .flat_map(|kind| self.attributes(kind).map(move |value| (kind, value)))
+            .filter_map(|(kind, value)| {
+                let ty = type_decl(value, *kind == "opaque")?;
+                Some(((ty.name.clone(), ty.arity()), ty))
+            })
+            .collect();
+        Typespecs { specs, types }
+    }
+
+    /// Collects the `-callback` declarations of a behaviour, keyed by name and arity
+    ///
+    /// Functions listed in `-optional_callbacks` without a `-callback` are skipped.
+    pub fn callbacks(&self) -> BTreeMap<(String, u32), CallbackSpec> {
+        let optional: Vec<_> = self
+            .attributes("optional_callbacks")
+            .filter_map(function_list)
+            .flatten()
+            .collect();
+        self.attributes("callback")
+            .filter_map(spec_clauses)
+            .map(|((name, arity), clauses)| {
+                let key = (name, arity);
+                let callback = CallbackSpec {
+                    name: key.0.clone(),
+                    arity,
+                    clauses,
+                    optional: optional.contains(&key),
+                };
+                (key, callback)
+            })
+            .collect()
+    }
+
+    /// Collects the `-record` declarations, keyed by name
+    pub fn records(&self) -> BTreeMap<String, RecordDef> {
+        self.attributes("record")
+            .filter_map(record_def)
+            .map(|record| (record.name.clone(), record))
+            .collect()
+    }
+}
+
+/// Reads a list of `{Name, Arity}` tuples, as in `-export` or `-optional_callbacks`
+pub(crate) fn function_list(value: &Term) -> Option<Vec<(String, u32)>> {
+    let functions = match value {
+        Term::Nil => &[][..],
+        Term::List(functions, _) => functions,
+        _ => return None,
+    };
+    functions
+        .iter()
+        .map(|function| match function {
+            Term::Tuple(parts) => match &parts[..] {
+                [Term::Atom(name), Term::Integer(arity)] => {
+                    Some((name.clone(), u32::try_from(*arity).ok()?))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Splits the value of a `-record` attribute, `{Name, Fields}`
+pub(crate) fn record_def(value: &Term) -> Option<RecordDef> {
+    let (name, fields) = match value {
+        Term::Tuple(parts) => match &parts[..] {
+            [Term::Atom(name), fields] => (name, fields.as_list()?),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let fields = fields
+        .iter()
+        .map(|field| {
+            let (field, ty) = match tagged(field) {
+                Some(("typed_record_field", [field, ty])) => (field, Some(ty.clone())),
+                _ => (field, None),
+            };
+            let (name, default) = match tagged(field)? {
+                ("record_field", [_, name]) => (name, None),
+                ("record_field", [_, name, default]) => (name, Some(default.clone())),
+                _ => return None,
+            };
+            match tagged(name)? {
+                ("atom", [_, Term::Atom(name)]) => Some(RecordField {
+                    name: name.clone(),
+                    default,
+                    ty,
+                }),
+                _ => None,
+            }
+        })
+        .collect::<Option<_>>()?;
+    Some(RecordDef {
+        name: name.clone(),
+        fields,
+    })
+}
+
+/// Splits the value of a `-type` attribute, `{Name, Type, Vars}`
+pub(crate) fn type_decl(value: &Term, opaque: bool) -> Option<TypeDecl> {
+    let (name, definition, params) = match value {
+        Term::Tuple(elements) => match &elements[..] {
+            [Term::Atom(name), definition, params] => (name, definition, params),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let params = params
+        .as_list()?
+        .iter()
+        .map(|param| match tagged(param) {
+            Some(("var", [_, Term::Atom(name)])) => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    Some(TypeDecl {
+        name: name.clone(),
+        params,
+        definition: definition.clone(),
+        opaque,
+    })
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Collects the specs and types from the abstract code, see `AbstractCode::typespecs`
+    ///
+    /// Returns `None` if the module has no abstract code.
+    pub fn typespecs(&mut self) -> Result<Option<Typespecs>> {
+        Ok(self.abstract_code()?.map(|code| code.typespecs()))
+    }
+
+    /// Collects the callbacks from the abstract code, see `AbstractCode::callbacks`
+    ///
+    /// Returns `None` if the module has no abstract code.
+    pub fn callbacks(&mut self) -> Result<Option<BTreeMap<(String, u32), CallbackSpec>>> {
+        Ok(self.abstract_code()?.map(|code| code.callbacks()))
+    }
+
+    /// Collects the records from the abstract code, see `AbstractCode::records`
+    ///
+    /// Returns `None` if the module has no abstract code.
+    pub fn records(&mut self) -> Result<Option<BTreeMap<String, RecordDef>>> {
+        Ok(self.abstract_code()?.map(|code| code.records()))
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    io::{self, Cursor, Read, Seek},
+    str,
+};
+
+use thiserror::Error;
+
+use crate::{
+    BeamFile, BeamFileError, Chunk, CodeHeader, ExpTChunk, Export, Id, ImpTChunk, InternerMut,
+    LocTChunk, RawAtoms, Result,
+};
+
+/// A structural problem found by `BeamFile::verify`
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerifyProblem {
+    #[error("Required chunk {0:?} is missing")]
+    MissingChunk(Id),
+
+    #[error("Atom table declares {declared} atoms, but contains {actual}")]
+    AtomCountMismatch { declared: usize, actual: usize },
+
+    #[error("Atom {0} is not valid UTF-8")]
+    InvalidAtomName(usize),
+
+    #[error("Invalid atom index {index} in chunk {chunk:?}")]
+    InvalidAtomIndex { chunk: Id, index: u32 },
+
+    #[error("Label {label} in chunk {chunk:?} is outside of the {label_count} labels of the code")]
+    LabelOutOfRange {
+        chunk: Id,
+        label: u32,
+        label_count: u32,
+    },
+
+    #[error("Malformed chunk {chunk:?}: {reason}")]
+    MalformedChunk { chunk: Id, reason: String },
+}
+
+/// The result of `BeamFile::verify`
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerifyReport {
+    pub problems: Vec<VerifyProblem>,
+}
+
+impl VerifyReport {
+    /// Returns `true` if no problems were found
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+const REQUIRED_CHUNKS: [Id; 4] = [
+    CodeHeader::ID,
+    Id::STR_T,
+    ImpTChunk::<()>::ID,
+    ExpTChunk::<()>::ID,
+];
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Checks the structural integrity of the file, like `beam_lib` does
+    ///
+    /// Verifies that the required chunks are present, that the atom table is
+    /// consistent, and that the import, export and local function tables
+    /// reference valid atoms and labels. Problems with the file contents are
+    /// collected into the report, only failures of the reader are errors.
+    pub fn verify(&mut self) -> Result<VerifyReport> {
+        let mut problems = Vec::new();
+
+        let atom_id = self.atom_chunk_id();
+        for id in [atom_id].iter().chain(&REQUIRED_CHUNKS) {
+            if !self.index.contains_key(id) {
+                problems.push(VerifyProblem::MissingChunk(*id));
+            }
+        }
+
+        let atom_count = match self.atoms_raw() {
+            Ok(raw) => {
+                verify_atoms(&raw, atom_id == Id::AT_U8, &mut problems);
+                Some(raw.len())
+            }
+            Err(BeamFileError::MissingChunk(_)) => None,
+            Err(err) => problem(atom_id, err, &mut problems)?,
+        };
+
+        let label_count = match self.code_header() {
+            Ok(header) => Some(header.label_count),
+            Err(BeamFileError::MissingChunk(_)) => None,
+            Err(err) => problem(CodeHeader::ID, err, &mut problems)?,
+        };
+
+        if let Some(atom_count) = atom_count {
+            let atoms = vec![(); atom_count];
+
+            self.verify_chunk::<ImpTChunk<()>>(&atoms, &mut problems)?;
+
+            if let Some(chunk) = self.verify_chunk::<ExpTChunk<()>>(&atoms, &mut problems)? {
+                verify_labels(
+                    ExpTChunk::<()>::ID,
+                    &chunk.exports,
+                    label_count,
+                    &mut problems,
+                );
+            }
+
+            if let Some(chunk) = self.verify_chunk::<LocTChunk<()>>(&atoms, &mut problems)? {
+                verify_labels(
+                    LocTChunk::<()>::ID,
+                    &chunk.locals,
+                    label_count,
+                    &mut problems,
+                );
+            }
+        }
+
+        Ok(VerifyReport { problems })
+    }
+
+    fn verify_chunk<C: Chunk<Atom = ()>>(
+        &mut self,
+        atoms: &[()],
+        problems: &mut Vec<VerifyProblem>,
+    ) -> Result<Option<C>> {
+        let raw = match self.read_raw(C::ID) {
+            Ok(raw) => raw,
+            Err(BeamFileError::MissingChunk(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        match C::decode(Cursor::new(raw), atoms) {
+            Ok(chunk) => Ok(Some(chunk)),
+            Err(err) => problem(C::ID, err, problems),
+        }
+    }
+}
+
+/// Records a decoding error as a problem, passing through reader failures
+fn problem<T>(
+    chunk: Id,
+    err: BeamFileError,
+    problems: &mut Vec<VerifyProblem>,
+) -> Result<Option<T>> {
+    let problem = match err.root() {
+        BeamFileError::InvalidAtomIndex { chunk, index } => VerifyProblem::InvalidAtomIndex {
+            chunk: *chunk,
+            index: *index,
+        },
+        BeamFileError::Io(io) if io.kind() == io::ErrorKind::UnexpectedEof => {
+            VerifyProblem::MalformedChunk {
+                chunk,
+                reason: "truncated".to_string(),
+            }
+        }
+        BeamFileError::Io(_) => return Err(err),
+        root => VerifyProblem::MalformedChunk {
+            chunk,
+            reason: root.to_string(),
+        },
+    };
+    problems.push(problem);
+    Ok(None)
+}
+
+fn verify_atoms(raw: &RawAtoms, utf8: bool, problems: &mut Vec<VerifyProblem>) {
+    let mut iter = raw.iter_bytes();
+    let mut actual = 0;
+
+    for (index, name) in iter.by_ref().enumerate() {
+        match name {
+            Ok(name) => {
+                if utf8 && str::from_utf8(name).is_err() {
+                    problems.push(VerifyProblem::InvalidAtomName(index));
+                }
+                actual += 1;
+            }
+            Err(_) => break,
+        }
+    }
+
+    // Count any well-formed entries past the declared number of atoms
+    let mut rest = iter.remainder();
+    while let Some((&len, tail)) = rest.split_first() {
+        if tail.len() < len as usize {
+            break;
+        }
+        rest = &tail[len as usize..];
+        actual += 1;
+    }
+
+    if actual != raw.len() || !rest.is_empty() {
+        problems.push(VerifyProblem::AtomCountMismatch {
+            declared: raw.len(),
+            actual,
+        });
+    }
+}
+
+fn verify_labels(
+    chunk: Id,
+    functions: &[Export<()>],
+    label_count: Option<u32>,
+    problems: &mut Vec<VerifyProblem>,
+) {
+    let label_count = match label_count {
+        Some(label_count) => label_count,
+        None => return,
+    };
+
+    for function in functions {
+        if function.label == 0 || function.label >= label_count {
+            problems.push(VerifyProblem::LabelOutOfRange {
+                chunk,
+                label: function.label,
+                label_count,
+            });
+        }
+    }
+}
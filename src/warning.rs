@@ -0,0 +1,67 @@
+use core::fmt;
+
+use crate::Id;
+
+/// A recoverable anomaly found in a BEAM file
+///
+/// Unlike errors, warnings don't prevent using the file. They're collected in
+/// `BeamFile::warnings` for tools that want to report them, such as linters.
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Warning {
+    /// Chunk id not produced by any known version of the compiler
+    UnknownChunk { chunk: Id, position: u64 },
+    /// Chunk id appeared more than once
+    ///
+    /// See `BeamFileOptions::duplicate_chunks`.
+    DuplicateChunk(Id),
+    /// Padding after the chunk data contains non-zero bytes
+    NonZeroPadding { chunk: Id, position: u64 },
+    /// Final chunk isn't padded to a multiple of 4 bytes
+    UnpaddedFinalChunk(Id),
+    /// Data following the end of the payload, in bytes
+    TrailingData(u64),
+    /// File ends before the declared end of the payload
+    ///
+    /// Only chunks fully present are indexed.
+    /// See `BeamFileOptions::recover_truncated`.
+    Truncated { declared: u64, available: u64 },
+    /// Atom name wasn't valid UTF-8 and was decoded lossily
+    ///
+    /// `index` is the position in `BeamFile::atom_index`. See `BeamFileOptions::invalid_atoms`.
+    LossyAtom { index: usize, offset: u64 },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::UnknownChunk { chunk, position } => {
+                write!(f, "unknown chunk {:?} at offset {}", chunk, position)
+            }
+            Warning::DuplicateChunk(chunk) => write!(f, "duplicate chunk {:?}", chunk),
+            Warning::NonZeroPadding { chunk, position } => write!(
+                f,
+                "non-zero padding after chunk {:?} at offset {}",
+                chunk, position
+            ),
+            Warning::UnpaddedFinalChunk(chunk) => {
+                write!(f, "final chunk {:?} is not padded", chunk)
+            }
+            Warning::TrailingData(len) => write!(f, "{} bytes of trailing data", len),
+            Warning::Truncated {
+                declared,
+                available,
+            } => write!(
+                f,
+                "file truncated, {} of {} payload bytes available",
+                available, declared
+            ),
+            Warning::LossyAtom { index, offset } => write!(
+                f,
+                "atom {} at offset {} is not valid UTF-8, decoded lossily",
+                index, offset
+            ),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use std::io::Cursor;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{BeamFile, ExpTChunk};
+
+/// A BEAM file parsed from bytes, for use from JavaScript
+#[wasm_bindgen(js_name = BeamFile)]
+pub struct WasmBeamFile {
+    file: BeamFile<Cursor<Vec<u8>>>,
+    atoms: Vec<String>,
+}
+
+/// An exported function, see `Export`
+#[wasm_bindgen(getter_with_clone, js_name = Export)]
+pub struct JsExport {
+    pub name: String,
+    pub arity: u32,
+    pub label: u32,
+}
+
+/// A chunk with its offset and size in bytes
+#[wasm_bindgen(getter_with_clone, js_name = Chunk)]
+pub struct JsChunk {
+    pub id: String,
+    pub offset: u32,
+    pub size: u32,
+}
+
+#[wasm_bindgen(js_class = BeamFile)]
+impl WasmBeamFile {
+    /// Parses the file and its atom table
+    #[wasm_bindgen(constructor)]
+    pub fn new(bytes: &[u8]) -> Result<WasmBeamFile, JsError> {
+        let mut file = BeamFile::from_bytes(bytes.to_vec())?;
+        let atoms = file.owned_atoms()?;
+        Ok(WasmBeamFile { file, atoms })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> Option<String> {
+        self.atoms.first().cloned()
+    }
+
+    pub fn atoms(&self) -> Vec<String> {
+        self.atoms.clone()
+    }
+
+    pub fn exports(&mut self) -> Result<Vec<JsExport>, JsError> {
+        let chunk = self
+            .file
+            .read_with_atoms::<ExpTChunk<String>>(&self.atoms)?;
+        Ok(chunk
+            .exports
+            .into_iter()
+            .map(|export| JsExport {
+                name: export.function,
+                arity: export.arity,
+                label: export.label,
+            })
+            .collect())
+    }
+
+    /// Chunks in the order they appear in the file
+    pub fn chunks(&self) -> Vec<JsChunk> {
+        self.file
+            .chunks()
+            .map(|(id, offset, size)| JsChunk {
+                id: id.to_string(),
+                offset: offset as u32,
+                size: size as u32,
+            })
+            .collect()
+    }
+}
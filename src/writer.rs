@@ -0,0 +1,197 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::{metrics::metrics, BeamFile, BeamFileError, Id, IndexEntry, InternerMut, Result};
+
+/// Chunks needed to load a module, kept by `BeamFile::strip`
+///
+/// The same set `beam_lib:strip/1` keeps, as of OTP 25.
+const SIGNIFICANT_CHUNKS: [Id; 11] = [
+    Id::ATOM,
+    Id::AT_U8,
+    Id::CODE,
+    Id::STR_T,
+    Id::IMP_T,
+    Id::EXP_T,
+    Id::FUN_T,
+    Id::LIT_T,
+    Id::META,
+    Id::LINE,
+    Id::TYPE,
+];
+
+/// Writes a BEAM file containing `chunks` in the given order
+pub fn write_beam<'a, W: Write>(
+    mut writer: W,
+    chunks: impl IntoIterator<Item = (Id, &'a [u8])>,
+) -> Result<()> {
+    let chunks: Vec<_> = chunks.into_iter().collect();
+    write_form_header(
+        &mut writer,
+        chunks.iter().map(|(_, data)| data.len() as u64),
+    )?;
+    for (id, data) in chunks {
+        write_chunk_header(&mut writer, id, data.len() as u64)?;
+        writer.write_all(data)?;
+        write_padding(&mut writer, data.len() as u64)?;
+    }
+    Ok(())
+}
+
+/// Writes the `FOR1` header for chunks of the given lengths
+fn write_form_header<W: Write>(writer: &mut W, lens: impl Iterator<Item = u64>) -> Result<u64> {
+    let size = lens.map(|len| 8 + padded_len(len)).sum::<u64>() + 4;
+    if size > u32::MAX as u64 {
+        return Err(BeamFileError::LimitExceeded {
+            limit: "Total chunk size",
+            value: size,
+            max: u32::MAX as u64,
+        });
+    }
+    writer.write_all(b"FOR1")?;
+    writer.write_all(&(size as u32).to_be_bytes())?;
+    writer.write_all(b"BEAM")?;
+    Ok(8 + size)
+}
+
+fn write_chunk_header<W: Write>(writer: &mut W, id: Id, len: u64) -> Result<()> {
+    if len > u32::MAX as u64 {
+        return Err(BeamFileError::LimitExceeded {
+            limit: "Chunk size",
+            value: len,
+            max: u32::MAX as u64,
+        });
+    }
+    writer.write_all(&id.0)?;
+    writer.write_all(&(len as u32).to_be_bytes())?;
+    Ok(())
+}
+
+fn write_padding<W: Write>(writer: &mut W, len: u64) -> io::Result<()> {
+    writer.write_all(&[0; 3][..(padded_len(len) - len) as usize])
+}
+
+fn padded_len(len: u64) -> u64 {
+    (len + 3) & !3
+}
+
+/// Contents of a chunk written by a `Rewrite`
+enum Content {
+    /// Copied from the original file
+    Copy(IndexEntry),
+    Replace(Vec<u8>),
+}
+
+impl Content {
+    fn len(&self) -> u64 {
+        match self {
+            Content::Copy(entry) => entry.len,
+            Content::Replace(data) => data.len() as u64,
+        }
+    }
+}
+
+/// Writes a modified copy of a file, see `BeamFile::rewrite`
+///
+/// Unchanged chunks are copied from the original reader through a small
+/// buffer, so only replaced chunks are held in memory.
+pub struct Rewrite<'f, R, I: InternerMut> {
+    file: &'f mut BeamFile<R, I>,
+    chunks: Vec<(Id, Content)>,
+}
+
+impl<R: Read + Seek, I: InternerMut> Rewrite<'_, R, I> {
+    /// Leaves out a chunk
+    pub fn remove(&mut self, id: Id) -> &mut Self {
+        self.retain(|chunk| chunk != id)
+    }
+
+    /// Keeps only the chunks for which `keep` returns `true`
+    pub fn retain(&mut self, mut keep: impl FnMut(Id) -> bool) -> &mut Self {
+        self.chunks.retain(|(id, _)| keep(*id));
+        self
+    }
+
+    /// Replaces the contents of a chunk, adding it at the end if it's absent
+    pub fn replace(&mut self, id: Id, data: Vec<u8>) -> &mut Self {
+        match self.chunks.iter_mut().find(|(chunk, _)| *chunk == id) {
+            Some((_, content)) => *content = Content::Replace(data),
+            None => self.chunks.push((id, Content::Replace(data))),
+        }
+        self
+    }
+
+    /// Writes the file, returning the number of bytes written
+    ///
+    /// The original is read sequentially, one chunk at a time.
+    pub fn write_to<W: Write>(&mut self, mut writer: W) -> Result<u64> {
+        let size = write_form_header(
+            &mut writer,
+            self.chunks.iter().map(|(_, content)| content.len()),
+        )?;
+        let reader = &mut self.file.reader;
+        let path = self.file.path.as_deref();
+        for (id, content) in &self.chunks {
+            write_chunk_header(&mut writer, *id, content.len())?;
+            match content {
+                Content::Copy(entry) => {
+                    let copied = reader
+                        .seek(SeekFrom::Start(entry.position))
+                        .and_then(|_| io::copy(&mut reader.by_ref().take(entry.len), &mut writer))
+                        .map_err(|err| {
+                            BeamFileError::from(err)
+                                .in_chunk(*id, entry.position)
+                                .in_file(path)
+                        })?;
+                    if copied != entry.len {
+                        let err = io::Error::from(io::ErrorKind::UnexpectedEof);
+                        return Err(BeamFileError::from(err)
+                            .in_chunk(*id, entry.position + copied)
+                            .in_file(path));
+                    }
+                    metrics().bytes_read(entry.len);
+                }
+                Content::Replace(data) => writer.write_all(data)?,
+            }
+            write_padding(&mut writer, content.len())?;
+        }
+        Ok(size)
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Starts writing a modified copy of the file, with all chunks in file order
+    ///
+    /// Duplicated chunks are written once, as kept in the index.
+    pub fn rewrite(&mut self) -> Rewrite<'_, R, I> {
+        let mut entries: Vec<_> = self
+            .index
+            .iter()
+            .map(|(id, entry)| (*id, entry.clone()))
+            .collect();
+        entries.sort_by_key(|(_, entry)| entry.position);
+        let chunks = entries
+            .into_iter()
+            .map(|(id, entry)| (id, Content::Copy(entry)))
+            .collect();
+        Rewrite { file: self, chunks }
+    }
+
+    /// Encodes the file without the chunks not needed to load it
+    ///
+    /// Debug information, attributes and compile info are removed, unless
+    /// listed in `keep`. The remaining chunks stay in file order.
+    pub fn strip(&mut self, keep: &[Id]) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.strip_to(keep, &mut output)?;
+        Ok(output)
+    }
+
+    /// Like `strip`, streaming the result to `writer` instead of buffering it
+    ///
+    /// Returns the number of bytes written.
+    pub fn strip_to<W: Write>(&mut self, keep: &[Id], writer: W) -> Result<u64> {
+        self.rewrite()
+            .retain(|id| SIGNIFICANT_CHUNKS.contains(&id) || keep.contains(&id))
+            .write_to(writer)
+    }
+}
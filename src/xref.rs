@@ -0,0 +1,365 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    fs,
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+    slice,
+};
+
+use fxhash::FxHashSet;
+use thiserror::Error;
+
+use crate::{
+    literals::LiteralDigest, optional, BeamFile, BeamFileError, CodeChunk, DocCoverage,
+    DocCoverageReport, Import, Instruction, Interner, InternerMut, Resolver, Result, SharedLiteral,
+    SharedLiteralReport, Symbol, SymbolTable, Term,
+};
+
+/// A deprecation declared with the `-deprecated` attribute
+#[derive(Clone, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Deprecation {
+    /// `None` if all functions are deprecated
+    pub function: Option<String>,
+    /// `None` if all arities are deprecated
+    pub arity: Option<u32>,
+    pub description: Option<String>,
+}
+
+impl Deprecation {
+    fn matches(&self, function: &str, arity: u32) -> bool {
+        self.function.as_deref().is_none_or(|name| name == function)
+            && self.arity.is_none_or(|a| a == arity)
+    }
+
+    /// Parses the value of a `deprecated` attribute
+    fn parse(value: &Term) -> Vec<Deprecation> {
+        value
+            .as_list()
+            .unwrap_or(slice::from_ref(value))
+            .iter()
+            .filter_map(|element| match element {
+                Term::Atom(name) if name == "module" => Some(Deprecation {
+                    function: None,
+                    arity: None,
+                    description: None,
+                }),
+                Term::Tuple(fields) if fields.len() == 2 || fields.len() == 3 => {
+                    let function = match fields[0].as_atom()? {
+                        "_" => None,
+                        name => Some(name.to_string()),
+                    };
+                    let arity = match &fields[1] {
+                        Term::Integer(arity) => Some(u32::try_from(*arity).ok()?),
+                        term if term.as_atom() == Some("_") => None,
+                        _ => return None,
+                    };
+                    let description = fields.get(2).map(|description| match description {
+                        Term::Atom(name) => name.clone(),
+                        Term::String(text) => String::from_utf8_lossy(text).into_owned(),
+                        term => term.to_string(),
+                    });
+                    Some(Deprecation {
+                        function,
+                        arity,
+                        description,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl<R: Read + Seek, I: InternerMut> BeamFile<R, I> {
+    /// Imports that no instruction of the `Code` chunk calls
+    ///
+    /// These are usually left over from code removed by optimizations or
+    /// macro expansion. Imports are returned in the order of the import table.
+    pub fn unused_imports(&mut self) -> Result<Vec<Import<String>>> {
+        let atoms = self.owned_atoms()?;
+        let imports = self.owned_imports(&atoms)?;
+        if imports.is_empty() {
+            return Ok(imports);
+        }
+        let code = self.read_with_atoms::<CodeChunk<String>>(&atoms)?;
+
+        let mut called = vec![false; imports.len()];
+        for index in code.instructions.iter().filter_map(Instruction::import) {
+            if let Some(called) = called.get_mut(index as usize) {
+                *called = true;
+            }
+        }
+        Ok(imports
+            .into_iter()
+            .zip(called)
+            .filter_map(|(import, called)| (!called).then_some(import))
+            .collect())
+    }
+}
+
+/// A problem found by the `BeamSet` analyses
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum XrefFinding {
+    #[error("{caller} calls undefined function {module}:{function}/{arity}")]
+    UndefinedCall {
+        caller: String,
+        module: String,
+        function: String,
+        arity: u32,
+    },
+
+    #[error(
+        "{caller} calls deprecated function {module}:{function}/{arity}{}",
+        .description.as_ref().map_or(String::new(), |d| format!(": {}", d))
+    )]
+    DeprecatedCall {
+        caller: String,
+        module: String,
+        function: String,
+        arity: u32,
+        description: Option<String>,
+    },
+
+    #[error("module {module} is defined more than once, in {}", .paths.join(", "))]
+    DuplicateModule {
+        module: String,
+        /// Paths of every copy, the analysed one first, or `<unknown>` for
+        /// modules not read from a file
+        paths: Vec<String>,
+    },
+}
+
+#[derive(Debug)]
+struct Module {
+    path: Option<PathBuf>,
+    exports: FxHashSet<(Symbol, u32)>,
+    imports: Vec<Import<Symbol>>,
+    deprecated: Vec<Deprecation>,
+    doc_coverage: DocCoverage,
+    literals: Vec<LiteralDigest>,
+}
+
+/// A set of modules analysed together, like an application or a release
+///
+/// Calls are resolved through the import tables, so findings are reported
+/// per calling module. Calls to modules outside of the set are not checked.
+///
+/// Atoms of all the modules are interned in a single `SymbolTable`, so calls
+/// are matched with exports by comparing symbols.
+#[derive(Debug, Default)]
+pub struct BeamSet {
+    symbols: SymbolTable,
+    modules: BTreeMap<String, Module>,
+    /// Paths of every copy of the modules added more than once
+    duplicates: BTreeMap<String, Vec<Option<PathBuf>>>,
+}
+
+impl BeamSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a module
+    ///
+    /// Like the code server, only the first module of a name is kept, the
+    /// others are reported by `duplicate_modules`. Fails if the module has
+    /// no name, with an empty atom table.
+    pub fn add<R: Read + Seek, I: InternerMut>(&mut self, file: &mut BeamFile<R, I>) -> Result<()> {
+        let atoms = file.owned_atoms()?;
+        let name = match atoms.first() {
+            Some(name) => name.clone(),
+            None => {
+                let err = BeamFileError::MalformedChunk {
+                    chunk: file.atom_chunk_id(),
+                    reason: "no module name",
+                };
+                return Err(err.in_file(file.path()));
+            }
+        };
+        if let Some(first) = self.modules.get(&name) {
+            self.duplicates
+                .entry(name)
+                .or_insert_with(|| vec![first.path.clone()])
+                .push(file.path().map(Path::to_path_buf));
+            return Ok(());
+        }
+        let exports = file.owned_exports(&atoms)?;
+        let imports = file
+            .owned_imports(&atoms)?
+            .iter()
+            .map(|import| Import {
+                module: self.symbols.intern(&import.module),
+                function: self.symbols.intern(&import.function),
+                arity: import.arity,
+            })
+            .collect();
+        let deprecated = optional(file.attributes())?
+            .map(|chunk| chunk.attributes)
+            .unwrap_or_default()
+            .iter()
+            .filter(|(key, _)| key == "deprecated")
+            .flat_map(|(_, value)| Deprecation::parse(value))
+            .collect();
+
+        let exports_by_symbol = exports
+            .iter()
+            .map(|export| (self.symbols.intern(&export.function), export.arity))
+            .collect();
+        let doc_coverage = file.doc_coverage_of(name.clone(), exports)?;
+        let literals = optional(file.literals())?.map_or_else(Vec::new, |chunk| chunk.digests());
+
+        let module = Module {
+            path: file.path().map(Path::to_path_buf),
+            exports: exports_by_symbol,
+            imports,
+            deprecated,
+            doc_coverage,
+            literals,
+        };
+        self.modules.insert(name, module);
+        Ok(())
+    }
+
+    /// Adds all `.beam` files in a directory, not descending into subdirectories
+    pub fn add_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+        let mut paths = fs::read_dir(dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<std::io::Result<Vec<_>>>()
+            })
+            .map_err(|err| BeamFileError::from(err).in_file(Some(dir)))?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "beam"));
+        paths.sort();
+
+        for path in paths {
+            self.add(&mut BeamFile::from_file(path)?)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the modules in the set
+    pub fn modules(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    /// Path a module was loaded from
+    pub fn path(&self, module: &str) -> Option<&Path> {
+        self.modules.get(module)?.path.as_deref()
+    }
+
+    /// Modules added more than once, see `add`
+    pub fn duplicate_modules(&self) -> Vec<XrefFinding> {
+        self.duplicates
+            .iter()
+            .map(|(module, paths)| XrefFinding::DuplicateModule {
+                module: module.clone(),
+                paths: paths
+                    .iter()
+                    .map(|path| match path {
+                        Some(path) => path.display().to_string(),
+                        None => "<unknown>".to_string(),
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// Calls to functions not exported by modules in the set
+    pub fn undefined_calls(&self) -> Vec<XrefFinding> {
+        self.calls()
+            .filter_map(|(caller, import, target)| {
+                match target.exports.contains(&(import.function, import.arity)) {
+                    true => None,
+                    false => Some(XrefFinding::UndefinedCall {
+                        caller: caller.clone(),
+                        module: self.symbols.resolve(&import.module).to_string(),
+                        function: self.symbols.resolve(&import.function).to_string(),
+                        arity: import.arity,
+                    }),
+                }
+            })
+            .collect()
+    }
+
+    /// Calls to functions deprecated by modules in the set
+    pub fn deprecated_calls(&self) -> Vec<XrefFinding> {
+        self.calls()
+            .filter_map(|(caller, import, target)| {
+                let function = self.symbols.resolve(&import.function);
+                let deprecation = target
+                    .deprecated
+                    .iter()
+                    .find(|deprecation| deprecation.matches(function, import.arity))?;
+                Some(XrefFinding::DeprecatedCall {
+                    caller: caller.clone(),
+                    module: self.symbols.resolve(&import.module).to_string(),
+                    function: function.to_string(),
+                    arity: import.arity,
+                    description: deprecation.description.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Documentation coverage of every module, in the order of their names
+    pub fn doc_coverage(&self) -> DocCoverageReport {
+        DocCoverageReport {
+            modules: self
+                .modules
+                .values()
+                .map(|module| module.doc_coverage.clone())
+                .collect(),
+        }
+    }
+
+    /// Literals of at least `min_size` bytes embedded in more than one module
+    ///
+    /// Literals are compared by a 64-bit fingerprint of their text.
+    /// Large maps or binaries built by macros are the usual culprits.
+    pub fn shared_literals(&self, min_size: u32) -> SharedLiteralReport {
+        let mut shared: HashMap<_, SharedLiteral> = HashMap::new();
+        for (name, module) in &self.modules {
+            for literal in module
+                .literals
+                .iter()
+                .filter(|literal| literal.size >= min_size)
+            {
+                shared
+                    .entry(literal.fingerprint)
+                    .or_insert_with(|| SharedLiteral {
+                        fingerprint: literal.fingerprint,
+                        size: literal.size,
+                        modules: Vec::new(),
+                        preview: literal.preview.clone(),
+                    })
+                    .modules
+                    .push(name.clone());
+            }
+        }
+        let mut literals: Vec<_> = shared
+            .into_values()
+            .filter(|literal| literal.modules.len() > 1)
+            .collect();
+        literals.sort_by(|a, b| {
+            b.wasted()
+                .cmp(&a.wasted())
+                .then(a.fingerprint.0.cmp(&b.fingerprint.0))
+        });
+        SharedLiteralReport { literals }
+    }
+
+    /// Imports of every module, along with the imported module if it's in the set
+    fn calls(&self) -> impl Iterator<Item = (&String, &Import<Symbol>, &Module)> {
+        self.modules.iter().flat_map(move |(caller, module)| {
+            module.imports.iter().filter_map(move |import| {
+                let target = self.modules.get(self.symbols.resolve(&import.module))?;
+                Some((caller, import, target))
+            })
+        })
+    }
+}
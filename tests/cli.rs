@@ -0,0 +1,150 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use fast_beam::{write_beam, Id};
+
+fn fast_beam(args: &[&str]) -> Option<i32> {
+    Command::new(env!("CARGO_BIN_EXE_fast-beam"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+}
+
+/// An empty directory for the files of one test
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn xref_exit_status() {
+    assert_eq!(fast_beam(&["xref", "fixtures"]), Some(0));
+
+    // The fixture calls `erlang:get_module_info/1,2`, which this module lacks
+    let dir = scratch_dir("xref_exit_status");
+    fs::copy("fixtures/test.beam", dir.join("test.beam")).unwrap();
+    let mut erlang = Vec::new();
+    write_beam(&mut erlang, vec![(Id::AT_U8, &b"\0\0\0\x01\x06erlang"[..])]).unwrap();
+    fs::write(dir.join("erlang.beam"), erlang).unwrap();
+    assert_eq!(fast_beam(&["xref", dir.to_str().unwrap()]), Some(1));
+
+    fs::write(dir.join("broken.beam"), b"FOR1").unwrap();
+    assert_eq!(fast_beam(&["xref", dir.to_str().unwrap()]), Some(2));
+    assert_eq!(fast_beam(&["xref", "fixtures/missing"]), Some(2));
+}
+
+#[test]
+fn diff_exit_status() {
+    assert_eq!(
+        fast_beam(&["diff", "fixtures/test.beam", "fixtures/test.beam"]),
+        Some(0)
+    );
+
+    let dir = scratch_dir("diff_exit_status");
+    let stripped = dir.join("test.beam");
+    let out = dir.to_str().unwrap();
+    assert_eq!(
+        fast_beam(&["strip", "fixtures/test.beam", "--out", out]),
+        Some(0)
+    );
+    let stripped = stripped.to_str().unwrap();
+    assert_eq!(
+        fast_beam(&["diff", "fixtures/test.beam", stripped]),
+        Some(1)
+    );
+    assert_eq!(
+        fast_beam(&["diff", "fixtures/test.beam", "fixtures/missing.beam"]),
+        Some(2)
+    );
+}
+
+#[test]
+fn strip_errors() {
+    let dir = scratch_dir("strip_errors");
+    let out = dir.join("missing").join("out");
+    assert_eq!(
+        fast_beam(&[
+            "strip",
+            "fixtures/test.beam",
+            "--out",
+            out.to_str().unwrap()
+        ]),
+        Some(0)
+    );
+    assert!(out.join("test.beam").is_file());
+
+    // Both inputs are named test.beam, so neither is written
+    let other = dir.join("other");
+    fs::create_dir(&other).unwrap();
+    fs::copy("fixtures/test.beam", other.join("test.beam")).unwrap();
+    let collide = dir.join("collide");
+    let copy = other.join("test.beam");
+    let output = Command::new(env!("CARGO_BIN_EXE_fast-beam"))
+        .args(["strip", "fixtures/test.beam", copy.to_str().unwrap()])
+        .args(["--out", collide.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("would both be written to"));
+    assert_eq!(fs::read_dir(&collide).unwrap().count(), 0);
+
+    // The target can't be replaced, which leaves no partial file behind
+    let blocked = dir.join("blocked");
+    fs::create_dir_all(blocked.join("test.beam")).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_fast-beam"))
+        .args([
+            "strip",
+            "fixtures/test.beam",
+            "--out",
+            blocked.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let target = blocked.join("test.beam");
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&*target.to_string_lossy()));
+    assert!(!blocked.join("test.beam.partial").exists());
+}
+
+#[test]
+fn decompile_exit_status() {
+    assert_eq!(fast_beam(&["decompile", "fixtures/test.beam"]), Some(0));
+
+    let dir = scratch_dir("decompile_exit_status");
+    let out = dir.to_str().unwrap();
+    assert_eq!(
+        fast_beam(&["strip", "fixtures/test.beam", "--out", out]),
+        Some(0)
+    );
+    let stripped = dir.join("test.beam");
+    assert_eq!(
+        fast_beam(&["decompile", stripped.to_str().unwrap()]),
+        Some(1)
+    );
+
+    let mut no_debug_info = Vec::new();
+    write_beam(
+        &mut no_debug_info,
+        vec![(Id::AT_U8, &b"\0\0\0\x01\x01m"[..])],
+    )
+    .unwrap();
+    let no_debug_info_path = dir.join("m.beam");
+    fs::write(&no_debug_info_path, no_debug_info).unwrap();
+    assert_eq!(
+        fast_beam(&["decompile", no_debug_info_path.to_str().unwrap()]),
+        Some(1)
+    );
+
+    let broken = dir.join("broken.beam");
+    fs::write(&broken, b"FOR1").unwrap();
+    assert_eq!(fast_beam(&["decompile", broken.to_str().unwrap()]), Some(2));
+}